@@ -0,0 +1,11 @@
+// dymod embeds its own rustc version the same way a subcrate does, to
+// compare the two when a dylib is loaded
+#[allow(dead_code, clippy::needless_doctest_main)]
+#[path = "src/build.rs"]
+mod build;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/build.rs");
+    build::embed_rustc_version();
+}
@@ -0,0 +1,104 @@
+//! The game logic of the `state_migration` example, which is
+//! hotswapped while the example runs.
+//!
+//! Everything the game keeps between frames is in `GameState`. The
+//! host copies it out before this dylib is unloaded, and back into the
+//! new one once it's loaded.
+
+use std::sync::Mutex;
+
+dymod::abi_magic!();
+
+/// Changed whenever the fields of `GameState` are, so that a state
+/// saved by an incompatible version is ignored rather than misread.
+const STATE_VERSION: u32 = 1;
+
+/// The whole state of the game.
+///
+/// It's `#[repr(C)]`, without any padding, and only holds plain data,
+/// so that its bytes mean the same thing to every version of this
+/// dylib with the same `STATE_VERSION`. A pointer, or anything which
+/// owns one like a `String`, would point into memory the old dylib
+/// may have freed.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct GameState {
+    version: u32,
+    score: u32,
+    frame: u64,
+    player_x: f32,
+    player_speed: f32,
+}
+
+/// Lost whenever the dylib is unloaded, unless the host saves it.
+static STATE: Mutex<GameState> = Mutex::new(GameState {
+    version: STATE_VERSION,
+    score: 0,
+    frame: 0,
+    player_x: 0.0,
+    player_speed: 1.5,
+});
+
+/// Moves the player along, scoring a point each time they reach the
+/// end of the track.
+#[no_mangle]
+pub extern "C" fn update() {
+    let mut state = STATE.lock().unwrap();
+    state.frame += 1;
+    state.player_x += state.player_speed;
+    if state.player_x >= 10.0 {
+        state.player_x -= 10.0;
+        state.score += 1;
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn frame() -> u64 {
+    STATE.lock().unwrap().frame
+}
+
+#[no_mangle]
+pub extern "C" fn score() -> u32 {
+    STATE.lock().unwrap().score
+}
+
+#[no_mangle]
+pub extern "C" fn player_x() -> f32 {
+    STATE.lock().unwrap().player_x
+}
+
+dymod::ffi_safe! {
+    /// Copies the state into `buffer`, which the host owns, so that it
+    /// outlives this dylib. Returns how many bytes were written, or 0
+    /// if it doesn't fit.
+    pub fn save_state(buffer: &mut [u8]) -> usize {
+        let state = *STATE.lock().unwrap();
+        let len = std::mem::size_of::<GameState>();
+        if buffer.len() < len {
+            return 0;
+        }
+
+        // `GameState` has no padding, so all of its bytes are initialized
+        let bytes =
+            unsafe { std::slice::from_raw_parts(&state as *const GameState as *const u8, len) };
+        buffer[..len].copy_from_slice(bytes);
+        len
+    }
+
+    /// Replaces the state with one saved by `save_state()`, possibly by
+    /// the previous version of this dylib. Returns whether it could.
+    pub fn load_state(saved: &[u8]) -> bool {
+        if saved.len() != std::mem::size_of::<GameState>() {
+            return false;
+        }
+
+        // The host's buffer isn't necessarily aligned for `GameState`
+        let state = unsafe { std::ptr::read_unaligned(saved.as_ptr() as *const GameState) };
+        if state.version != STATE_VERSION {
+            return false;
+        }
+
+        *STATE.lock().unwrap() = state;
+        true
+    }
+}
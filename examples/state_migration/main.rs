@@ -0,0 +1,125 @@
+//! Hotswaps the game logic in `game/` while a small game loop runs,
+//! keeping the game's state across each reload with reload hooks.
+//!
+//! Run it with `cargo run --example state_migration`. It reloads the
+//! game every few seconds whether or not it's changed, to show the
+//! state surviving it. While it runs, try changing how `update()`
+//! moves the player in `game/src/lib.rs`, then run `cargo build` in
+//! `game/`: the next frame is drawn by the new code, carrying on from
+//! where the old one left off.
+//!
+//! In release mode, or with the `force-static` feature, the game is
+//! linked statically, and none of this is needed.
+
+use std::time::Duration;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "game/src/lib.rs"]
+    #[dylib = "{manifest}/examples/state_migration/{name}/target/debug/{prefix}{name}{suffix}"]
+    #[ffi_safe]
+    pub mod game {
+        fn update();
+        fn frame() -> u64;
+        fn score() -> u32;
+        fn player_x() -> f32;
+        fn save_state(buffer: &mut [u8]) -> usize;
+        fn load_state(saved: &[u8]) -> bool;
+    }
+}
+
+const FRAMES: u64 = 120;
+
+/// Everything needed to hotswap the game, which is only dynamically
+/// linked in debug mode, or with the `force-dynamic` feature, unless
+/// the `force-static` or `force-stub` feature is on.
+#[cfg(all(
+    not(any(feature = "force-static", feature = "force-stub")),
+    any(feature = "force-dynamic", debug_assertions)
+))]
+mod hotswap {
+    use std::sync::Mutex;
+
+    use super::game;
+
+    pub const FRAMES_PER_RELOAD: u64 = 40;
+
+    /// The game's state while it's being reloaded.
+    ///
+    /// This is a copy in memory the host owns, rather than anything the
+    /// old dylib returned, as that's unloaded before it's restored.
+    static SAVED_STATE: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+    /// Called while the old dylib is still loaded.
+    fn save_state() {
+        let mut saved = SAVED_STATE.lock().unwrap();
+        saved.resize(256, 0);
+        let len = game::save_state(&mut saved);
+        saved.truncate(len);
+    }
+
+    /// Called once the new dylib is loaded, before anything else uses
+    /// it.
+    fn restore_state() {
+        let saved = SAVED_STATE.lock().unwrap();
+        if !game::load_state(&saved) {
+            println!("The new game can't read the old game's state, so it starts over");
+        }
+    }
+
+    /// Builds the game's dylib, as running the example only builds the
+    /// host, then registers the hooks which keep its state.
+    pub fn start() {
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let status = std::process::Command::new(cargo)
+            .arg("build")
+            .current_dir(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/examples/state_migration/game"
+            ))
+            .status()
+            .expect("Failed to run cargo");
+        assert!(status.success(), "Failed to build the game");
+
+        game::on_before_reload(save_state);
+        game::on_reload(restore_state);
+    }
+
+    /// Reloads the game, even if it hasn't changed.
+    pub fn reload() {
+        game::force_reload().expect("Failed to reload the game");
+        println!("Reloaded the game at frame {}", game::frame());
+    }
+}
+
+fn main() {
+    #[cfg(all(
+        not(any(feature = "force-static", feature = "force-stub")),
+        any(feature = "force-dynamic", debug_assertions)
+    ))]
+    hotswap::start();
+
+    for frame in 1..=FRAMES {
+        game::update();
+
+        if frame % 10 == 0 {
+            println!(
+                "frame {:>3}: player at {:>4.1}, score {}",
+                game::frame(),
+                game::player_x(),
+                game::score()
+            );
+        }
+
+        #[cfg(all(
+            not(any(feature = "force-static", feature = "force-stub")),
+            any(feature = "force-dynamic", debug_assertions)
+        ))]
+        if frame % hotswap::FRAMES_PER_RELOAD == 0 {
+            hotswap::reload();
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,353 @@
+//! Helpers for the build script of a crate using dymod.
+//!
+//! These are meant to be called from `build.rs`, with dymod added as
+//! a build dependency:
+//!
+//! ```toml
+//! [build-dependencies]
+//! dymod = "0.4"
+//! ```
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Checks that the subcrate in `subcrate_dir` is built as a `dylib` or
+/// `cdylib`, printing a cargo warning if it isn't.
+///
+/// Without this, a missing `crate-type` only shows up at runtime,
+/// when the dylib can't be found.
+///
+/// ```rust,no_run
+/// // build.rs
+/// fn main() {
+///     dymod::build::check_crate_type("subcrate");
+/// }
+/// ```
+pub fn check_crate_type(subcrate_dir: impl AsRef<Path>) {
+    let manifest_path = subcrate_dir.as_ref().join("Cargo.toml");
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+
+    let manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            println!(
+                "cargo:warning=dymod: could not read {}: {}",
+                manifest_path.display(),
+                error
+            );
+            return;
+        }
+    };
+
+    let crate_types = crate_types(&manifest);
+    if !crate_types
+        .iter()
+        .any(|crate_type| crate_type == "dylib" || crate_type == "cdylib")
+    {
+        println!(
+            "cargo:warning=dymod: {} doesn't build a dylib, so it can't be hotswapped. \
+             Add `crate-type = [\"dylib\"]` to its `[lib]` section.",
+            manifest_path.display()
+        );
+    }
+}
+
+/// Creates a subcrate in `subcrate_dir`, laid out the way dymod
+/// expects, with a `Cargo.toml` which builds a `dylib` and a
+/// `src/lib.rs` containing an example function.
+///
+/// The crate is named after the directory, which should match the
+/// name of the module in the `dymod!` block. Files which already exist
+/// are left alone, so this is safe to call on every build.
+///
+/// ```rust,no_run
+/// // build.rs
+/// fn main() {
+///     dymod::build::scaffold("subcrate").expect("Failed to create subcrate");
+///     dymod::build::check_crate_type("subcrate");
+/// }
+/// ```
+pub fn scaffold(subcrate_dir: impl AsRef<Path>) -> std::io::Result<()> {
+    let subcrate_dir = subcrate_dir.as_ref();
+    let name = subcrate_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} isn't a valid crate name", subcrate_dir.display()),
+            )
+        })?;
+
+    std::fs::create_dir_all(subcrate_dir.join("src"))?;
+
+    create_if_missing(
+        &subcrate_dir.join("Cargo.toml"),
+        &format!(
+            r#"[package]
+name = "{}"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+crate-type = ["dylib"]
+"#,
+            name
+        ),
+    )?;
+
+    create_if_missing(
+        &subcrate_dir.join("src").join("lib.rs"),
+        r#"// Functions to hotswap must be `pub extern "C"` and `#[no_mangle]`,
+// and declared in the `dymod!` block
+#[no_mangle]
+pub extern "C" fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+"#,
+    )
+}
+
+/// Embeds the version of rustc building the subcrate, so that dymod
+/// can warn when the dylib was built by a different version than the
+/// crate loading it.
+///
+/// Types which aren't `#[repr(C)]` may be laid out differently by two
+/// versions of rustc, so passing them between the two is undefined
+/// behaviour. The version is exported by
+/// [`abi_magic!()`](crate::abi_magic), which the subcrate must also use.
+///
+/// ```rust,no_run
+/// // subcrate/build.rs
+/// fn main() {
+///     dymod::build::embed_rustc_version();
+/// }
+/// ```
+pub fn embed_rustc_version() {
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    match Command::new(&rustc).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=DYMOD_RUSTC_VERSION={}", version.trim());
+        }
+        _ => println!(
+            "cargo:warning=dymod: could not run `{} --version`, so the rustc version can't be checked",
+            Path::new(&rustc).display()
+        ),
+    }
+}
+
+/// Checks that the host and the subcrate in `subcrate_dir` depend on
+/// the same version of the crate `name`, printing a cargo warning if
+/// they don't.
+///
+/// The types passed between the two are best defined once, in a crate
+/// both depend on. If each is built with a different version of it,
+/// those types can still differ, which is undefined behaviour, so
+/// call this in the host's `build.rs` with the name of that crate.
+///
+/// The versions are read from each crate's `Cargo.lock`, or that of
+/// the workspace it's in, so the subcrate must have been built once.
+///
+/// ```rust,no_run
+/// // build.rs
+/// fn main() {
+///     dymod::build::check_shared_dependency("subcrate", "game_types");
+/// }
+/// ```
+pub fn check_shared_dependency(subcrate_dir: impl AsRef<Path>, name: &str) {
+    let host_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let host = match read_locked_versions(&host_dir, name) {
+        Some(versions) => versions,
+        None => return,
+    };
+    let subcrate = match read_locked_versions(subcrate_dir.as_ref(), name) {
+        Some(versions) => versions,
+        None => return,
+    };
+
+    let (host_lockfile, host_versions) = host;
+    let (subcrate_lockfile, subcrate_versions) = subcrate;
+    for (lockfile, versions) in [
+        (&host_lockfile, &host_versions),
+        (&subcrate_lockfile, &subcrate_versions),
+    ] {
+        if versions.is_empty() {
+            println!(
+                "cargo:warning=dymod: {} doesn't lock any version of `{}`, \
+                 so its types can't be shared. Add it as a dependency.",
+                lockfile.display(),
+                name
+            );
+            return;
+        }
+    }
+
+    if host_versions != subcrate_versions {
+        println!(
+            "cargo:warning=dymod: the host uses `{}` {}, but {} uses {}. \
+             Depend on the same version, so their types have the same layout.",
+            name,
+            host_versions.join(", "),
+            subcrate_dir.as_ref().display(),
+            subcrate_versions.join(", ")
+        );
+    }
+}
+
+/// Finds the `Cargo.lock` for the crate in `dir`, returning it along
+/// with the versions of `name` it locks, or printing a cargo warning
+/// if it can't be read.
+fn read_locked_versions(dir: &Path, name: &str) -> Option<(PathBuf, Vec<String>)> {
+    let lockfile = std::fs::canonicalize(dir).ok().and_then(|dir| {
+        dir.ancestors()
+            .map(|dir| dir.join("Cargo.lock"))
+            .find(|lockfile| lockfile.exists())
+    });
+    let lockfile = match lockfile {
+        Some(lockfile) => lockfile,
+        None => {
+            println!(
+                "cargo:warning=dymod: could not find a Cargo.lock for {}, \
+                 so its version of `{}` can't be checked. Build it first.",
+                dir.display(),
+                name
+            );
+            return None;
+        }
+    };
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+
+    match std::fs::read_to_string(&lockfile) {
+        Ok(contents) => Some((lockfile, locked_versions(&contents, name))),
+        Err(error) => {
+            println!(
+                "cargo:warning=dymod: could not read {}: {}",
+                lockfile.display(),
+                error
+            );
+            None
+        }
+    }
+}
+
+fn create_if_missing(path: &Path, contents: &str) -> std::io::Result<()> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => file.write_all(contents.as_bytes()),
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns the crate types of the `[lib]` target in the contents of a
+/// Cargo.toml file.
+///
+/// This only understands the usual ways of writing the `crate-type`
+/// key, not every valid TOML document.
+///
+/// ```rust
+/// let manifest = r#"
+/// [lib]
+/// crate-type = ["dylib", "rlib"]
+/// "#;
+///
+/// assert_eq!(dymod::build::crate_types(manifest), ["dylib", "rlib"]);
+/// ```
+pub fn crate_types(manifest: &str) -> Vec<String> {
+    let mut in_lib = false;
+    let mut lines = manifest.lines();
+
+    while let Some(line) = lines.next() {
+        let line = strip_comment(line).trim();
+
+        if line.starts_with('[') {
+            in_lib = line == "[lib]";
+            continue;
+        }
+
+        let key_and_value = if in_lib {
+            line.split_once('=')
+        } else {
+            line.strip_prefix("lib.")
+                .and_then(|line| line.split_once('='))
+        };
+
+        if let Some((key, value)) = key_and_value {
+            let key = key.trim();
+            if key == "crate-type" || key == "crate_type" {
+                // The array may be split over several lines
+                let mut value = value.to_owned();
+                while !value.contains(']') {
+                    match lines.next() {
+                        Some(line) => value.push_str(strip_comment(line)),
+                        None => break,
+                    }
+                }
+
+                return value
+                    .split('"')
+                    .skip(1)
+                    .step_by(2)
+                    .map(str::to_owned)
+                    .collect();
+            }
+        }
+    }
+
+    Vec::new()
+}
+
+/// Returns the versions of the package `name` in the contents of a
+/// Cargo.lock file, sorted and without duplicates. There's more than
+/// one if different crates depend on incompatible versions of it.
+///
+/// ```rust
+/// let lockfile = r#"
+/// [[package]]
+/// name = "game_types"
+/// version = "0.2.1"
+///
+/// [[package]]
+/// name = "subcrate"
+/// version = "0.1.0"
+/// dependencies = [
+///  "game_types",
+/// ]
+/// "#;
+///
+/// assert_eq!(dymod::build::locked_versions(lockfile, "game_types"), ["0.2.1"]);
+/// ```
+pub fn locked_versions(lockfile: &str, name: &str) -> Vec<String> {
+    let mut versions = Vec::new();
+    let mut package = None;
+
+    for line in lockfile.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            package = None;
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"');
+            match key.trim() {
+                "name" => package = Some(value == name),
+                "version" if package == Some(true) => versions.push(value.to_owned()),
+                _ => {}
+            }
+        }
+    }
+
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or("")
+}
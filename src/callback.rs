@@ -0,0 +1,55 @@
+use core::ffi::c_void;
+
+/// Calls a closure passed by a `#[ffi_safe]` module as an
+/// `impl FnMut(...)` argument, for the dylib to call through a plain
+/// `extern "C"` function pointer.
+///
+/// The dylib is passed the function returned by `trampoline()`, then a
+/// pointer to the closure, which it passes back as the first argument
+/// of each call. `Args` is a tuple of the closure's argument types, and
+/// `R` is what it returns.
+pub trait Trampoline<Args, R> {
+    /// The type of the function pointer the dylib is passed.
+    type Fn: Copy;
+
+    /// Returns the function which calls this closure, given a pointer
+    /// to it.
+    fn trampoline(&self) -> Self::Fn;
+}
+
+macro_rules! impl_trampoline {
+    ($(($($arg: ident $argname: ident),*))*) => {
+        $(
+        impl<F, R, $($arg),*> Trampoline<($($arg,)*), R> for F
+        where
+            F: FnMut($($arg),*) -> R,
+        {
+            type Fn = extern "C" fn(*mut c_void, $($arg),*) -> R;
+
+            fn trampoline(&self) -> Self::Fn {
+                extern "C" fn call<F, R, $($arg),*>(closure: *mut c_void, $($argname: $arg),*) -> R
+                where
+                    F: FnMut($($arg),*) -> R,
+                {
+                    // The closure outlives the call it was passed to,
+                    // which is the only time the dylib may call this
+                    let closure = unsafe { &mut *(closure as *mut F) };
+                    closure($($argname),*)
+                }
+
+                call::<F, R, $($arg),*>
+            }
+        }
+        )*
+    };
+}
+
+impl_trampoline! {
+    ()
+    (A a)
+    (A a, B b)
+    (A a, B b, C c)
+    (A a, B b, C c, D d)
+    (A a, B b, C c, D d, E e)
+    (A a, B b, C c, D d, E e, G g)
+}
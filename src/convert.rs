@@ -0,0 +1,64 @@
+use core::ptr::NonNull;
+
+/// Converts the value returned by a statically linked function into the
+/// return type declared for it in `dymod!`.
+///
+/// The conversions are the same ones dynamic linking makes for free: a
+/// declared `Option<NonNull<T>>` is returned as a raw pointer, which is
+/// `None` when null.
+pub trait FromReturn<R> {
+    fn from_return(value: R) -> Self;
+}
+
+impl<T> FromReturn<T> for T {
+    #[inline(always)]
+    fn from_return(value: T) -> T {
+        value
+    }
+}
+
+impl<T> FromReturn<*mut T> for Option<NonNull<T>> {
+    #[inline(always)]
+    fn from_return(value: *mut T) -> Self {
+        NonNull::new(value)
+    }
+}
+
+impl<T> FromReturn<*const T> for Option<NonNull<T>> {
+    #[inline(always)]
+    fn from_return(value: *const T) -> Self {
+        NonNull::new(value as *mut T)
+    }
+}
+
+/// Turns the pointer and length of a slice passed by a `#[ffi_safe]`
+/// module back into a slice, for `ffi_safe!()`. A null pointer, as a C
+/// caller might pass with a length of 0, is an empty slice.
+///
+/// # Safety
+///
+/// Unless it's null, `ptr` must point to `len` valid values of `T`,
+/// which outlive `'a`.
+#[inline(always)]
+pub unsafe fn slice_from_abi<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if ptr.is_null() {
+        &[]
+    } else {
+        core::slice::from_raw_parts(ptr, len)
+    }
+}
+
+/// As `slice_from_abi()`, for a mutable slice.
+///
+/// # Safety
+///
+/// As for `slice_from_abi()`, and nothing else may access the values
+/// during `'a`.
+#[inline(always)]
+pub unsafe fn slice_from_abi_mut<'a, T>(ptr: *mut T, len: usize) -> &'a mut [T] {
+    if ptr.is_null() {
+        &mut []
+    } else {
+        core::slice::from_raw_parts_mut(ptr, len)
+    }
+}
@@ -0,0 +1,80 @@
+/// How a dymod module's dylib is copied before it's loaded, as set
+/// with its `set_copy_strategy()` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum CopyStrategy {
+    /// Use `Never` on Linux, where a dylib can be replaced while it's
+    /// loaded and there's no cache to get around, and `Versioned`
+    /// everywhere else, where copying is needed to get around the file
+    /// lock on Windows and the dyld cache on macOS.
+    #[default]
+    PlatformDefault,
+
+    /// Copy the dylib to a new path, next to the original, for each
+    /// version. This means the build can replace the original while a
+    /// copy is loaded, and that the previous version stays loaded if
+    /// the new one fails to load.
+    Versioned,
+
+    /// Load the first version of the dylib from where it was built,
+    /// and copy each one after it as with `Versioned`.
+    ///
+    /// Most runs never reload, so this usually writes nothing, while a
+    /// reload still keeps the previous version loaded until the new one
+    /// has loaded, and keeps it if the new one fails to load.
+    OnReload,
+
+    /// Load the dylib from where it was built, without writing
+    /// anything to its directory, for example when it's on a read-only
+    /// filesystem.
+    ///
+    /// The same path can't be loaded twice at once, so the old version
+    /// is unloaded before the new one is loaded. If the new one fails
+    /// to load, the module is left with no dylib loaded until the next
+    /// successful reload. Old versions can't be retained either.
+    ///
+    /// If the system won't unload the old version, such as a Rust dylib
+    /// with thread-local destructors under glibc, loading the same path
+    /// again returns it instead, so the reload succeeds with the old
+    /// code.
+    Never,
+
+    /// Move a new version of the dylib into place by renaming it,
+    /// rather than copying it, so a dylib which is still being written
+    /// is never loaded.
+    ///
+    /// The build must write each new version next to the original,
+    /// with `.new` added to its name, such as `libsubcrate.so.new`.
+    /// Once that file exists, the dylib counts as changed, and reloading
+    /// renames it to a new path for that version and loads it. Until
+    /// then, the first load copies the original as with `Versioned`.
+    AtomicRename,
+}
+
+impl CopyStrategy {
+    /// Returns the strategy `PlatformDefault` stands for on this
+    /// platform, or this one if it isn't `PlatformDefault`.
+    pub fn resolve(self) -> CopyStrategy {
+        match self {
+            CopyStrategy::PlatformDefault if cfg!(target_os = "linux") => CopyStrategy::Never,
+            CopyStrategy::PlatformDefault => CopyStrategy::Versioned,
+            strategy => strategy,
+        }
+    }
+}
+
+/// What happens when the copy of an old version of a dymod module's
+/// dylib can't be removed once the new one has loaded, as set with
+/// its `set_cleanup_policy()` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum CleanupPolicy {
+    /// Warn about it, and leave the copy where it is. The new version
+    /// stays loaded either way, as the old copy is no longer used.
+    #[default]
+    BestEffort,
+
+    /// Panic, after the new version has loaded, so that a copy which
+    /// can't be removed never goes unnoticed.
+    Strict,
+}
@@ -0,0 +1,2097 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{
+    Arc, Mutex, MutexGuard, Once, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+use std::thread::LocalKey;
+use std::time::{Duration, Instant, SystemTime};
+
+use libloading::{Library, Symbol};
+
+use crate::{
+    CleanupPolicy, CopyStrategy, DefaultLoader, DylibInfo, DymodEvent, DymodLoader, ModuleStatus,
+    ReloadError, RustcVersion, SymbolResolution,
+};
+
+mod arch;
+#[cfg(feature = "autobuild")]
+mod autobuild;
+pub mod diagnostics;
+mod diff;
+mod fs;
+mod memo;
+#[cfg(feature = "async")]
+mod nonblocking;
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(all(test, unix))]
+mod tests;
+
+pub use self::arch::dylib_arch;
+pub use self::memo::Memo;
+
+use self::fs::{FsOps, RealFs};
+
+/// Returns the path of the dylib built for the subcrate `modname`
+/// under `manifest_dir`.
+///
+/// The path is built component by component rather than as a string,
+/// so directories containing spaces or non-ASCII characters are fine.
+pub fn dylib_path(manifest_dir: &str, modname: &str) -> PathBuf {
+    profile_dylib_path(manifest_dir, modname, None, "debug")
+}
+
+/// Returns the path of the dylib built for the subcrate `modname`
+/// under `manifest_dir`, when it's built for the target `triple`, which
+/// cargo gives a directory of its own.
+pub fn target_dylib_path(manifest_dir: &str, modname: &str, triple: &str) -> PathBuf {
+    profile_dylib_path(manifest_dir, modname, Some(triple), "debug")
+}
+
+/// Returns the path of the dylib built for the subcrate `modname` under
+/// `manifest_dir` with the given cargo profile, for the target `triple`
+/// if given.
+fn profile_dylib_path(
+    manifest_dir: &str,
+    modname: &str,
+    triple: Option<&str>,
+    profile: &str,
+) -> PathBuf {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    let mut path = Path::new(manifest_dir).join(modname).join("target");
+    if let Some(triple) = triple {
+        path.push(triple);
+    }
+    path.join(profile)
+        .join(format!("{}{}{}", DLL_PREFIX, modname, DLL_SUFFIX))
+}
+
+/// Returns the path of the dylib for the subcrate `modname` next to the
+/// running executable, or in the working directory if that can't be
+/// found.
+pub fn exe_dylib_path(modname: &str) -> PathBuf {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    let exe = std::env::current_exe().ok();
+    let dir = exe
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(Path::new("."));
+    dir.join(format!("{}{}{}", DLL_PREFIX, modname, DLL_SUFFIX))
+}
+
+/// The placeholders a `#[dylib]` path template can use.
+const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "manifest",
+    "name",
+    "profile",
+    "prefix",
+    "suffix",
+    "target_dir",
+];
+
+/// Panics if `template` uses a placeholder other than the ones in
+/// `TEMPLATE_PLACEHOLDERS`, or leaves one unclosed. This is called in
+/// a const, so the mistake is caught at compile time.
+pub const fn check_dylib_template(template: &str) {
+    let bytes = template.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'}' {
+            panic!("unmatched `}}` in `#[dylib]` path template");
+        }
+        if bytes[i] != b'{' {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != b'}' {
+            end += 1;
+        }
+        if end == bytes.len() {
+            panic!("unclosed `{{` in `#[dylib]` path template");
+        }
+
+        let mut known = false;
+        let mut p = 0;
+        while p < TEMPLATE_PLACEHOLDERS.len() {
+            let placeholder = TEMPLATE_PLACEHOLDERS[p].as_bytes();
+            if placeholder.len() == end - start {
+                let mut matches = true;
+                let mut j = 0;
+                while j < placeholder.len() {
+                    if placeholder[j] != bytes[start + j] {
+                        matches = false;
+                    }
+                    j += 1;
+                }
+                known |= matches;
+            }
+            p += 1;
+        }
+        if !known {
+            panic!(
+                "unknown placeholder in `#[dylib]` path template, \
+                 expected one of {{manifest}}, {{name}}, {{profile}}, {{prefix}}, {{suffix}} or {{target_dir}}"
+            );
+        }
+        i = end + 1;
+    }
+}
+
+/// Returns the path of the dylib for the subcrate `modname`, by filling
+/// in the placeholders of a `#[dylib]` path template.
+///
+/// `{target_dir}` is `CARGO_TARGET_DIR` if it's set when the program
+/// runs, or the subcrate's own `target` directory otherwise.
+/// `{profile}` is `debug` or `release`, whichever the host was built
+/// with.
+pub fn template_dylib_path(template: &str, manifest_dir: &str, modname: &str) -> PathBuf {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    fill_dylib_template(template, manifest_dir, modname, profile)
+}
+
+/// Fills in a `#[dylib]` path template, with `profile` for `{profile}`.
+fn fill_dylib_template(
+    template: &str,
+    manifest_dir: &str,
+    modname: &str,
+    profile: &str,
+) -> PathBuf {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    let mut path = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        let end = start + rest[start..].find('}').expect("Unclosed placeholder.");
+        match &rest[start + 1..end] {
+            "manifest" => path.push_str(manifest_dir),
+            "name" => path.push_str(modname),
+            "profile" => path.push_str(profile),
+            "prefix" => path.push_str(DLL_PREFIX),
+            "suffix" => path.push_str(DLL_SUFFIX),
+            "target_dir" => match std::env::var("CARGO_TARGET_DIR") {
+                Ok(dir) => path.push_str(&dir),
+                Err(_) => {
+                    let dir = Path::new(manifest_dir).join(modname).join("target");
+                    path.push_str(&dir.to_string_lossy());
+                }
+            },
+            other => panic!("Unknown placeholder {{{}}}.", other),
+        }
+        rest = &rest[end + 1..];
+    }
+    path.push_str(rest);
+    path.into()
+}
+
+/// Runs `cargo build` in `subcrate_dir`, with `--release` if `release`
+/// is set and for the target `triple` if given, returning whether it
+/// succeeded along with everything it printed.
+fn cargo_build(
+    subcrate_dir: &Path,
+    release: bool,
+    triple: Option<&str>,
+) -> (Result<(), ReloadError>, String) {
+    // The same cargo as the one running the host, if it is
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = std::process::Command::new(cargo);
+    command.arg("build").current_dir(subcrate_dir);
+    if release {
+        command.arg("--release");
+    }
+    if let Some(triple) = triple {
+        command.args(["--target", triple]);
+    }
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(error) => {
+            let stderr = String::new();
+            return (
+                Err(ReloadError::BuildFailed {
+                    path: subcrate_dir.to_owned(),
+                    error,
+                    stderr,
+                }),
+                String::new(),
+            );
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    let combined = String::from_utf8_lossy(&output.stdout).into_owned() + &stderr;
+    if output.status.success() {
+        (Ok(()), combined)
+    } else {
+        let error = std::io::Error::other(format!("cargo build failed with {}", output.status));
+        let path = subcrate_dir.to_owned();
+        (
+            Err(ReloadError::BuildFailed {
+                path,
+                error,
+                stderr,
+            }),
+            combined,
+        )
+    }
+}
+
+/// Returns the path a new version of the dylib at `path` is written to
+/// by the build, with `CopyStrategy::AtomicRename`.
+pub fn new_dylib_path(path: &Path) -> PathBuf {
+    let mut new = path.as_os_str().to_owned();
+    new.push(".new");
+    new.into()
+}
+
+/// Every dynamic module which has been loaded, in the order they were
+/// first loaded.
+static REGISTRY: Mutex<Vec<&'static DynamicModule>> = Mutex::new(Vec::new());
+
+/// The instance number given to the next module to copy its dylib.
+static NEXT_INSTANCE: AtomicUsize = AtomicUsize::new(1);
+
+fn registry() -> MutexGuard<'static, Vec<&'static DynamicModule>> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Reloads every dynamic module which has been loaded, returning the
+/// first error if any of them fail.
+pub fn reload_all() -> Result<(), ReloadError> {
+    let modules = registry().clone();
+    let mut result = Ok(());
+    for module in modules {
+        let reloaded = module.force_reload();
+        if result.is_ok() {
+            result = reloaded;
+        }
+    }
+    result
+}
+
+/// Incremented by `trigger_reload()`. Each module compares it against
+/// the value it last saw to tell whether it should reload.
+static RELOAD_TRIGGER: AtomicUsize = AtomicUsize::new(0);
+
+/// Asks every dynamic module to reload on its next call. This only
+/// touches an atomic, so it's safe to call from a signal handler.
+pub fn trigger_reload() {
+    RELOAD_TRIGGER.fetch_add(1, Ordering::Release);
+}
+
+/// Called with the message of each panic forwarded by a dylib, or
+/// `None` to print it.
+static PANIC_HANDLER: RwLock<Option<fn(&str)>> = RwLock::new(None);
+
+pub fn set_panic_handler(handler: fn(&str)) {
+    *PANIC_HANDLER
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(handler);
+}
+
+/// Passed to each dylib's `__dymod_init`, for it to call with its
+/// panics.
+extern "C" fn forward_panic(message: *const u8, len: usize) {
+    let message = unsafe { std::slice::from_raw_parts(message, len) };
+    let message = String::from_utf8_lossy(message);
+    let handler = *PANIC_HANDLER.read().unwrap_or_else(PoisonError::into_inner);
+    match handler {
+        Some(handler) => handler(&message),
+        None => diagnostics::warn(format_args!("dylib {}", message)),
+    }
+}
+
+/// Hashes the contents of the file at `path` with 64-bit FNV-1a, which
+/// is fast and plenty to tell whether a rebuild changed anything.
+#[cfg(feature = "content-hash")]
+fn content_hash(path: &Path) -> Result<u64, std::io::Error> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buffer = vec![0; 64 * 1024];
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    loop {
+        let len = file.read(&mut buffer)?;
+        if len == 0 {
+            return Ok(hash);
+        }
+        for &byte in &buffer[..len] {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Clears the install name of the dylib at `path`, so that dyld
+/// doesn't return a cached copy of a previous version.
+///
+/// This is only a workaround, so if `install_name_tool` isn't
+/// installed (it comes with the Xcode command line tools), the step
+/// is skipped with a warning instead.
+#[cfg(target_os = "macos")]
+fn clear_install_name(path: &Path) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let output = std::process::Command::new("install_name_tool")
+        .arg("-id")
+        .arg("")
+        .arg(path)
+        .output();
+
+    match output {
+        Ok(output) => assert!(
+            output.status.success(),
+            "install_name_tool failed: {:#?}",
+            output
+        ),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            if !WARNED.swap(true, Ordering::Relaxed) {
+                diagnostics::warn(format_args!(
+                    "install_name_tool not found, so reloaded dylibs may be cached by dyld. \
+                     Install the Xcode command line tools to fix this."
+                ));
+            }
+        }
+        Err(error) => panic!("Failed to start install_name_tool: {}", error),
+    }
+}
+
+/// The runtime state of a dynamically linked dymod module.
+///
+/// Each dynamic `dymod!` module has one of these in a static, and the
+/// generated functions forward to it.
+pub struct DynamicModule {
+    manifest_dir: &'static str,
+    name: &'static str,
+    functions: &'static [&'static str],
+
+    /// Whether the dylib is next to the executable, rather than in the
+    /// subcrate's `target` directory under `manifest_dir`.
+    relative_to_exe: bool,
+
+    /// Whether the dylib's path is only given at run time, by
+    /// `load_from()`.
+    runtime_path: bool,
+
+    /// The target the subcrate is built for, whose directory under its
+    /// `target` directory the dylib is in, if it has a `#[target]`.
+    target_triple: Option<&'static str>,
+
+    /// Whether the subcrate is built with `--release`, and the dylib
+    /// loaded from its `release` directory, with
+    /// `#[dylib_build_profile = "release"]`.
+    release_build: bool,
+
+    /// The `#[dylib]` path template the dylib is found with, instead of
+    /// either directory, if there is one.
+    dylib_template: Option<&'static str>,
+
+    /// How many arguments each of `functions` takes, in the same order,
+    /// or empty if they're unknown.
+    arities: &'static [u8],
+
+    /// The ABI the declared functions are called with, to check against
+    /// any exported by `arity!()` in the dylib.
+    abi: &'static str,
+    auto_reload: bool,
+
+    /// The file watched for changes instead of the dylib, relative to
+    /// the dylib's directory, if the module has a `#[trigger]`.
+    trigger: Option<&'static str>,
+
+    /// Whether the host uses the system allocator, as exported by its
+    /// `alloc_marker!()`, for a `#[checked(alloc)]` module.
+    host_system_alloc: Option<bool>,
+
+    /// The path the dylib is loaded from, once it's been worked out.
+    /// Each one is leaked, so that it can be borrowed for as long as
+    /// the module, and there's only a new one when the resolver is set.
+    dylib_path: Mutex<Option<&'static Path>>,
+
+    /// Works out the path the dylib is loaded from, instead of the
+    /// manifest or executable directory, if one has been set.
+    path_resolver: Mutex<Option<Box<PathResolver>>>,
+    symbol_resolution: Mutex<SymbolResolution>,
+    copy_strategy: Mutex<CopyStrategy>,
+
+    /// Where each version of the dylib is copied to, instead of next to
+    /// it, if one has been set.
+    copy_directory: Mutex<Option<PathBuf>>,
+    cleanup_policy: Mutex<CleanupPolicy>,
+
+    /// Copies the dylib and checks whether it's changed, which is only
+    /// anything but the real filesystem in tests.
+    fs: &'static dyn FsOps,
+
+    /// Opens the dylib, or `DefaultLoader` if this is `None`.
+    loader: Mutex<Option<Box<dyn DymodLoader>>>,
+
+    /// Builds the dylib for `rebuild()`, instead of `cargo build`, if
+    /// one has been set.
+    builder: Mutex<Option<Arc<Builder>>>,
+
+    /// The output of the last build by `rebuild()`.
+    last_build_output: Mutex<Option<String>>,
+
+    change_check: Mutex<ChangeCheck>,
+    state: RwLock<State>,
+    registered: Once,
+
+    /// Which module this is, among those copying their dylibs in this
+    /// process, or 0 until it first copies one.
+    instance: AtomicUsize,
+
+    /// Held for the whole of a reload, so that only one happens at a
+    /// time, even while the hooks run without `state` locked.
+    reloading: Mutex<()>,
+    hooks: Mutex<Hooks>,
+
+    /// Whether reload hooks are running, during which changes to the
+    /// dylib are ignored instead of starting another reload.
+    in_hook: AtomicBool,
+
+    /// Whether the module is pinned to the loaded dylib, which is then
+    /// only reloaded explicitly.
+    pinned: AtomicBool,
+
+    /// The value of `RELOAD_TRIGGER` when this module last reloaded.
+    seen_trigger: AtomicUsize,
+
+    /// Whether a dylib built by a different version of rustc has been
+    /// warned about already.
+    warned_rustc_version: AtomicBool,
+
+    /// How many previously loaded libraries are kept loaded after a
+    /// reload, as set by `set_max_retained_libraries()`, or 2 by
+    /// default.
+    max_retained: AtomicUsize,
+
+    #[cfg(feature = "watch")]
+    watcher: watch::Watcher,
+}
+
+/// A function which builds the dylib before it's reloaded.
+type Builder = dyn Fn() -> Result<(), ReloadError> + Send + Sync;
+
+/// A function which returns the path of the dylib.
+type PathResolver = dyn Fn() -> PathBuf + Send + Sync;
+
+/// What's needed to tell whether the dylib has changed.
+struct ChangeCheck {
+    /// The minimum time between checks of the dylib's modified time.
+    interval: Duration,
+    last_check: Option<Instant>,
+    modified_time: Option<SystemTime>,
+
+    /// The hash of the loaded dylib's contents, so that a rebuild which
+    /// only changes its modified time doesn't reload it.
+    #[cfg(feature = "content-hash")]
+    content_hash: Option<u64>,
+}
+
+struct Hooks {
+    before_reload: Vec<fn()>,
+    after_reload: Vec<fn()>,
+    events: Option<Sender<DymodEvent>>,
+}
+
+struct State {
+    library: Option<Library>,
+    version: usize,
+
+    /// Addresses of `functions`, in the same order, if they were
+    /// resolved eagerly when the library was loaded.
+    symbols: Vec<usize>,
+
+    /// The copy of the dylib that `library` was loaded from, if it
+    /// was copied, which is removed once it's unloaded.
+    copy: Option<PathBuf>,
+
+    /// The dylib that `library` was loaded from.
+    file: Option<DylibInfo>,
+
+    /// Previously loaded libraries which are kept loaded, oldest first.
+    retained: VecDeque<Retained>,
+
+    last_reload: Option<SystemTime>,
+    last_error: Option<String>,
+}
+
+impl DynamicModule {
+    pub const fn new(
+        manifest_dir: &'static str,
+        name: &'static str,
+        functions: &'static [&'static str],
+    ) -> Self {
+        DynamicModule {
+            manifest_dir,
+            name,
+            functions,
+            relative_to_exe: false,
+            runtime_path: false,
+            target_triple: None,
+            release_build: false,
+            dylib_template: None,
+            arities: &[],
+            abi: "C",
+            auto_reload: true,
+            trigger: None,
+            host_system_alloc: None,
+            dylib_path: Mutex::new(None),
+            path_resolver: Mutex::new(None),
+            symbol_resolution: Mutex::new(SymbolResolution::Lazy),
+            copy_strategy: Mutex::new(CopyStrategy::PlatformDefault),
+            copy_directory: Mutex::new(None),
+            cleanup_policy: Mutex::new(CleanupPolicy::BestEffort),
+            fs: &RealFs,
+            loader: Mutex::new(None),
+            builder: Mutex::new(None),
+            last_build_output: Mutex::new(None),
+            change_check: Mutex::new(ChangeCheck {
+                interval: Duration::ZERO,
+                last_check: None,
+                modified_time: None,
+                #[cfg(feature = "content-hash")]
+                content_hash: None,
+            }),
+            state: RwLock::new(State {
+                library: None,
+                version: 0,
+                symbols: Vec::new(),
+                copy: None,
+                file: None,
+                retained: VecDeque::new(),
+                last_reload: None,
+                last_error: None,
+            }),
+            reloading: Mutex::new(()),
+            hooks: Mutex::new(Hooks {
+                before_reload: Vec::new(),
+                after_reload: Vec::new(),
+                events: None,
+            }),
+            in_hook: AtomicBool::new(false),
+            pinned: AtomicBool::new(false),
+            seen_trigger: AtomicUsize::new(0),
+            warned_rustc_version: AtomicBool::new(false),
+            max_retained: AtomicUsize::new(2),
+            registered: Once::new(),
+            instance: AtomicUsize::new(0),
+            #[cfg(feature = "watch")]
+            watcher: watch::Watcher::new(),
+        }
+    }
+
+    /// Looks for the dylib next to the running executable instead of
+    /// under the manifest directory.
+    pub const fn relative_to_exe(mut self) -> Self {
+        self.relative_to_exe = true;
+        self
+    }
+
+    /// Loads nothing until `load_from()` gives the path of the dylib,
+    /// as with a `#[runtime_path]` module.
+    pub const fn runtime_path(mut self) -> Self {
+        self.runtime_path = true;
+        self
+    }
+
+    /// Copies the dylib and checks whether it's changed with `fs`,
+    /// rather than on the real filesystem.
+    #[cfg(test)]
+    pub(crate) const fn with_fs(mut self, fs: &'static dyn FsOps) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    /// Looks for the dylib in the directory cargo builds the target
+    /// `triple` in, and builds it for that target in `rebuild()`.
+    pub const fn target_triple(mut self, triple: &'static str) -> Self {
+        self.target_triple = Some(triple);
+        self
+    }
+
+    /// Builds the subcrate with `--release` in `rebuild()`, and looks
+    /// for the dylib in the `release` directory instead of `debug`.
+    pub const fn release_build(mut self) -> Self {
+        self.release_build = true;
+        self
+    }
+
+    fn profile(&self) -> &'static str {
+        if self.release_build {
+            "release"
+        } else {
+            "debug"
+        }
+    }
+
+    /// Finds the dylib by filling in the placeholders of `template`,
+    /// instead of in the manifest or executable directory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `template` has an unknown placeholder, which fails to
+    /// compile when the module is in a static.
+    pub const fn dylib_template(mut self, template: &'static str) -> Self {
+        check_dylib_template(template);
+        self.dylib_template = Some(template);
+        self
+    }
+
+    /// Sets how many arguments each declared function takes, to check
+    /// against any exported by `arity!()` in the dylib.
+    pub const fn arities(mut self, arities: &'static [u8]) -> Self {
+        self.arities = arities;
+        self
+    }
+
+    /// Sets the ABI the declared functions are called with, such as
+    /// `"system"`, rather than `"C"`.
+    pub const fn abi(mut self, abi: &'static str) -> Self {
+        self.abi = abi;
+        self
+    }
+
+    /// Warns when the dylib's `alloc_marker!()` doesn't match the
+    /// host's, given as whether the host uses the system allocator.
+    pub const fn host_system_alloc(mut self, system: bool) -> Self {
+        self.host_system_alloc = Some(system);
+        self
+    }
+
+    /// Never reloads the dylib when a function is called, even with the
+    /// `auto-reload` feature, so it's only reloaded explicitly.
+    pub const fn no_auto_reload(mut self) -> Self {
+        self.auto_reload = false;
+        self
+    }
+
+    /// Watches `trigger` for changes instead of the dylib, so that it's
+    /// only reloaded once the build touches it. A relative `trigger` is
+    /// relative to the dylib's directory.
+    pub const fn trigger(mut self, trigger: &'static str) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    pub fn dylib_path(&self) -> &'static Path {
+        let mut path = self
+            .dylib_path
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(path) = *path {
+            return path;
+        }
+
+        let resolver = self
+            .path_resolver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let resolved = match &*resolver {
+            Some(resolver) => resolver(),
+            None => match self.dylib_template {
+                Some(template) => {
+                    fill_dylib_template(template, self.manifest_dir, self.name, self.profile())
+                }
+                None if self.relative_to_exe => exe_dylib_path(self.name),
+                None if self.runtime_path => PathBuf::new(),
+                None => profile_dylib_path(
+                    self.manifest_dir,
+                    self.name,
+                    self.target_triple,
+                    self.profile(),
+                ),
+            },
+        };
+        let resolved = &*Box::leak(resolved.into_boxed_path());
+        *path = Some(resolved);
+        resolved
+    }
+
+    /// Sets the function which works out the path of the dylib, instead
+    /// of looking in the manifest or executable directory. It's called
+    /// the next time the path is needed, and its result is used from
+    /// then on.
+    pub fn set_path_resolver(&self, resolver: impl Fn() -> PathBuf + Send + Sync + 'static) {
+        *self
+            .path_resolver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(resolver));
+        *self
+            .dylib_path
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Loads the dylib at `path`, and keeps loading it from there,
+    /// replacing any dylib that's already loaded.
+    pub fn load_from(&'static self, path: impl Into<PathBuf>) -> Result<(), ReloadError> {
+        let path = path.into();
+        self.set_path_resolver(move || path.clone());
+        self.force_reload()
+    }
+
+    pub fn set_eager_symbol_resolution(&self, eager: bool) {
+        self.set_symbol_resolution(if eager {
+            SymbolResolution::Eager
+        } else {
+            SymbolResolution::Lazy
+        });
+    }
+
+    pub fn set_symbol_resolution(&self, resolution: SymbolResolution) {
+        *self
+            .symbol_resolution
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = resolution;
+    }
+
+    pub fn reload(&'static self) {
+        if let Err(error) = self.force_reload() {
+            panic!("Failed to reload dylib: {}", error);
+        }
+    }
+
+    /// Builds the dylib with the builder set by `set_builder()`, or by
+    /// running `cargo build` in the subcrate, then reloads it. If the
+    /// build fails, the old dylib is kept.
+    pub fn rebuild(&'static self) -> Result<(), ReloadError> {
+        // Cloned out, so that the builder can call `set_builder()` itself
+        let builder = self
+            .builder
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let (result, output) = match builder {
+            Some(builder) => {
+                let result = builder();
+                let output = match &result {
+                    Err(ReloadError::BuildFailed { stderr, .. }) => Some(stderr.clone()),
+                    _ => None,
+                };
+                (result, output)
+            }
+            None => {
+                let subcrate_dir = Path::new(self.manifest_dir).join(self.name);
+                let (result, output) =
+                    cargo_build(&subcrate_dir, self.release_build, self.target_triple);
+                (result, Some(output))
+            }
+        };
+
+        *self.build_output() = output;
+        if let Err(error) = result {
+            self.write().last_error = Some(error.to_string());
+            return Err(error);
+        }
+        self.force_reload()
+    }
+
+    /// Returns the output of the last build by `rebuild()`, if it was
+    /// by `cargo build` or it failed.
+    pub fn last_build_output(&self) -> Option<String> {
+        self.build_output().clone()
+    }
+
+    /// Reloads the dylib even if it hasn't changed, unlike
+    /// `reload_if_modified()`.
+    pub fn force_reload(&'static self) -> Result<(), ReloadError> {
+        self.reload_from_source(None, None)
+    }
+
+    /// Reloads the dylib from `path` this once, instead of from
+    /// `dylib_path()`, which is still the one checked for changes.
+    pub fn reload_from(&'static self, path: &Path) -> Result<(), ReloadError> {
+        self.reload_from_source(Some(path), None)
+    }
+
+    /// Reloads the dylib like `reload()`, unless another thread has
+    /// loaded a version other than `seen` by the time this one can,
+    /// such as while this one waited for it to finish. Otherwise every
+    /// thread which found the dylib unloaded or changed would reload it
+    /// in turn, each unloading it from under the others again when it
+    /// isn't copied.
+    fn reload_unless_reloaded_since(&'static self, seen: usize) {
+        if let Err(error) = self.reload_from_source(None, Some(seen)) {
+            panic!("Failed to reload dylib: {}", error);
+        }
+    }
+
+    /// Reloads the dylib from `source`, or `dylib_path()` if it's
+    /// `None`, unless a version other than `seen` has been loaded if
+    /// it's given.
+    fn reload_from_source(
+        &'static self,
+        source: Option<&Path>,
+        seen: Option<usize>,
+    ) -> Result<(), ReloadError> {
+        if self.runtime_path && !self.has_path_resolver() {
+            return Err(ReloadError::NoDylibPath { module: self.name });
+        }
+
+        // Unloading the dylib would pull its code out from under the call
+        // which is still running it
+        if in_dylib_call() && self.read().library.is_some() {
+            return Err(ReloadError::ReentrantReload {
+                path: self.dylib_path().to_owned(),
+            });
+        }
+
+        let _reloading = self
+            .reloading
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(seen) = seen {
+            let state = self.read();
+            if state.library.is_some() && state.version != seen {
+                return Ok(());
+            }
+        }
+        self.registered.call_once(|| {
+            registry().push(self);
+            // A dylib chosen at run time has no subcrate to build
+            #[cfg(feature = "autobuild")]
+            if !self.runtime_path {
+                autobuild::start(
+                    self,
+                    Path::new(self.manifest_dir).join(self.name).join("src"),
+                );
+            }
+        });
+        self.seen_trigger
+            .store(RELOAD_TRIGGER.load(Ordering::Acquire), Ordering::Relaxed);
+        self.send_event(DymodEvent::ReloadStarted);
+
+        let version = self.read().version;
+        let reloading = self.read().library.is_some();
+        let strategy = self.copy_strategy().resolve();
+        let copy = match strategy {
+            CopyStrategy::Never => None,
+            CopyStrategy::OnReload if !reloading => None,
+            _ => Some(self.copy_path(version)),
+        };
+
+        // Loading the same path again would just return the library
+        // that's already loaded, so without a copy, the old one has to
+        // be unloaded first
+        if copy.is_none() && reloading {
+            self.run_hooks(|hooks| &hooks.before_reload);
+            self.replace_library(None, None, Vec::new());
+        }
+
+        let loaded = match self.load_dylib(strategy, copy, source) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                let message = error.to_string();
+                self.write().last_error = Some(message.clone());
+                self.send_event(DymodEvent::ReloadFailed { error: message });
+                return Err(error);
+            }
+        };
+
+        if loaded.copy.is_some() && reloading {
+            self.run_hooks(|hooks| &hooks.before_reload);
+        }
+
+        self.replace_library(Some(loaded.library), loaded.copy, loaded.symbols);
+        {
+            let mut state = self.write();
+            state.file = loaded.file.map(|file| DylibInfo {
+                generation: state.version,
+                ..file
+            });
+            state.last_reload = Some(SystemTime::now());
+            state.last_error = None;
+        }
+        {
+            let mut check = self.change_check();
+            check.modified_time = loaded.modified_time;
+            #[cfg(feature = "content-hash")]
+            {
+                check.content_hash = loaded.content_hash;
+            }
+        }
+
+        self.send_event(DymodEvent::ReloadSucceeded {
+            version: self.current_generation(),
+        });
+        if reloading {
+            self.run_hooks(|hooks| &hooks.after_reload);
+        }
+
+        Ok(())
+    }
+
+    /// Loads the dylib, or the one at `source` if given, first copying
+    /// it to `copy` if given, without replacing the currently loaded
+    /// library.
+    fn load_dylib(
+        &self,
+        strategy: CopyStrategy,
+        copy: Option<PathBuf>,
+        source: Option<&Path>,
+    ) -> Result<Loaded, ReloadError> {
+        let dylib_path = self.dylib_path();
+        let source = source.unwrap_or(dylib_path);
+        let modified_time = self.watched_modified_time();
+        #[cfg(feature = "content-hash")]
+        let content_hash = content_hash(dylib_path).ok();
+
+        #[cfg(feature = "watch")]
+        self.watcher.clear();
+
+        // Create the new
+        let (path, file) = match &copy {
+            Some(copy) => {
+                if let Some(dir) = copy.parent() {
+                    self.fs
+                        .create_dir_all(dir)
+                        .map_err(|error| ReloadError::CopyFailed {
+                            path: copy.clone(),
+                            error,
+                        })?;
+                }
+                let new_path = new_dylib_path(dylib_path);
+                let renaming = source == dylib_path
+                    && strategy == CopyStrategy::AtomicRename
+                    && self.fs.exists(&new_path);
+                let file = if renaming {
+                    self.dylib_file(&new_path, dylib_path)
+                } else {
+                    self.dylib_file(source, source)
+                };
+                let source = if renaming { &new_path } else { source };
+                // Replace anything left at the copy's path rather than
+                // writing into it, in case it's still loaded
+                let _ = self.fs.remove_file(copy);
+                let moved = if renaming {
+                    self.fs.rename(source, copy)
+                } else {
+                    self.fs.copy(source, copy)
+                };
+                moved.map_err(|error| ReloadError::CopyFailed {
+                    path: copy.clone(),
+                    error,
+                })?;
+
+                // Clear install name to confuse dyld cache
+                #[cfg(target_os = "macos")]
+                clear_install_name(copy);
+
+                (copy.as_path(), file)
+            }
+            None => (source, self.dylib_file(source, source)),
+        };
+
+        // Load the new, keeping the old if anything goes wrong
+        match self.load(path) {
+            Ok((library, symbols)) => Ok(Loaded {
+                library,
+                symbols,
+                modified_time,
+                #[cfg(feature = "content-hash")]
+                content_hash,
+                copy,
+                file,
+            }),
+            Err(error) => {
+                if let Some(copy) = &copy {
+                    let _ = self.fs.remove_file(copy);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Returns the path `version` of the dylib is copied to before it's
+    /// loaded, which is in the copy directory if one has been set.
+    ///
+    /// The path is unique to this module in this process, so another
+    /// module or process loading the same dylib never copies over it.
+    pub fn copy_path(&self, version: usize) -> PathBuf {
+        let mut versioned = self.dylib_path().as_os_str().to_owned();
+        versioned.push(format!(
+            ".{}-{}.{}",
+            std::process::id(),
+            self.instance(),
+            version
+        ));
+        let versioned = PathBuf::from(versioned);
+        match &*self
+            .copy_directory
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            Some(dir) => dir.join(versioned.file_name().unwrap_or_default()),
+            None => versioned,
+        }
+    }
+
+    fn instance(&self) -> usize {
+        let instance = self.instance.load(Ordering::Acquire);
+        if instance != 0 {
+            return instance;
+        }
+        let next = NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed);
+        match self
+            .instance
+            .compare_exchange(0, next, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => next,
+            Err(instance) => instance,
+        }
+    }
+
+    /// Reads the size and modified time of the dylib at `path`, before
+    /// it's loaded from there as `dylib`. Its generation is filled in
+    /// once it has been.
+    fn dylib_file(&self, path: &Path, dylib: &Path) -> Option<DylibInfo> {
+        Some(DylibInfo {
+            path: dylib.to_owned(),
+            size: self.fs.len(path).ok()?,
+            modified: self.fs.modified(path).ok()?,
+            generation: 0,
+        })
+    }
+
+    /// Replaces the loaded library, then unloads the old one and
+    /// removes its copy.
+    fn replace_library(
+        &self,
+        library: Option<Library>,
+        copy: Option<PathBuf>,
+        symbols: Vec<usize>,
+    ) {
+        let mut state = self.write();
+
+        // Drop the old, unless it's retained, which it can only be if it
+        // was still loaded alongside the new one
+        let old_library = std::mem::replace(&mut state.library, library);
+        let old_copy = std::mem::replace(&mut state.copy, copy);
+        let old_version = state.version;
+        state.symbols = symbols;
+        let mut expired = Vec::new();
+        match (old_library, old_copy) {
+            (Some(library), copy) if state.library.is_some() => {
+                state.retained.push_back(Retained {
+                    library,
+                    copy,
+                    version: old_version,
+                });
+            }
+            (old_library, old_copy) => {
+                drop(old_library);
+                expired.extend(old_copy);
+            }
+        }
+
+        // Bumped under the same lock as the library is replaced, so that
+        // a symbol cached for the old version is never used with the new
+        // library
+        if state.library.is_some() {
+            state.version += 1;
+        }
+
+        self.unload_expired(state, expired);
+    }
+
+    /// Unloads the oldest retained libraries beyond the limit, then
+    /// removes their copies, and those in `expired`, which the new
+    /// library doesn't need.
+    fn unload_expired(&self, mut state: RwLockWriteGuard<'_, State>, mut expired: Vec<PathBuf>) {
+        let max_retained = self.max_retained.load(Ordering::Relaxed);
+        while state.retained.len() > max_retained {
+            let old = state.retained.pop_front().unwrap();
+            drop(old.library);
+            expired.extend(old.copy);
+        }
+        drop(state);
+
+        for old_copy in expired {
+            if let Err(error) = self.fs.remove_file(&old_copy) {
+                self.cleanup_failed(&old_copy, error);
+            }
+        }
+    }
+
+    /// Keeps up to `max` previously loaded libraries loaded after each
+    /// reload, unloading the oldest beyond that, including straight
+    /// away if more than `max` are retained already.
+    pub fn set_max_retained_libraries(&self, max: usize) {
+        self.max_retained.store(max, Ordering::Relaxed);
+        self.unload_expired(self.write(), Vec::new());
+    }
+
+    fn cleanup_failed(&self, old_copy: &Path, error: std::io::Error) {
+        let policy = *self
+            .cleanup_policy
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match policy {
+            CleanupPolicy::Strict => {
+                panic!(
+                    "Failed to delete old dylib at {}: {}",
+                    old_copy.display(),
+                    error
+                )
+            }
+            CleanupPolicy::BestEffort => diagnostics::warn(format_args!(
+                "failed to delete old dylib at {}: {}; leaving it in place",
+                old_copy.display(),
+                error
+            )),
+        }
+    }
+
+    pub fn set_copy_strategy(&self, strategy: CopyStrategy) {
+        *self.copy_strategy() = strategy;
+    }
+
+    pub fn set_copy_directory(&self, dir: impl Into<PathBuf>) {
+        *self
+            .copy_directory
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(dir.into());
+    }
+
+    pub fn set_cleanup_policy(&self, policy: CleanupPolicy) {
+        *self
+            .cleanup_policy
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = policy;
+    }
+
+    pub fn set_loader(&self, loader: impl DymodLoader + 'static) {
+        *self.loader() = Some(Box::new(loader));
+    }
+
+    pub fn set_builder(
+        &self,
+        builder: impl Fn() -> Result<(), ReloadError> + Send + Sync + 'static,
+    ) {
+        *self.builder.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(builder));
+    }
+
+    #[cfg(unix)]
+    pub fn set_open_flags(&self, flags: i32) {
+        self.set_loader(crate::loader::OpenFlagsLoader { flags });
+    }
+
+    pub fn set_pinned(&self, pinned: bool) {
+        self.pinned.store(pinned, Ordering::Release);
+    }
+
+    fn is_pinned(&self) -> bool {
+        self.pinned.load(Ordering::Acquire)
+    }
+
+    pub fn on_before_reload(&self, hook: fn()) {
+        self.hooks().before_reload.push(hook);
+    }
+
+    pub fn on_reload(&self, hook: fn()) {
+        self.hooks().after_reload.push(hook);
+    }
+
+    /// Sends each event from now on to `sender`, instead of any sender
+    /// set before, until its receiver is dropped.
+    pub fn set_event_sender(&self, sender: Sender<DymodEvent>) {
+        self.hooks().events = Some(sender);
+    }
+
+    fn send_event(&self, event: DymodEvent) {
+        let mut hooks = self.hooks();
+        if let Some(sender) = &hooks.events {
+            if sender.send(event).is_err() {
+                hooks.events = None;
+            }
+        }
+    }
+
+    /// Calls each of the selected hooks, with no locks held so that
+    /// they can call the module's functions.
+    fn run_hooks(&self, select: impl FnOnce(&Hooks) -> &Vec<fn()>) {
+        let hooks = select(&self.hooks()).clone();
+        self.in_hook.store(true, Ordering::Release);
+        for hook in hooks {
+            hook();
+        }
+        self.in_hook.store(false, Ordering::Release);
+    }
+
+    pub fn status(&self) -> ModuleStatus {
+        let state = self.read();
+        ModuleStatus {
+            version: state.version,
+            loaded: state.library.is_some(),
+            last_reload: state.last_reload,
+            last_error: state.last_error.clone(),
+            auto_reload: crate::AUTO_RELOAD && self.auto_reload,
+        }
+    }
+
+    /// Returns the size and modified time of the loaded dylib, or `None`
+    /// if it isn't loaded or they couldn't be read.
+    pub fn loaded_dylib_info(&self) -> Option<DylibInfo> {
+        let state = self.read();
+        match state.library {
+            Some(_) => state.file.clone(),
+            None => None,
+        }
+    }
+
+    /// The version of the loaded dylib, the same as in `status()`.
+    pub fn current_generation(&self) -> usize {
+        self.read().version
+    }
+
+    /// Whether the dylib loaded as `generation` is still loaded, either
+    /// as the current one or as one retained after a reload.
+    pub fn is_generation_live(&self, generation: usize) -> bool {
+        let state = self.read();
+        (state.library.is_some() && state.version == generation)
+            || state.retained.iter().any(|old| old.version == generation)
+    }
+
+    /// Returns the loaded library, loading or reloading it first if
+    /// necessary.
+    pub fn library(&'static self) -> LoadedLibrary<'static> {
+        // Without a copy, another thread may unload the library between
+        // checking it and locking it, before loading the new one
+        loop {
+            let seen = self.read().version;
+            if self.needs_reload() {
+                self.reload_unless_reloaded_since(seen);
+            }
+
+            let state = self.read();
+            if state.library.is_some() {
+                return LoadedLibrary {
+                    functions: self.functions,
+                    dylib_path: self.dylib_path(),
+                    state,
+                };
+            }
+        }
+    }
+
+    /// Returns the loaded library, loading it first if it isn't loaded,
+    /// but without checking whether it has changed.
+    pub fn loaded_library(&'static self) -> LoadedLibrary<'static> {
+        loop {
+            let state = self.read();
+            if state.library.is_some() {
+                return LoadedLibrary {
+                    functions: self.functions,
+                    dylib_path: self.dylib_path(),
+                    state,
+                };
+            }
+
+            let seen = state.version;
+            drop(state);
+            self.reload_unless_reloaded_since(seen);
+        }
+    }
+
+    /// Reloads the library if it isn't loaded yet, or has changed since
+    /// it was loaded, returning whether it was reloaded.
+    pub fn reload_if_changed(&'static self) -> Result<bool, ReloadError> {
+        if self.read().library.is_none() || (!self.is_pinned() && self.reload_triggered()) {
+            self.force_reload()?;
+            Ok(true)
+        } else if self.pending_reload() {
+            self.send_event(DymodEvent::ChangeDetected);
+            self.force_reload()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Reloads the dylib whenever it has changed, checking every
+    /// `interval` until `stop` is set, and calls `on_reload` after each
+    /// successful reload. A failed reload keeps the old dylib, and its
+    /// error is only recorded in the status.
+    #[cfg(feature = "auto-reload")]
+    pub fn run_watch_loop(
+        &'static self,
+        interval: Duration,
+        stop: &AtomicBool,
+        mut on_reload: impl FnMut(),
+    ) {
+        while !stop.load(Ordering::Acquire) {
+            if let Ok(true) = self.reload_if_changed() {
+                on_reload();
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Reloads the dylib, carrying its state over with the `save_state`,
+    /// `state_len` and `load_state` functions it exports. If it isn't
+    /// loaded yet, it's only loaded.
+    pub fn reload_preserving_state(&'static self) -> Result<(), ReloadError> {
+        // Checked under the same lock as the snapshot is taken, as a
+        // failed reload on another thread may unload the library first
+        let snapshot = match self.save_state()? {
+            Some(snapshot) => snapshot,
+            None => return self.force_reload(),
+        };
+        self.force_reload()?;
+
+        let loaded = self.library();
+        let library = loaded.state.library.as_ref().unwrap();
+        let path = loaded.state.copy.as_deref().unwrap_or(self.dylib_path());
+        unsafe {
+            let load_state: Symbol<extern "C" fn(*const u8, usize)> =
+                state_symbol(library, path, "load_state")?;
+            let _call = CallGuard::enter();
+            load_state(snapshot.as_ptr(), snapshot.len());
+        }
+        Ok(())
+    }
+
+    /// Reloads the dylib, and returns which of its functions are in a
+    /// different place in the new one. Each is looked up before and after
+    /// reloading, whatever the symbol resolution mode. If it isn't loaded
+    /// yet, it's loaded, and every function it has is returned.
+    pub fn reload_with_diff(&'static self) -> Result<Vec<&'static str>, ReloadError> {
+        let before = self.symbol_offsets();
+        self.force_reload()?;
+        let after = self.symbol_offsets();
+
+        Ok(self
+            .functions
+            .iter()
+            .zip(before.iter().zip(&after))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(&function, _)| function)
+            .collect())
+    }
+
+    fn symbol_offsets(&self) -> Vec<Option<usize>> {
+        match &self.read().library {
+            Some(library) => diff::symbol_offsets(library, self.functions),
+            None => vec![None; self.functions.len()],
+        }
+    }
+
+    /// Takes the snapshot of the loaded dylib's state returned by its
+    /// `save_state` function, or returns `None` if it isn't loaded.
+    fn save_state(&self) -> Result<Option<Box<[u8]>>, ReloadError> {
+        let state = self.read();
+        let library = match &state.library {
+            Some(library) => library,
+            None => return Ok(None),
+        };
+        let path = state.copy.as_deref().unwrap_or(self.dylib_path());
+        unsafe {
+            let save_state: Symbol<extern "C" fn() -> *mut u8> =
+                state_symbol(library, path, "save_state")?;
+            let state_len: Symbol<extern "C" fn() -> usize> =
+                state_symbol(library, path, "state_len")?;
+
+            let _call = CallGuard::enter();
+            let snapshot = save_state();
+            let len = state_len();
+            if snapshot.is_null() {
+                return Ok(Some(Box::new([])));
+            }
+
+            // Allocated by the dylib as a `Box<[u8]>`, which is freed
+            // here once the new dylib has loaded it
+            Ok(Some(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                snapshot, len,
+            ))))
+        }
+    }
+
+    /// Calls `f` with the loaded library, loading it first if necessary.
+    pub fn with_library<R>(&'static self, f: impl FnOnce(&Library) -> R) -> R {
+        let loaded = self.library();
+        f(loaded.state.library.as_ref().unwrap())
+    }
+
+    fn load(&self, path: &Path) -> Result<(Library, Vec<usize>), ReloadError> {
+        check_arch(path).map_err(refuse)?;
+
+        let library = match &*self.loader() {
+            Some(loader) => loader.load(path)?,
+            None => DefaultLoader.load(path)?,
+        };
+
+        self.check_abi_magic(&library, path).map_err(refuse)?;
+        self.check_arities(&library, path).map_err(refuse)?;
+        self.check_linkages(&library, path).map_err(refuse)?;
+        self.check_rustc_version(&library, path);
+        self.check_allocator(&library, path);
+        init(&library);
+
+        let resolution = *self
+            .symbol_resolution
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let symbols = match resolution {
+            SymbolResolution::Lazy => Vec::new(),
+            SymbolResolution::Eager => self.resolve_symbols(&library, path).map_err(refuse)?,
+            SymbolResolution::ValidateEager => {
+                // Only checked, so each call still looks it up lazily
+                self.resolve_symbols(&library, path).map_err(refuse)?;
+                Vec::new()
+            }
+        };
+
+        Ok((library, symbols))
+    }
+
+    /// Loads the dylib if it isn't already loaded, and checks that every
+    /// declared function can be found in it.
+    pub fn check(&'static self) -> Result<(), ReloadError> {
+        if self.read().library.is_none() {
+            self.force_reload()?;
+        }
+
+        let state = self.read();
+        let path = state.copy.as_deref().unwrap_or(self.dylib_path());
+        self.resolve_symbols(state.library.as_ref().unwrap(), path)
+            .map(|_| ())
+    }
+
+    /// Checks the constant exported by `dymod::abi_magic!()`, if the
+    /// dylib has one.
+    fn check_abi_magic(&self, library: &Library, path: &Path) -> Result<(), ReloadError> {
+        let symbol = format!("__DYMOD_ABI_{}", self.name);
+        let found = match unsafe { library.get::<*const u64>(symbol.as_bytes()) } {
+            Ok(magic) => unsafe { **magic },
+            Err(_) => return Ok(()),
+        };
+
+        if found == crate::ABI_MAGIC {
+            Ok(())
+        } else {
+            Err(ReloadError::AbiVersionMismatch {
+                path: path.to_owned(),
+                expected: crate::ABI_MAGIC,
+                found,
+            })
+        }
+    }
+
+    /// Checks the number of arguments of each declared function against
+    /// the one exported by `arity!()`, for those which have one.
+    fn check_arities(&self, library: &Library, path: &Path) -> Result<(), ReloadError> {
+        for (&function, &expected) in self.functions.iter().zip(self.arities) {
+            let symbol = format!("__DYMOD_ARITY_{}", function);
+            let found = match unsafe { library.get::<*const u8>(symbol.as_bytes()) } {
+                Ok(arity) => unsafe { **arity },
+                Err(_) => continue,
+            };
+
+            if found != expected {
+                return Err(ReloadError::ArityMismatch {
+                    symbol: function,
+                    path: path.to_owned(),
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the ABI of each declared function against the one
+    /// exported by `arity!()`, for those which have one.
+    fn check_linkages(&self, library: &Library, path: &Path) -> Result<(), ReloadError> {
+        let expected = crate::abi_hash(self.abi);
+        for &function in self.functions {
+            let symbol = format!("__DYMOD_LINKAGE_{}", function);
+            let found = match unsafe { library.get::<*const u64>(symbol.as_bytes()) } {
+                Ok(linkage) => unsafe { **linkage },
+                Err(_) => continue,
+            };
+
+            if found != expected {
+                return Err(ReloadError::AbiMismatch {
+                    function,
+                    path: path.to_owned(),
+                    expected: self.abi,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Warns if the dylib's `alloc_marker!()` says it uses a different
+    /// allocator to the host, for a `#[checked(alloc)]` module.
+    fn check_allocator(&self, library: &Library, path: &Path) {
+        let host = match self.host_system_alloc {
+            Some(host) => host,
+            None => return,
+        };
+        let symbol = format!("__DYMOD_SYSTEM_ALLOC_{}", self.name);
+        let found = match unsafe { library.get::<*const bool>(symbol.as_bytes()) } {
+            Ok(system) => unsafe { **system },
+            Err(_) => return,
+        };
+
+        if found != host {
+            let describe = |system| {
+                if system {
+                    "the system allocator"
+                } else {
+                    "a custom allocator"
+                }
+            };
+            diagnostics::warn(format_args!(
+                "{} uses {}, but is loaded by a crate using {}. \
+                 Dropping data allocated by the other side will crash.",
+                path.display(),
+                describe(found),
+                describe(host)
+            ));
+        }
+    }
+
+    /// Warns if the dylib was built by a different version of rustc,
+    /// the first time it happens for this module. Nothing is checked if
+    /// either version wasn't embedded.
+    fn check_rustc_version(&self, library: &Library, path: &Path) {
+        let symbol = format!("__DYMOD_RUSTC_{}", self.name);
+        let found = match unsafe { library.get::<*const RustcVersion>(symbol.as_bytes()) } {
+            Ok(version) => unsafe { (**version).read() },
+            Err(_) => return,
+        };
+
+        if let (Some(expected), Some(found)) = (crate::RUSTC_VERSION, found) {
+            if expected != found && !self.warned_rustc_version.swap(true, Ordering::Relaxed) {
+                diagnostics::warn(format_args!(
+                    "{} was built by {}, but is loaded by a crate built by {}. \
+                     Types which aren't #[repr(C)] may not match.",
+                    path.display(),
+                    found,
+                    expected
+                ));
+            }
+        }
+    }
+
+    fn resolve_symbols(&self, library: &Library, path: &Path) -> Result<Vec<usize>, ReloadError> {
+        self.functions
+            .iter()
+            .map(|&function| {
+                let symbol = unsafe { get_symbol::<*mut std::ffi::c_void>(library, function) };
+                symbol
+                    .map(|symbol| symbol as usize)
+                    .map_err(|_| ReloadError::SymbolNotFound {
+                        symbol: function,
+                        path: path.to_owned(),
+                    })
+            })
+            .collect()
+    }
+
+    pub fn set_change_check_interval(&self, interval: Duration) {
+        self.change_check().interval = interval;
+    }
+
+    /// Sets how long the dylib must stop changing for before the
+    /// watcher reloads it. This has no effect without the `watch`
+    /// feature.
+    #[allow(unused_variables)]
+    pub fn set_reload_debounce(&'static self, debounce: Duration) {
+        #[cfg(feature = "watch")]
+        self.watcher.set_debounce(debounce);
+    }
+
+    /// Returns whether the dylib should be loaded or reloaded before
+    /// the next call.
+    fn needs_reload(&'static self) -> bool {
+        self.read().library.is_none()
+            || (!self.in_hook.load(Ordering::Acquire)
+                && !self.is_pinned()
+                && !in_dylib_call()
+                && self.auto_reload
+                && (self.reload_triggered() || self.file_changed()))
+    }
+
+    /// Returns a future which loads or reloads the dylib on a
+    /// background thread if necessary, and completes once the next
+    /// call won't need to. The future panics if reloading fails.
+    #[cfg(feature = "async")]
+    pub fn ready(&'static self) -> nonblocking::Ready {
+        let seen = self.read().version;
+        if self.needs_reload() {
+            nonblocking::Ready::reload(self, seen)
+        } else {
+            nonblocking::Ready::done()
+        }
+    }
+
+    /// Returns whether `trigger_reload()` has been called since this
+    /// module last reloaded.
+    fn reload_triggered(&self) -> bool {
+        let trigger = RELOAD_TRIGGER.load(Ordering::Acquire);
+        trigger != self.seen_trigger.load(Ordering::Relaxed)
+    }
+
+    fn file_changed(&'static self) -> bool {
+        let file_changed = || -> Result<bool, std::io::Error> {
+            let mut check = self.change_check();
+
+            if !check.interval.is_zero() {
+                let now = Instant::now();
+                if let Some(last_check) = check.last_check {
+                    if now.duration_since(last_check) < check.interval {
+                        return Ok(false);
+                    }
+                }
+                check.last_check = Some(now);
+            }
+
+            if *self.copy_strategy() == CopyStrategy::AtomicRename {
+                return Ok(self.new_dylib_written());
+            }
+
+            Ok(self.dylib_changed(&mut check)? && !self.same_contents(&check))
+        };
+
+        let changed = crate::AUTO_RELOAD && self.auto_reload && file_changed().unwrap_or(false);
+        if changed {
+            self.send_event(DymodEvent::ChangeDetected);
+        }
+        changed
+    }
+
+    /// Asks the watcher thread whether the dylib has changed, starting
+    /// it if necessary.
+    #[cfg(feature = "watch")]
+    fn dylib_changed(&'static self, check: &mut ChangeCheck) -> Result<bool, std::io::Error> {
+        self.watcher
+            .start(&self.watched_path(), check.modified_time);
+        Ok(self.watcher.take_changed())
+    }
+
+    #[cfg(not(feature = "watch"))]
+    fn dylib_changed(&self, check: &mut ChangeCheck) -> Result<bool, std::io::Error> {
+        let modified_time = self.fs.modified(&self.watched_path())?;
+        let changed = check.modified_time.is_some() && check.modified_time != Some(modified_time);
+        check.modified_time = Some(modified_time);
+        Ok(changed)
+    }
+
+    /// Returns whether the dylib has changed since it was loaded,
+    /// without reloading it.
+    pub fn pending_reload(&self) -> bool {
+        if self.is_pinned() {
+            return false;
+        }
+
+        if *self.copy_strategy() == CopyStrategy::AtomicRename {
+            return self.new_dylib_written();
+        }
+
+        let mut check = self.change_check();
+        match (check.modified_time, self.fs.modified(&self.watched_path())) {
+            (Some(loaded_time), Ok(modified_time)) if loaded_time != modified_time => {
+                if self.same_contents(&check) {
+                    // Only hash it again once it's modified again
+                    check.modified_time = Some(modified_time);
+                    false
+                } else {
+                    true
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Reloads the dylib unless it's loaded and its modified time hasn't
+    /// changed since, returning whether it was reloaded. Unlike
+    /// `reload_if_changed()`, this reloads a pinned module too, and one
+    /// whose modified time can't be read, so that the error is returned.
+    pub fn reload_if_modified(&'static self) -> Result<bool, ReloadError> {
+        if self.read().library.is_some() {
+            if !self.modified_since_load() {
+                return Ok(false);
+            }
+            self.send_event(DymodEvent::ChangeDetected);
+        }
+        self.force_reload()?;
+        Ok(true)
+    }
+
+    fn modified_since_load(&self) -> bool {
+        if *self.copy_strategy() == CopyStrategy::AtomicRename {
+            return self.new_dylib_written();
+        }
+
+        let mut check = self.change_check();
+        match (check.modified_time, self.fs.modified(&self.watched_path())) {
+            (Some(loaded_time), Ok(modified_time)) if loaded_time == modified_time => false,
+            (Some(_), Ok(modified_time)) if self.same_contents(&check) => {
+                check.modified_time = Some(modified_time);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the file whose modified time says whether the dylib has
+    /// changed, which is the trigger file if the module has one.
+    fn watched_path(&self) -> PathBuf {
+        let dylib_path = self.dylib_path();
+        match self.trigger {
+            Some(trigger) => dylib_path.parent().unwrap_or(Path::new(".")).join(trigger),
+            None => dylib_path.to_owned(),
+        }
+    }
+
+    /// Returns the modified time of the watched file. A trigger file
+    /// which doesn't exist counts as modified long ago, so that creating
+    /// it is a change too.
+    fn watched_modified_time(&self) -> Option<SystemTime> {
+        match self.fs.modified(&self.watched_path()) {
+            Ok(time) => Some(time),
+            Err(_) if self.trigger.is_some() => Some(SystemTime::UNIX_EPOCH),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns whether the build has written a new version of the dylib
+    /// for `CopyStrategy::AtomicRename` to move into place.
+    fn new_dylib_written(&self) -> bool {
+        self.fs.exists(&new_dylib_path(self.dylib_path()))
+    }
+
+    /// Returns whether the dylib has the same contents as when it was
+    /// loaded, with the `content-hash` feature. Without it, a change to
+    /// the modified time is always taken as a change.
+    #[cfg(feature = "content-hash")]
+    fn same_contents(&self, check: &ChangeCheck) -> bool {
+        check.content_hash.is_some() && content_hash(self.dylib_path()).ok() == check.content_hash
+    }
+
+    #[cfg(not(feature = "content-hash"))]
+    fn same_contents(&self, _check: &ChangeCheck) -> bool {
+        false
+    }
+
+    fn has_path_resolver(&self) -> bool {
+        self.path_resolver
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    fn hooks(&self) -> MutexGuard<'_, Hooks> {
+        self.hooks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn build_output(&self) -> MutexGuard<'_, Option<String>> {
+        self.last_build_output
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn loader(&self) -> MutexGuard<'_, Option<Box<dyn DymodLoader>>> {
+        self.loader.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn copy_strategy(&self) -> MutexGuard<'_, CopyStrategy> {
+        self.copy_strategy
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn change_check(&self) -> MutexGuard<'_, ChangeCheck> {
+        self.change_check
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A previously loaded library, kept loaded by
+/// `set_max_retained_libraries()`.
+struct Retained {
+    library: Library,
+
+    /// The copy `library` was loaded from, unless it was loaded in
+    /// place.
+    copy: Option<PathBuf>,
+    version: usize,
+}
+
+/// A newly loaded dylib, which hasn't replaced the current one yet.
+struct Loaded {
+    library: Library,
+    symbols: Vec<usize>,
+    modified_time: Option<SystemTime>,
+    #[cfg(feature = "content-hash")]
+    content_hash: Option<u64>,
+    copy: Option<PathBuf>,
+    file: Option<DylibInfo>,
+}
+
+/// Looks up the function `name` in `library`, or failing that, `name`
+/// with a leading underscore, as some linkers name them on Mach-O.
+unsafe fn get_symbol<T: Copy>(library: &Library, name: &str) -> Result<T, std::io::Error> {
+    match library.get::<T>(name.as_bytes()) {
+        Ok(symbol) => Ok(*symbol),
+        Err(error) => library
+            .get::<T>(format!("_{}", name).as_bytes())
+            .map(|symbol| *symbol)
+            .map_err(|_| error),
+    }
+}
+
+/// Calls the dylib's `__dymod_init` function, if it has one.
+fn init(library: &Library) {
+    let init = unsafe { library.get::<extern "C" fn(crate::ForwardPanic)>(b"__dymod_init") };
+    if let Ok(init) = init {
+        let _call = CallGuard::enter();
+        init(forward_panic);
+    }
+}
+
+/// Looks up one of the functions used by `reload_preserving_state()`.
+unsafe fn state_symbol<'lib, T>(
+    library: &'lib Library,
+    path: &Path,
+    symbol: &'static str,
+) -> Result<Symbol<'lib, T>, ReloadError> {
+    library
+        .get(symbol.as_bytes())
+        .map_err(|_| ReloadError::SymbolNotFound {
+            symbol,
+            path: path.to_owned(),
+        })
+}
+
+/// The version of the dylib a function was last looked up in on this
+/// thread, and its address. Each generated function has one of these in
+/// a thread local, so calling it only looks it up again after a reload.
+///
+/// Versions start at 1, so the initial `(0, 0)` is never used.
+pub type SymbolCache = Cell<(usize, usize)>;
+
+std::thread_local! {
+    /// How many calls into a dylib are running on this thread.
+    static CALL_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Held for the duration of a call into a dylib, during which no dylib
+/// which is already loaded can be reloaded from the same thread, such
+/// as by a callback into the host.
+pub struct CallGuard(());
+
+impl CallGuard {
+    pub fn enter() -> Self {
+        CALL_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        CallGuard(())
+    }
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        CALL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+fn in_dylib_call() -> bool {
+    CALL_DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// A read lock on a loaded dylib.
+///
+/// The dylib can't be reloaded while this is held.
+pub struct LoadedLibrary<'a> {
+    functions: &'static [&'static str],
+    dylib_path: &'static Path,
+    state: RwLockReadGuard<'a, State>,
+}
+
+impl LoadedLibrary<'_> {
+    /// The version of the loaded dylib, which is different for every
+    /// load.
+    pub fn version(&self) -> usize {
+        self.state.version
+    }
+
+    /// Looks up the declared function `name` in the dylib, or in
+    /// `cache` if this thread has already looked it up in the same
+    /// version of the dylib.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the function pointer type of the exported function.
+    pub unsafe fn function<T: Copy>(&self, cache: &'static LocalKey<SymbolCache>, name: &str) -> T {
+        // The version can't change while `state` is locked, and every
+        // load gets a new one, so a cached address with the current
+        // version is from the library which is loaded now
+        match self.cached(cache, || self.resolve::<T>(name)) {
+            Ok(function) => function,
+            Err(_) if cfg!(feature = "strict") => abort_missing_symbol(name),
+            Err(error) => panic!("Failed to get symbol from dylib: {}", error),
+        }
+    }
+
+    /// Looks up the declared function `name` as with `function()`, or
+    /// returns `None` if the dylib doesn't have it.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the function pointer type of the exported function.
+    pub unsafe fn try_function<T: Copy>(
+        &self,
+        cache: &'static LocalKey<SymbolCache>,
+        name: &str,
+    ) -> Option<T> {
+        self.cached(cache, || self.resolve::<T>(name)).ok()
+    }
+
+    /// Looks up the declared function `name` as the given version of its
+    /// symbol, such as `V2` for `name@@V2`, or in `cache` as with
+    /// `function()`.
+    ///
+    /// Symbol versions are only supported on Linux with glibc. Elsewhere,
+    /// the plain name is looked up instead, with a warning.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the function pointer type of the exported function.
+    pub unsafe fn versioned_function<T: Copy>(
+        &self,
+        cache: &'static LocalKey<SymbolCache>,
+        name: &str,
+        version: &str,
+    ) -> T {
+        match self.try_versioned_function(cache, name, version) {
+            Some(function) => function,
+            None if cfg!(feature = "strict") => abort_missing_symbol(name),
+            None => panic!("Failed to get versioned symbol from dylib"),
+        }
+    }
+
+    /// Looks up the declared function `name` as with
+    /// `versioned_function()`, or returns `None` if the dylib doesn't
+    /// have that version of it.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the function pointer type of the exported function.
+    pub unsafe fn try_versioned_function<T: Copy>(
+        &self,
+        cache: &'static LocalKey<SymbolCache>,
+        name: &str,
+        version: &str,
+    ) -> Option<T> {
+        self.cached(cache, || {
+            self.resolve_versioned::<T>(name, version).ok_or(())
+        })
+        .ok()
+    }
+
+    unsafe fn cached<T: Copy, E>(
+        &self,
+        cache: &'static LocalKey<SymbolCache>,
+        resolve: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let version = self.state.version;
+        if let Ok((cached_version, address)) = cache.try_with(Cell::get) {
+            if cached_version == version {
+                return Ok(std::mem::transmute_copy(&address));
+            }
+        }
+
+        let function = resolve()?;
+        let address: usize = std::mem::transmute_copy(&function);
+        let _ = cache.try_with(|cache| cache.set((version, address)));
+        Ok(function)
+    }
+
+    unsafe fn resolve<T: Copy>(&self, name: &str) -> Result<T, std::io::Error> {
+        if !self.state.symbols.is_empty() {
+            if let Some(index) = self.functions.iter().position(|&function| function == name) {
+                return Ok(std::mem::transmute_copy(&self.state.symbols[index]));
+            }
+        }
+
+        get_symbol(self.state.library.as_ref().unwrap(), name)
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe fn resolve_versioned<T: Copy>(&self, name: &str, version: &str) -> Option<T> {
+        let path = self.state.copy.as_deref().unwrap_or(self.dylib_path);
+        let address = crate::loader::versioned_symbol(path, name, version)?;
+        Some(std::mem::transmute_copy(&address))
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    unsafe fn resolve_versioned<T: Copy>(&self, name: &str, version: &str) -> Option<T> {
+        diagnostics::warn(format_args!(
+            "symbol versions aren't supported on this platform, so `{}` is loaded instead of `{}@{}`",
+            name, name, version
+        ));
+        self.resolve::<T>(name).ok()
+    }
+}
+
+/// Checks that the dylib at `path` was built for the architecture of
+/// the running process, where its header says which it was built for.
+fn check_arch(path: &Path) -> Result<(), ReloadError> {
+    match dylib_arch(path) {
+        Some(found) if found != std::env::consts::ARCH => Err(ReloadError::ArchitectureMismatch {
+            path: path.to_owned(),
+            expected: std::env::consts::ARCH,
+            found,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// With the `strict` feature, aborts the process after warning about
+/// `error`, as a dylib which fails a check can't be called safely.
+/// Otherwise, it's returned as is.
+fn refuse(error: ReloadError) -> ReloadError {
+    if cfg!(feature = "strict") {
+        diagnostics::warn(format_args!(
+            "{}; aborting, as the `strict` feature is enabled",
+            error
+        ));
+        std::process::abort()
+    }
+    error
+}
+
+/// Aborts the process because the function `name` isn't in the loaded
+/// dylib, and it's `#[missing_symbol = "abort"]` or the `strict`
+/// feature is enabled.
+pub fn abort_missing_symbol(name: &str) -> ! {
+    diagnostics::warn(format_args!(
+        "`{}` couldn't be found in the dylib, so the process is aborting",
+        name
+    ));
+    std::process::abort()
+}
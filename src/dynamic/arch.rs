@@ -0,0 +1,85 @@
+use std::io::Read;
+use std::path::Path;
+
+/// How much of the dylib is read to find its architecture. A PE header
+/// further in than this is rare enough to not be checked.
+const HEADER_LEN: u64 = 4096;
+
+/// Returns the architecture the dylib at `path` was built for, named as
+/// in `std::env::consts::ARCH`, from its ELF, Mach-O or PE header.
+///
+/// This is `None` if the file can't be read, isn't in one of those
+/// formats, is a universal Mach-O binary holding several architectures,
+/// or is for an architecture dymod doesn't know the name of, in which
+/// case it isn't checked.
+pub fn dylib_arch(path: &Path) -> Option<&'static str> {
+    let mut header = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(HEADER_LEN).read_to_end(&mut header))
+        .ok()?;
+
+    match header.get(..4)? {
+        [0x7f, b'E', b'L', b'F'] => elf_arch(&header),
+        [0xcf, 0xfa, 0xed, 0xfe] | [0xce, 0xfa, 0xed, 0xfe] => macho_arch(&header),
+        [b'M', b'Z', ..] => pe_arch(&header),
+        _ => None,
+    }
+}
+
+fn elf_arch(header: &[u8]) -> Option<&'static str> {
+    let is_64 = *header.get(4)? == 2;
+    let machine = match header.get(5)? {
+        1 => u16::from_le_bytes(read(header, 18)?),
+        2 => u16::from_be_bytes(read(header, 18)?),
+        _ => return None,
+    };
+
+    Some(match machine {
+        3 => "x86",
+        62 => "x86_64",
+        40 => "arm",
+        183 => "aarch64",
+        8 if is_64 => "mips64",
+        8 => "mips",
+        20 => "powerpc",
+        21 => "powerpc64",
+        22 => "s390x",
+        243 if is_64 => "riscv64",
+        243 => "riscv32",
+        258 => "loongarch64",
+        _ => return None,
+    })
+}
+
+/// Reads a little-endian Mach-O header, which is all that Apple's
+/// current platforms use.
+fn macho_arch(header: &[u8]) -> Option<&'static str> {
+    Some(match u32::from_le_bytes(read(header, 4)?) {
+        7 => "x86",
+        0x0100_0007 => "x86_64",
+        12 => "arm",
+        0x0100_000c => "aarch64",
+        18 => "powerpc",
+        0x0100_0012 => "powerpc64",
+        _ => return None,
+    })
+}
+
+fn pe_arch(header: &[u8]) -> Option<&'static str> {
+    let offset = u32::from_le_bytes(read(header, 0x3c)?) as usize;
+    if header.get(offset..offset.checked_add(4)?)? != b"PE\0\0" {
+        return None;
+    }
+
+    Some(match u16::from_le_bytes(read(header, offset + 4)?) {
+        0x014c => "x86",
+        0x8664 => "x86_64",
+        0x01c0 | 0x01c4 => "arm",
+        0xaa64 => "aarch64",
+        _ => return None,
+    })
+}
+
+fn read<const N: usize>(header: &[u8], offset: usize) -> Option<[u8; N]> {
+    header.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::{diagnostics, DynamicModule};
+
+/// How often the autobuild thread checks the subcrate's source for
+/// changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Starts a thread which rebuilds and reloads `module` whenever a `.rs`
+/// file under `src` changes, with the `autobuild` feature.
+pub fn start(module: &'static DynamicModule, src: PathBuf) {
+    // Read before the thread starts, so that no change after the dylib
+    // was loaded is missed
+    let last_seen = source_signature(&src);
+    std::thread::Builder::new()
+        .name("dymod-autobuild".into())
+        .spawn(move || watch(module, &src, last_seen))
+        .expect("Failed to start dymod autobuild thread");
+}
+
+fn watch(module: &'static DynamicModule, src: &Path, mut last_seen: (Option<SystemTime>, usize)) {
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let signature = source_signature(src);
+        if signature == last_seen {
+            continue;
+        }
+        last_seen = signature;
+
+        // The old dylib stays loaded, unless it was loaded in place and
+        // had to be unloaded, so there's nothing to do but say why
+        if let Err(error) = module.rebuild() {
+            diagnostics::warn(format_args!("automatic rebuild failed: {}", error));
+        }
+    }
+}
+
+/// The latest modified time of any `.rs` file under `dir`, along with
+/// how many there are, so that removing one counts as a change too.
+fn source_signature(dir: &Path) -> (Option<SystemTime>, usize) {
+    let mut latest = None;
+    let mut count = 0;
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "rs") {
+                count += 1;
+                latest = latest.max(metadata.modified().ok());
+            }
+        }
+    }
+    (latest, count)
+}
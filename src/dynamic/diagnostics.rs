@@ -0,0 +1,22 @@
+use std::io::Write;
+use std::sync::{Mutex, PoisonError};
+
+/// Where warnings go, or stderr if this is `None`.
+static WRITER: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+pub fn set_diagnostics_writer(writer: Box<dyn Write + Send>) {
+    *WRITER.lock().unwrap_or_else(PoisonError::into_inner) = Some(writer);
+}
+
+/// Writes a warning about something which doesn't stop the dylib from
+/// loading, but may cause trouble.
+pub fn warn(message: std::fmt::Arguments) {
+    let mut writer = WRITER.lock().unwrap_or_else(PoisonError::into_inner);
+    match writer.as_mut() {
+        Some(writer) => {
+            let _ = writeln!(writer, "dymod: {}", message);
+            let _ = writer.flush();
+        }
+        None => eprintln!("dymod: {}", message),
+    }
+}
@@ -0,0 +1,74 @@
+use std::ffi::c_void;
+
+use libloading::Library;
+
+use super::get_symbol;
+
+/// Returns where each of `functions` is in `library`, as an offset from
+/// the start of the dylib it was loaded from, or `None` for those it
+/// doesn't export.
+///
+/// Unlike their addresses, which are different every time a dylib is
+/// loaded, these only change when the build moves a function, which is
+/// usually only when it or one before it has changed.
+pub fn symbol_offsets(library: &Library, functions: &[&str]) -> Vec<Option<usize>> {
+    functions
+        .iter()
+        .map(|function| {
+            let address = unsafe { get_symbol::<*mut c_void>(library, function) }.ok()?;
+            Some(address as usize - image_base(address).unwrap_or(0))
+        })
+        .collect()
+}
+
+/// Returns the address the dylib containing `address` was loaded at.
+#[cfg(unix)]
+fn image_base(address: *mut c_void) -> Option<usize> {
+    use std::ffi::{c_char, c_int};
+
+    #[repr(C)]
+    struct DlInfo {
+        fname: *const c_char,
+        fbase: *mut c_void,
+        sname: *const c_char,
+        saddr: *mut c_void,
+    }
+
+    extern "C" {
+        fn dladdr(address: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    let mut info = DlInfo {
+        fname: std::ptr::null(),
+        fbase: std::ptr::null_mut(),
+        sname: std::ptr::null(),
+        saddr: std::ptr::null_mut(),
+    };
+    match unsafe { dladdr(address, &mut info) } {
+        0 => None,
+        _ => Some(info.fbase as usize),
+    }
+}
+
+/// Returns the address the dylib containing `address` was loaded at,
+/// which is also its module handle.
+#[cfg(windows)]
+fn image_base(address: *mut c_void) -> Option<usize> {
+    const FROM_ADDRESS: u32 = 0x4;
+    const UNCHANGED_REFCOUNT: u32 = 0x2;
+
+    extern "system" {
+        fn GetModuleHandleExW(flags: u32, name: *const c_void, module: *mut *mut c_void) -> i32;
+    }
+
+    let mut module = std::ptr::null_mut();
+    match unsafe { GetModuleHandleExW(FROM_ADDRESS | UNCHANGED_REFCOUNT, address, &mut module) } {
+        0 => None,
+        _ => Some(module as usize),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn image_base(_address: *mut c_void) -> Option<usize> {
+    None
+}
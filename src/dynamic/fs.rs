@@ -0,0 +1,140 @@
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// The filesystem operations a module makes while reloading: copying the
+/// dylib, checking whether it's changed, and removing old copies.
+///
+/// Modules use `RealFs`, but tests can swap in an in-memory filesystem,
+/// to go through reloads without writing anything to disk.
+pub(crate) trait FsOps: Send + Sync {
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+
+    fn len(&self, path: &Path) -> io::Result<u64>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+}
+
+/// Operates on the real filesystem, with `std::fs`.
+pub(crate) struct RealFs;
+
+impl FsOps for RealFs {
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        Ok(std::fs::metadata(path)?.len())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+}
+
+/// Files which only exist in memory, each with nothing but a modified
+/// time, which is all a reload looks at. Every file is empty.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryFs {
+    files: std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, SystemTime>>,
+}
+
+#[cfg(test)]
+impl MemoryFs {
+    /// Creates or replaces the file at `path`, as a build would.
+    pub fn write(&self, path: &Path, modified: SystemTime) {
+        self.files().insert(path.to_owned(), modified);
+    }
+
+    /// Returns every file, sorted by path.
+    pub fn paths(&self) -> Vec<std::path::PathBuf> {
+        let mut paths: Vec<_> = self.files().keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    fn files(
+        &self,
+    ) -> std::sync::MutexGuard<'_, std::collections::HashMap<std::path::PathBuf, SystemTime>> {
+        self.files
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+#[cfg(test)]
+impl FsOps for MemoryFs {
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files();
+        let modified = *files.get(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_owned(), modified);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files();
+        let modified = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_owned(), modified);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.files()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.files()
+            .get(path)
+            .copied()
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        self.modified(path).map(|_| 0)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files().contains_key(path)
+    }
+
+    /// Does nothing, as there are only files.
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, PoisonError};
+
+/// The results of a `#[memoize]` function, by its arguments, for the
+/// version of the dylib they were returned by.
+pub struct Memo<K, V> {
+    results: Mutex<Option<(usize, HashMap<K, V>)>>,
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Memo {
+            results: Mutex::new(None),
+        }
+    }
+
+    /// Returns the result for `args`, if the function has already been
+    /// called with them in this `version` of the dylib.
+    pub fn get(&self, version: usize, args: &K) -> Option<V> {
+        match &*self.results() {
+            Some((cached_version, results)) if *cached_version == version => {
+                results.get(args).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// Stores the result for `args`, forgetting any results from other
+    /// versions of the dylib.
+    pub fn insert(&self, version: usize, args: K, result: V) {
+        let mut results = self.results();
+        match &mut *results {
+            Some((cached_version, results)) if *cached_version == version => {
+                results.insert(args, result);
+            }
+            _ => *results = Some((version, HashMap::from([(args, result)]))),
+        }
+    }
+
+    fn results(&self) -> std::sync::MutexGuard<'_, Option<(usize, HashMap<K, V>)>> {
+        self.results.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,73 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::ReloadError;
+
+use super::DynamicModule;
+
+/// A future which waits for a dylib to be reloaded on a background
+/// thread, so that an async executor isn't blocked by it.
+pub struct Ready {
+    reload: Option<Arc<Mutex<Reload>>>,
+}
+
+/// The state shared between a `Ready` and its reload thread.
+#[derive(Default)]
+struct Reload {
+    result: Option<Result<(), ReloadError>>,
+    waker: Option<Waker>,
+}
+
+impl Ready {
+    /// Returns a future which is complete immediately.
+    pub fn done() -> Self {
+        Ready { reload: None }
+    }
+
+    /// Starts reloading `module` on a new thread, unless another call
+    /// has loaded a version other than `seen` by then, so that calls
+    /// which all saw the same change only reload it once.
+    pub fn reload(module: &'static DynamicModule, seen: usize) -> Self {
+        let reload = Arc::new(Mutex::new(Reload::default()));
+        let shared = Arc::clone(&reload);
+
+        std::thread::Builder::new()
+            .name("dymod-reload".to_owned())
+            .spawn(move || {
+                let result = module.reload_from_source(None, Some(seen));
+                let mut reload = shared.lock().unwrap_or_else(PoisonError::into_inner);
+                reload.result = Some(result);
+                if let Some(waker) = reload.waker.take() {
+                    waker.wake();
+                }
+            })
+            .expect("Failed to spawn dymod reload thread");
+
+        Ready {
+            reload: Some(reload),
+        }
+    }
+}
+
+impl Future for Ready {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let reload = match &self.reload {
+            Some(reload) => reload,
+            None => return Poll::Ready(()),
+        };
+
+        let mut reload = reload.lock().unwrap_or_else(PoisonError::into_inner);
+        match reload.result.take() {
+            Some(Ok(())) => Poll::Ready(()),
+            Some(Err(error)) => panic!("Failed to reload dylib: {}", error),
+            None => {
+                reload.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
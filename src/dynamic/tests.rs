@@ -0,0 +1,329 @@
+//! Goes through the reload state machine on an in-memory filesystem,
+//! with the test binary itself standing in for each dylib, so nothing
+//! is written to disk or built.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use super::fs::{FsOps, MemoryFs};
+use super::{diagnostics, forward_panic, new_dylib_path, DynamicModule};
+use crate::{CleanupPolicy, CopyStrategy, DymodEvent, DymodLoader, Library, ReloadError};
+
+/// Opens the running test binary, whatever path it's given.
+struct ThisProcess;
+
+impl DymodLoader for ThisProcess {
+    fn load(&self, _path: &Path) -> Result<Library, ReloadError> {
+        Ok(Library::from(libloading::os::unix::Library::this()))
+    }
+}
+
+/// A module whose dylib has been built once, on a filesystem of its own.
+fn module(strategy: CopyStrategy) -> (&'static DynamicModule, &'static MemoryFs, PathBuf) {
+    let fs: &'static MemoryFs = Box::leak(Box::default());
+    let module: &'static DynamicModule = Box::leak(Box::new(
+        DynamicModule::new("/memory", "plugin", &[]).with_fs(fs),
+    ));
+    module.set_loader(ThisProcess);
+    module.set_copy_strategy(strategy);
+
+    let dylib = module.dylib_path().to_owned();
+    fs.write(&dylib, built_at(0));
+    (module, fs, dylib)
+}
+
+fn built_at(seconds: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + seconds)
+}
+
+#[test]
+fn each_reload_is_a_new_version() {
+    let (module, _, _) = module(CopyStrategy::Versioned);
+    assert_eq!(module.status().version, 0);
+
+    module.force_reload().unwrap();
+    assert_eq!(module.current_generation(), 1);
+
+    module.force_reload().unwrap();
+    assert_eq!(module.current_generation(), 2);
+    assert!(module.is_generation_live(2));
+    assert!(module.is_generation_live(1));
+}
+
+#[test]
+fn two_old_versions_are_retained_by_default() {
+    let (module, _, _) = module(CopyStrategy::Versioned);
+    for _ in 0..4 {
+        module.force_reload().unwrap();
+    }
+
+    assert!(!module.is_generation_live(1));
+    assert!(module.is_generation_live(2));
+    assert!(module.is_generation_live(3));
+    assert!(module.is_generation_live(4));
+}
+
+#[test]
+fn only_a_changed_modified_time_is_a_change() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.force_reload().unwrap();
+    assert!(!module.pending_reload());
+    assert!(!module.reload_if_modified().unwrap());
+
+    fs.write(&dylib, built_at(1));
+    assert!(module.pending_reload());
+    assert!(module.reload_if_changed().unwrap());
+    assert!(!module.pending_reload());
+    assert_eq!(module.current_generation(), 2);
+}
+
+#[test]
+fn pinned_module_ignores_changes_until_reloaded_explicitly() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.force_reload().unwrap();
+    module.set_pinned(true);
+
+    fs.write(&dylib, built_at(1));
+    assert!(!module.pending_reload());
+    assert!(!module.reload_if_changed().unwrap());
+    assert!(module.reload_if_modified().unwrap());
+    assert_eq!(module.current_generation(), 2);
+}
+
+#[test]
+fn only_the_loaded_copy_is_kept() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.set_max_retained_libraries(0);
+    module.force_reload().unwrap();
+    assert_eq!(fs.paths(), [dylib.clone(), module.copy_path(0)]);
+
+    module.force_reload().unwrap();
+    assert_eq!(fs.paths(), [dylib.clone(), module.copy_path(1)]);
+}
+
+#[test]
+fn dylib_is_loaded_in_place_without_copying() {
+    let (module, fs, dylib) = module(CopyStrategy::Never);
+    module.force_reload().unwrap();
+    module.force_reload().unwrap();
+
+    assert_eq!(fs.paths(), [dylib]);
+    assert_eq!(module.current_generation(), 2);
+}
+
+#[test]
+fn only_reloads_are_copied_on_reload() {
+    let (module, fs, dylib) = module(CopyStrategy::OnReload);
+    module.force_reload().unwrap();
+    assert_eq!(fs.paths(), [dylib.as_path()]);
+
+    module.force_reload().unwrap();
+    assert_eq!(fs.paths(), [dylib.clone(), module.copy_path(1)]);
+    assert_eq!(module.current_generation(), 2);
+}
+
+#[test]
+fn failed_reload_on_reload_keeps_the_version_loaded_in_place() {
+    let (module, fs, dylib) = module(CopyStrategy::OnReload);
+    module.force_reload().unwrap();
+
+    fs.remove_file(&dylib).unwrap();
+    assert!(matches!(
+        module.force_reload(),
+        Err(ReloadError::CopyFailed { .. })
+    ));
+    assert_eq!(module.current_generation(), 1);
+    assert!(module.is_generation_live(1));
+}
+
+#[test]
+fn version_loaded_in_place_can_be_retained() {
+    let (module, _, _) = module(CopyStrategy::OnReload);
+    module.set_max_retained_libraries(1);
+    module.force_reload().unwrap();
+    module.force_reload().unwrap();
+
+    assert!(module.is_generation_live(1));
+    assert!(module.is_generation_live(2));
+}
+
+#[test]
+fn failed_copy_keeps_the_old_version() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.force_reload().unwrap();
+
+    fs.remove_file(&dylib).unwrap();
+    match module.force_reload() {
+        Err(ReloadError::CopyFailed { path, .. }) => {
+            assert_eq!(path, module.copy_path(1))
+        }
+        other => panic!("Expected CopyFailed, got {:?}", other),
+    }
+    assert_eq!(module.current_generation(), 1);
+    assert!(module.status().last_error.is_some());
+    assert_eq!(fs.paths(), [module.copy_path(0)]);
+}
+
+#[test]
+fn new_dylib_is_renamed_into_place() {
+    let (module, fs, dylib) = module(CopyStrategy::AtomicRename);
+    module.force_reload().unwrap();
+    assert!(!module.pending_reload());
+
+    fs.write(&new_dylib_path(&dylib), built_at(1));
+    assert!(module.pending_reload());
+    module.force_reload().unwrap();
+
+    assert!(!module.pending_reload());
+    assert_eq!(
+        fs.paths(),
+        [dylib.clone(), module.copy_path(0), module.copy_path(1)]
+    );
+}
+
+#[test]
+fn old_copy_which_cant_be_removed_is_left_behind() {
+    let (module, fs, _) = module(CopyStrategy::Versioned);
+    module.set_max_retained_libraries(0);
+    module.force_reload().unwrap();
+
+    fs.remove_file(&module.copy_path(0)).unwrap();
+    module.force_reload().unwrap();
+    assert_eq!(module.current_generation(), 2);
+    assert!(module.status().last_error.is_none());
+}
+
+#[test]
+#[should_panic(expected = "Failed to delete old dylib")]
+fn strict_cleanup_panics_when_old_copy_cant_be_removed() {
+    let (module, fs, _) = module(CopyStrategy::Versioned);
+    module.set_cleanup_policy(CleanupPolicy::Strict);
+    module.set_max_retained_libraries(0);
+    module.force_reload().unwrap();
+
+    fs.remove_file(&module.copy_path(0)).unwrap();
+    let _ = module.force_reload();
+}
+
+#[test]
+fn dylib_info_is_read_from_the_dylib_which_was_loaded() {
+    let (module, fs, dylib) = module(CopyStrategy::AtomicRename);
+    assert_eq!(module.loaded_dylib_info(), None);
+
+    module.force_reload().unwrap();
+    let info = module.loaded_dylib_info().unwrap();
+    assert_eq!(info.path, dylib);
+    assert_eq!(info.modified, built_at(0));
+    assert_eq!(info.generation, 1);
+
+    fs.write(&new_dylib_path(&dylib), built_at(1));
+    module.force_reload().unwrap();
+    let info = module.loaded_dylib_info().unwrap();
+    assert_eq!(info.modified, built_at(1));
+    assert_eq!(info.generation, 2);
+}
+
+#[test]
+fn change_is_sent_as_an_event_before_reloading() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.force_reload().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    module.set_event_sender(sender);
+
+    assert!(!module.reload_if_changed().unwrap());
+    fs.write(&dylib, built_at(1));
+    assert!(module.reload_if_changed().unwrap());
+    assert_eq!(
+        receiver.try_iter().collect::<Vec<_>>(),
+        [
+            DymodEvent::ChangeDetected,
+            DymodEvent::ReloadStarted,
+            DymodEvent::ReloadSucceeded { version: 2 },
+        ]
+    );
+}
+
+#[test]
+fn retained_libraries_stay_live_until_the_limit() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.set_max_retained_libraries(2);
+    for _ in 0..4 {
+        module.force_reload().unwrap();
+    }
+
+    assert!(!module.is_generation_live(1));
+    assert!(module.is_generation_live(2));
+    assert!(module.is_generation_live(3));
+    assert!(module.is_generation_live(4));
+    assert_eq!(
+        fs.paths(),
+        [
+            dylib.clone(),
+            module.copy_path(1),
+            module.copy_path(2),
+            module.copy_path(3),
+        ]
+    );
+
+    module.set_max_retained_libraries(0);
+    assert!(!module.is_generation_live(3));
+    assert_eq!(fs.paths(), [dylib.clone(), module.copy_path(3)]);
+}
+
+#[test]
+fn modules_loading_the_same_dylib_copy_it_to_different_paths() {
+    let (first, _, dylib) = module(CopyStrategy::Versioned);
+    let (second, _, _) = module(CopyStrategy::Versioned);
+    assert_eq!(second.dylib_path(), dylib);
+    assert_ne!(first.copy_path(1), second.copy_path(1));
+}
+
+#[test]
+fn nothing_is_retained_without_a_copy() {
+    let (module, _, _) = module(CopyStrategy::Never);
+    module.set_max_retained_libraries(2);
+    module.force_reload().unwrap();
+    module.force_reload().unwrap();
+
+    assert!(!module.is_generation_live(1));
+    assert!(module.is_generation_live(2));
+}
+
+#[test]
+fn copies_are_made_in_the_copy_directory() {
+    let (module, fs, dylib) = module(CopyStrategy::Versioned);
+    module.set_copy_directory("/copies");
+    module.force_reload().unwrap();
+
+    let copy = module.copy_path(0);
+    assert!(copy.starts_with("/copies"));
+    assert_eq!(fs.paths(), [copy, dylib]);
+}
+
+/// Collects diagnostics written by dymod.
+#[derive(Clone, Default)]
+struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Diagnostics {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn forwarded_panic_is_a_warning_without_a_handler() {
+    let diagnostics = Diagnostics::default();
+    diagnostics::set_diagnostics_writer(Box::new(diagnostics.clone()));
+
+    let message = "panicked at src/lib.rs:1:1:\nattempt to divide by zero";
+    forward_panic(message.as_ptr(), message.len());
+
+    let diagnostics = String::from_utf8(diagnostics.0.lock().unwrap().clone()).unwrap();
+    assert!(diagnostics.contains("dymod: dylib panicked at src/lib.rs:1:1:"));
+}
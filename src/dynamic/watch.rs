@@ -0,0 +1,128 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer_opt, Config, DebounceEventResult, Debouncer};
+
+use super::diagnostics;
+
+/// Watches a dylib for changes with `notify-debouncer-mini`, which
+/// reports them on a background thread.
+///
+/// A change is only reported once the dylib has stopped changing for
+/// the debounce duration, so a build which writes the file several
+/// times, or an editor which saves by writing a temporary file and
+/// renaming it, results in a single reload.
+pub struct Watcher {
+    started: Once,
+    changed: AtomicBool,
+    debounce: Mutex<Duration>,
+
+    /// The file being watched, and the debouncer watching it, once the
+    /// watcher has started.
+    running: Mutex<Option<(PathBuf, Debouncer<RecommendedWatcher>)>>,
+}
+
+impl Watcher {
+    pub const fn new() -> Self {
+        Watcher {
+            started: Once::new(),
+            changed: AtomicBool::new(false),
+            debounce: Mutex::new(Duration::from_millis(200)),
+            running: Mutex::new(None),
+        }
+    }
+
+    /// Starts watching `path` if the watcher isn't already running.
+    ///
+    /// `loaded_time` is the modified time of the dylib which is
+    /// currently loaded, so that changes made before the watcher starts
+    /// aren't missed.
+    pub fn start(&'static self, path: &Path, loaded_time: Option<SystemTime>) {
+        self.started.call_once(|| {
+            let modified_time = std::fs::metadata(path).and_then(|metadata| metadata.modified());
+            if let Ok(modified_time) = modified_time {
+                if Some(modified_time) != loaded_time {
+                    self.changed.store(true, Ordering::Release);
+                }
+            }
+            self.watch(path.to_owned());
+        });
+    }
+
+    /// Returns whether the dylib has changed since this was last
+    /// called, or since the last `clear()`.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::AcqRel)
+    }
+
+    /// Forgets any change seen so far, because the dylib is about to be
+    /// reloaded anyway.
+    pub fn clear(&self) {
+        self.changed.store(false, Ordering::Release);
+    }
+
+    /// Sets the debounce duration, restarting the debouncer with it if
+    /// it's already running.
+    pub fn set_debounce(&'static self, debounce: Duration) {
+        *self.debounce.lock().unwrap_or_else(PoisonError::into_inner) = debounce;
+
+        let running = self.running().take();
+        if let Some((path, debouncer)) = running {
+            drop(debouncer);
+            self.watch(path);
+        }
+    }
+
+    fn debounce(&self) -> Duration {
+        *self.debounce.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn running(&self) -> MutexGuard<'_, Option<(PathBuf, Debouncer<RecommendedWatcher>)>> {
+        self.running.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Watches the directory `path` is in, rather than the file itself,
+    /// so that it's still watched after it's replaced by a rename, and
+    /// can be created after the watcher starts.
+    fn watch(&'static self, path: PathBuf) {
+        let name: Option<OsString> = path.file_name().map(ToOwned::to_owned);
+        let changed = &self.changed;
+        let config = Config::default().with_timeout(self.debounce());
+        let debouncer = new_debouncer_opt::<_, RecommendedWatcher>(
+            config,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    if events
+                        .iter()
+                        .any(|event| event.path.file_name() == name.as_deref())
+                    {
+                        changed.store(true, Ordering::Release);
+                    }
+                }
+                Err(error) => {
+                    diagnostics::warn(format_args!("error while watching dylib: {}", error))
+                }
+            },
+        );
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let watched = debouncer.and_then(|mut debouncer| {
+            debouncer
+                .watcher()
+                .watch(dir, RecursiveMode::NonRecursive)?;
+            Ok(debouncer)
+        });
+        match watched {
+            Ok(debouncer) => *self.running() = Some((path, debouncer)),
+            Err(error) => diagnostics::warn(format_args!(
+                "failed to watch {} for changes: {}; the dylib won't be reloaded when it changes",
+                dir.display(),
+                error
+            )),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// An error that occurred while reloading a dymod module.
+///
+/// When a reload fails, the previously loaded version of the dylib
+/// (if any) stays loaded, unless it was loaded in place, as it is by
+/// default on Linux, and had to be unloaded first.
+///
+/// Its `Display` message names the path involved, along with the
+/// likely fix.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReloadError {
+    /// The dylib could not be copied (or, for
+    /// `dymod::host::load_from_bytes`, written) to the path it's
+    /// loaded from.
+    CopyFailed {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// The dylib could not be read, by `dymod::host::LibrarySet::load`,
+    /// which loads a copy of its contents.
+    ReadFailed {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// The dylib could not be loaded.
+    LoadFailed {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    /// A declared function could not be found in the dylib.
+    SymbolNotFound { symbol: &'static str, path: PathBuf },
+
+    /// The dylib could not be built by a module's `rebuild()`.
+    ///
+    /// `path` is the directory of the subcrate which was built, and
+    /// `stderr` is what the build printed to stderr, such as compiler
+    /// errors, or empty if it didn't run.
+    BuildFailed {
+        path: PathBuf,
+        error: std::io::Error,
+        stderr: String,
+    },
+
+    /// The dylib was reloaded from a thread which is running one of
+    /// the functions of a dymod module, such as from a callback passed
+    /// to it.
+    ReentrantReload { path: PathBuf },
+
+    /// A declared function takes a different number of arguments than
+    /// the one in the dylib, as exported by `dymod::arity!()`.
+    ArityMismatch {
+        symbol: &'static str,
+        path: PathBuf,
+        expected: u8,
+        found: u8,
+    },
+
+    /// A declared function has a different ABI than the one in the
+    /// dylib, as exported by `dymod::arity!()`, such as one which isn't
+    /// `extern "C"`.
+    AbiMismatch {
+        function: &'static str,
+        path: PathBuf,
+        expected: &'static str,
+    },
+
+    /// The dylib was built with `dymod::abi_magic!()` for a different
+    /// version of dymod.
+    AbiVersionMismatch {
+        path: PathBuf,
+        expected: u64,
+        found: u64,
+    },
+
+    /// The dylib was built for a different architecture than the
+    /// running process, such as by a build for another target.
+    ArchitectureMismatch {
+        path: PathBuf,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    /// A `#[runtime_path]` module was used before its `load_from()`
+    /// function said where its dylib is.
+    NoDylibPath { module: &'static str },
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReloadError::CopyFailed { path, error } => write!(
+                f,
+                "failed to copy dylib to {}: {}; \
+                 check that its directory exists and is writable, \
+                 or load it in place with CopyStrategy::Never",
+                path.display(),
+                error
+            ),
+            ReloadError::ReadFailed { path, error } => write!(
+                f,
+                "failed to read dylib at {}: {}; \
+                 check that it exists and is readable",
+                path.display(),
+                error
+            ),
+            ReloadError::LoadFailed { path, error } => write!(
+                f,
+                "failed to load dylib at {}: {}; \
+                 did you run `cargo build` in the subcrate directory \
+                 and set crate-type = [\"dylib\"]?",
+                path.display(),
+                error
+            ),
+            ReloadError::SymbolNotFound { symbol, path } => write!(
+                f,
+                "function `{}` not found in dylib at {}; \
+                 check that it's `#[no_mangle] pub extern \"C\"` in the subcrate, \
+                 and that the subcrate has been rebuilt since it was added",
+                symbol,
+                path.display()
+            ),
+            ReloadError::BuildFailed {
+                path,
+                error,
+                stderr,
+            } => {
+                write!(
+                    f,
+                    "failed to build subcrate in {}: {}",
+                    path.display(),
+                    error
+                )?;
+                if stderr.is_empty() {
+                    write!(f, "; is cargo installed and on the PATH?")
+                } else {
+                    write!(f, "; fix the errors below, then rebuild\n{}", stderr)
+                }
+            }
+            ReloadError::ReentrantReload { path } => write!(
+                f,
+                "can't reload dylib at {} from inside a call to it; \
+                 reload once the dymod function has returned, \
+                 such as with reload_on_drop()",
+                path.display()
+            ),
+            ReloadError::ArityMismatch {
+                symbol,
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "function `{}` is declared with {} arguments, \
+                 but takes {} in dylib at {}; \
+                 update its signature in the dymod! block to match the subcrate",
+                symbol,
+                expected,
+                found,
+                path.display()
+            ),
+            ReloadError::AbiMismatch {
+                function,
+                path,
+                expected,
+            } => write!(
+                f,
+                "function `{}` in dylib at {} isn't `extern \"{}\"`; \
+                 add it to the function in the subcrate",
+                function,
+                path.display(),
+                expected
+            ),
+            ReloadError::AbiVersionMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "dylib at {} was built for a different version of dymod \
+                 (ABI {:#x}, expected {:#x}); \
+                 use the same version of dymod in the subcrate and rebuild it",
+                path.display(),
+                found,
+                expected
+            ),
+            ReloadError::ArchitectureMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "dylib at {} was built for {}, but this process is {}; \
+                 it may be left over from a build for another target, \
+                 so rebuild the subcrate for this one",
+                path.display(),
+                found,
+                expected
+            ),
+            ReloadError::NoDylibPath { module } => write!(
+                f,
+                "dymod module `{}` has no dylib to load yet; \
+                 call its load_from() function with the path of one first",
+                module
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReloadError::CopyFailed { error, .. }
+            | ReloadError::ReadFailed { error, .. }
+            | ReloadError::LoadFailed { error, .. }
+            | ReloadError::BuildFailed { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
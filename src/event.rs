@@ -0,0 +1,19 @@
+/// Something which happened to a dymod module's dylib, as sent to the
+/// channel given to its `set_event_sender()` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DymodEvent {
+    /// The dylib changed since it was loaded, so it's about to be
+    /// reloaded.
+    ChangeDetected,
+
+    /// The dylib is being loaded or reloaded.
+    ReloadStarted,
+
+    /// The dylib was loaded, as the given version.
+    ReloadSucceeded { version: usize },
+
+    /// The dylib failed to load, with the same error as in
+    /// `status().last_error`.
+    ReloadFailed { error: String },
+}
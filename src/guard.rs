@@ -0,0 +1,26 @@
+use crate::ReloadError;
+
+/// Reloads a dymod module when it's dropped, as returned by the
+/// module's `reload_on_drop()` function.
+///
+/// Dropping can't return an error, so if reloading fails, the error is
+/// ignored and the old dylib stays loaded, unless it was loaded in
+/// place and had to be unloaded first. It can still be read from
+/// the module's `status().last_error`.
+#[must_use = "the module is reloaded when this is dropped"]
+pub struct ReloadOnDrop {
+    reload: fn() -> Result<(), ReloadError>,
+}
+
+impl ReloadOnDrop {
+    #[doc(hidden)]
+    pub const fn new(reload: fn() -> Result<(), ReloadError>) -> Self {
+        ReloadOnDrop { reload }
+    }
+}
+
+impl Drop for ReloadOnDrop {
+    fn drop(&mut self) {
+        let _ = (self.reload)();
+    }
+}
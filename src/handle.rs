@@ -0,0 +1,110 @@
+/// Shortens the lifetime of a value returned by a dymod function to that
+/// of a borrow of the module's `DynModule` handle.
+///
+/// A `&'static str` returned by a dynamically linked function really
+/// only lives until the dylib is reloaded. Calling the function through
+/// the handle returns a `&'a str` instead, borrowing the handle, and as
+/// reloading through the handle takes `&mut self`, the borrow checker
+/// refuses to reload while it's still held.
+///
+/// This is implemented for the primitive types, references, `Option`s
+/// of references and raw pointers, along with `String`, `Vec` and `Box`
+/// when std is available. A function returning any other type can
+/// still be called through the handle as long as the type implements
+/// it too, which for an owned type only needs to return itself:
+///
+/// ```
+/// pub struct Score(u32);
+///
+/// impl<'a> dymod::Reborrow<'a> for Score {
+///     type Output = Score;
+///
+///     fn reborrow(self) -> Score {
+///         self
+///     }
+/// }
+/// ```
+pub trait Reborrow<'a> {
+    /// The type returned through the handle.
+    type Output;
+
+    /// Converts the returned value.
+    fn reborrow(self) -> Self::Output;
+}
+
+impl<'a, T: ?Sized> Reborrow<'a> for &'static T {
+    type Output = &'a T;
+
+    fn reborrow(self) -> &'a T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> Reborrow<'a> for &'static mut T {
+    type Output = &'a mut T;
+
+    fn reborrow(self) -> &'a mut T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> Reborrow<'a> for Option<&'static T> {
+    type Output = Option<&'a T>;
+
+    fn reborrow(self) -> Option<&'a T> {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> Reborrow<'a> for Option<&'static mut T> {
+    type Output = Option<&'a mut T>;
+
+    fn reborrow(self) -> Option<&'a mut T> {
+        self
+    }
+}
+
+/// Implements `Reborrow` for types which don't borrow anything, by
+/// returning them unchanged.
+macro_rules! reborrow_owned {
+    ($(impl[$($generics: tt)*] $type: ty;)*) => {
+        $(
+        impl<'a, $($generics)*> Reborrow<'a> for $type {
+            type Output = $type;
+
+            fn reborrow(self) -> $type {
+                self
+            }
+        }
+        )*
+    };
+}
+
+reborrow_owned! {
+    impl[] ();
+    impl[] bool;
+    impl[] char;
+    impl[] u8;
+    impl[] u16;
+    impl[] u32;
+    impl[] u64;
+    impl[] u128;
+    impl[] usize;
+    impl[] i8;
+    impl[] i16;
+    impl[] i32;
+    impl[] i64;
+    impl[] i128;
+    impl[] isize;
+    impl[] f32;
+    impl[] f64;
+    impl[T: ?Sized] *const T;
+    impl[T: ?Sized] *mut T;
+}
+
+#[cfg(not(feature = "no_std"))]
+reborrow_owned! {
+    impl[] String;
+    impl[T: 'static] Vec<T>;
+    impl[T: ?Sized + 'static] Box<T>;
+}
@@ -0,0 +1,270 @@
+//! Loading dylibs which don't come from a `dymod!` module.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libloading::{Library, Symbol};
+
+use crate::ReloadError;
+
+/// A dylib loaded with [`load_from_bytes`].
+///
+/// This dereferences to a `dymod::Library`, so symbols are looked up
+/// the same way as in a dymod module's `with_library`. The dylib is
+/// unloaded, and any file it was written to removed, when this is
+/// dropped, so symbols can't outlive it.
+pub struct LoadedLibrary {
+    // Declared first, so that the dylib is unloaded before its backing
+    // file is removed.
+    library: Library,
+    _backing: Backing,
+}
+
+impl Deref for LoadedLibrary {
+    type Target = Library;
+
+    fn deref(&self) -> &Library {
+        &self.library
+    }
+}
+
+enum Backing {
+    #[cfg(target_os = "linux")]
+    Memfd {
+        _file: File,
+    },
+    TempFile {
+        _file: TempFile,
+    },
+}
+
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Loads a dylib from its contents in memory.
+///
+/// On Linux, the bytes are written to an anonymous in-memory file, so
+/// they never touch the disk. Elsewhere, or if the kernel doesn't
+/// support that, they are written to a new file in the temp directory
+/// which only the current user can read, and which is removed when the
+/// `LoadedLibrary` is dropped.
+///
+/// # Safety
+///
+/// Loading a dylib runs its initialisation code, so the bytes must be
+/// a dylib you trust, built for the current target.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let library = unsafe { dymod::host::load_from_bytes(&bytes)? };
+/// let count_sheep: dymod::Symbol<extern "C" fn(u32) -> &'static str> =
+///     unsafe { library.get(b"count_sheep")? };
+/// ```
+pub unsafe fn load_from_bytes(bytes: &[u8]) -> Result<LoadedLibrary, ReloadError> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(memfd) = memfd::create() {
+            let path = memfd::path(&memfd);
+            write_all(&memfd, &path, bytes)?;
+            let library = load(&path)?;
+            return Ok(LoadedLibrary {
+                library,
+                _backing: Backing::Memfd { _file: memfd },
+            });
+        }
+    }
+
+    let (file, temp_file) = create_temp_file()?;
+    write_all(&file, &temp_file.0, bytes)?;
+    drop(file);
+
+    let library = load(&temp_file.0)?;
+    Ok(LoadedLibrary {
+        library,
+        _backing: Backing::TempFile { _file: temp_file },
+    })
+}
+
+/// Several versions of a dylib, loaded side by side under labels.
+///
+/// Each dylib is loaded from a private copy of its contents, so two
+/// builds of the same path can be compared, such as the version a
+/// dymod module has loaded and the one it's about to reload. The dymod
+/// module keeps using its own dylib regardless.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut versions = dymod::host::LibrarySet::new();
+/// unsafe { versions.load("old", subcrate::dylib_path())? };
+///
+/// // ...rebuild the subcrate...
+/// unsafe { versions.load("new", subcrate::dylib_path())? };
+///
+/// for label in ["old", "new"] {
+///     let position = unsafe {
+///         versions.call_in(label, "animate_from_to", |f: extern "C" fn(f32, f32) -> f32| {
+///             f(0.0, 1.0)
+///         })
+///     };
+///     println!("{}: {:?}", label, position);
+/// }
+/// ```
+#[derive(Default)]
+pub struct LibrarySet {
+    libraries: HashMap<String, LoadedLibrary>,
+}
+
+impl LibrarySet {
+    /// Creates a set with no dylibs loaded.
+    pub fn new() -> Self {
+        LibrarySet::default()
+    }
+
+    /// Loads a copy of the dylib at `path` under `label`, replacing
+    /// and unloading any dylib which already had that label.
+    ///
+    /// # Safety
+    ///
+    /// Loading a dylib runs its initialisation code, so it must be a
+    /// dylib you trust. Nothing obtained from a dylib this replaces may
+    /// still be in use.
+    pub unsafe fn load(
+        &mut self,
+        label: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<(), ReloadError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|error| ReloadError::ReadFailed {
+            path: path.to_owned(),
+            error,
+        })?;
+        let library = load_from_bytes(&bytes)?;
+        self.libraries.insert(label.into(), library);
+        Ok(())
+    }
+
+    /// Returns the dylib loaded under `label`, if any.
+    pub fn get(&self, label: &str) -> Option<&LoadedLibrary> {
+        self.libraries.get(label)
+    }
+
+    /// Unloads the dylib loaded under `label`, returning whether there
+    /// was one.
+    ///
+    /// # Safety
+    ///
+    /// Nothing obtained from the dylib may still be in use.
+    pub unsafe fn remove(&mut self, label: &str) -> bool {
+        self.libraries.remove(label).is_some()
+    }
+
+    /// Returns the labels of every loaded dylib, in no particular order.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.libraries.keys().map(String::as_str)
+    }
+
+    /// Looks up the function `symbol` in the dylib loaded under
+    /// `label`, and passes it to `call`. Returns `None` if there's no
+    /// dylib with that label, or it has no such function.
+    ///
+    /// # Safety
+    ///
+    /// `F` must be the function pointer type of the exported function,
+    /// and `call` mustn't keep it.
+    pub unsafe fn call_in<F: Copy, R>(
+        &self,
+        label: &str,
+        symbol: &str,
+        call: impl FnOnce(F) -> R,
+    ) -> Option<R> {
+        let library = self.get(label)?;
+        let function: Symbol<F> = library.get(symbol.as_bytes()).ok()?;
+        Some(call(*function))
+    }
+}
+
+fn write_all(mut file: &File, path: &Path, bytes: &[u8]) -> Result<(), ReloadError> {
+    file.write_all(bytes)
+        .map_err(|error| ReloadError::CopyFailed {
+            path: path.to_owned(),
+            error,
+        })
+}
+
+fn load(path: &Path) -> Result<Library, ReloadError> {
+    Library::new(path.as_os_str()).map_err(|error| ReloadError::LoadFailed {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+/// Creates a new file in the temp directory, readable only by the
+/// current user where the platform supports it.
+fn create_temp_file() -> Result<(File, TempFile), ReloadError> {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "{}dymod-{}-{}{}",
+        DLL_PREFIX,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        DLL_SUFFIX
+    ));
+
+    match options.open(&path) {
+        Ok(file) => Ok((file, TempFile(path))),
+        Err(error) => Err(ReloadError::CopyFailed { path, error }),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod memfd {
+    use std::fs::File;
+    use std::os::raw::{c_char, c_int, c_uint};
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+    use std::path::PathBuf;
+
+    const MFD_CLOEXEC: c_uint = 1;
+
+    extern "C" {
+        fn memfd_create(name: *const c_char, flags: c_uint) -> c_int;
+    }
+
+    /// Creates an anonymous in-memory file, or returns `None` if the
+    /// kernel doesn't support them.
+    pub fn create() -> Option<File> {
+        let fd = unsafe { memfd_create(c"dymod".as_ptr(), MFD_CLOEXEC) };
+        if fd < 0 {
+            None
+        } else {
+            Some(unsafe { File::from_raw_fd(fd) })
+        }
+    }
+
+    /// Returns a path through which `file` can be opened by name.
+    pub fn path(file: &File) -> PathBuf {
+        PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(clippy::needless_doctest_main)]
+#![cfg_attr(feature = "no_std", no_std)]
 
 //! # dymod
 //!
@@ -34,6 +35,49 @@
 //! crate-type = ["dylib"]
 //! ```
 //!
+//! If you forget this, the dylib won't be built, and this only shows
+//! up when your code tries to load it. To get a warning at build time
+//! instead, add dymod as a build dependency and call
+//! `dymod::build::check_crate_type` from your `build.rs`:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     dymod::build::check_crate_type("subcrate");
+//! }
+//! ```
+//!
+//! If you're starting from scratch, `dymod::build::scaffold("subcrate")`
+//! creates this layout for you, along with a `Cargo.toml` that builds a
+//! dylib. It never overwrites existing files, so it can stay in your
+//! `build.rs`.
+//!
+//! Types passed between the host and the subcrate, such as a
+//! `GameState`, are best defined once, in a third crate which both
+//! depend on, rather than copied into each, where they can drift
+//! apart. Even then, the two could be built with different versions of
+//! it. To get a warning when that happens, name the shared crate in
+//! `dymod::build::check_shared_dependency`:
+//!
+//! ```rust,ignore
+//! // build.rs
+//! fn main() {
+//!     dymod::build::check_shared_dependency("subcrate", "game_types");
+//! }
+//! ```
+//!
+//! It compares the versions in the `Cargo.lock` of each, so it only
+//! checks the subcrate once it has been built.
+//!
+//! Or, to define them in the subcrate itself, make it a dependency of
+//! the host, as described in [Depending on the
+//! subcrate](#depending-on-the-subcrate).
+//!
+//! A `cdylib` works just as well, and the dylib has the same file
+//! name either way. It's usually the better choice: it only exports
+//! your `#[no_mangle]` functions, and it doesn't depend on the Rust
+//! standard library being available as a separate dylib at runtime.
+//!
 //! Now you need to add the code that you want to hotswap. Any
 //! functions should be `pub extern "C"` and `#[no_mangle]`.
 //!
@@ -76,6 +120,178 @@
 //! }
 //! ```
 //!
+//! If loading fails because the dylib can't be found, check where dymod
+//! is looking for it. Each dynamically linked module has a `dylib_path()`
+//! function which returns the path, and marking the module
+//! `#[debug_path]` also generates a `RESOLVED_DYLIB_PATH` constant
+//! containing the same path, worked out at compile time:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[debug_path]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//!
+//! println!("Loading from {}", subcrate::RESOLVED_DYLIB_PATH);
+//! ```
+//!
+//! Neither is generated when the module is linked statically or stubbed
+//! out, as there's no dylib to find.
+//!
+//! By default, the path is under the host crate's directory, as it was
+//! when the program was built, so it only exists on the machine that
+//! built it. To run a debug build somewhere
+//! else, mark the module `#[path_relative_to = "exe"]`, and dymod looks
+//! for the dylib (such as `libsubcrate.so`) next to the executable
+//! instead:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[path_relative_to = "exe"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! The default is `#[path_relative_to = "manifest"]`. A module relative
+//! to the executable can't also be `#[debug_path]`, as its path isn't
+//! known until it runs.
+//!
+//! For other layouts, give the whole path as a template with
+//! `#[dylib = "..."]`, instead of either directory:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../plugins/subcrate/src/lib.rs"]
+//!     #[dylib = "{manifest}/plugins/{name}/target/{profile}/{prefix}{name}{suffix}"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Its placeholders are filled in when the program runs:
+//!
+//! -   `{manifest}` is the host crate's directory, as it was when the
+//!     program was built.
+//! -   `{name}` is the name of the module.
+//! -   `{profile}` is `debug` or `release`, whichever the host was built
+//!     with, or `release` for a module with
+//!     `#[dylib_build_profile = "release"]`.
+//! -   `{prefix}` and `{suffix}` are the platform's dylib prefix and
+//!     suffix, such as `lib` and `.so` on Linux.
+//! -   `{target_dir}` is `CARGO_TARGET_DIR` if it's set, or the
+//!     `target` directory of the subcrate otherwise.
+//!
+//! Any other placeholder fails to compile, when the module is
+//! dynamically linked:
+//!
+//! ```rust,compile_fail
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[dylib = "{manifest}/{name}/target/{mode}/{prefix}{name}{suffix}"]
+//!     pub mod subcrate {}
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! A templated module can't be `#[debug_path]` either.
+//!
+//! When cross-compiling, cargo puts a subcrate built with `--target`
+//! into a directory named after the target. Mark the module with
+//! `#[target = "..."]`, and dymod builds the subcrate for that target
+//! and looks for its dylib there, such as under
+//! `subcrate/target/aarch64-linux-android/debug`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[target = "aarch64-linux-android"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Without it, the dylib is looked for where cargo builds for the host.
+//!
+//! To profile the subcrate while still hotswapping it, build it with
+//! optimizations by marking the module
+//! `#[dylib_build_profile = "release"]`. The host stays a debug build,
+//! and reloads as usual, but the dylib is looked for in
+//! `subcrate/target/release`, and `rebuild()` and the `autobuild`
+//! feature build it with `cargo build --release`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[dylib_build_profile = "release"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Then edit the subcrate, and either call `rebuild()` or run
+//! `cargo build --release` in its directory, and the next call uses the
+//! optimized build. This doesn't change how the module is linked: it's
+//! still linked statically in a release build of the host, unless it's
+//! `#[dynamic_in_release]`.
+//!
+//! Before loading a dylib, dymod reads its ELF, Mach-O or PE header,
+//! and if it was built for a different architecture than the running
+//! program, such as one left over from a build for another target, the
+//! reload fails with `ReloadError::ArchitectureMismatch`, naming both,
+//! rather than with whatever the platform's loader makes of it.
+//!
+//! For any other layout, such as a path read from a config file, pass a
+//! function which returns the path to `set_path_resolver()`. It's
+//! called the next time the path is needed, instead of looking in either
+//! directory, and its result is used from then on:
+//!
+//! ```rust,ignore
+//! subcrate::set_path_resolver(|| config().plugin_dir.join("libsubcrate.so"));
+//! ```
+//!
+//! When there's no subcrate at all, such as for a plugin the user picks
+//! while the program runs, mark the module `#[runtime_path]` instead of
+//! giving it a `#[path]`. It's never linked statically, even in release
+//! mode, and nothing is loaded until its `load_from()` function is
+//! called with the path of a dylib. Until then, calling its functions
+//! panics, and reloading it returns `ReloadError::NoDylibPath`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[runtime_path]
+//!     pub mod plugin {
+//!         fn plugin_name() -> &'static str;
+//!     }
+//! }
+//!
+//! plugin::load_from(chosen_plugin_file)?;
+//! println!("Loaded {}", plugin::plugin_name());
+//! ```
+//!
+//! Any dynamic module can call `load_from()`, which loads the dylib at
+//! that path straight away and keeps reloading it from there.
+//!
+//! To try out a single build instead, such as a colleague's, or one
+//! from a bisect, call `reload_from()`. It loads the dylib at that path
+//! once, but leaves the module's path as it was, so the next reload,
+//! such as when the usual dylib is rebuilt, goes back to it:
+//!
+//! ```rust,ignore
+//! subcrate::reload_from("/tmp/libsubcrate-before-refactor.so")?;
+//! ```
+//!
 //! ## Safety
 //!
 //! In release mode, the module you specify is linked statically
@@ -94,11 +310,98 @@
 //! -   Unless both crates use the system allocator (which is luckily
 //!     the default since Rust 1.32.0) then dropping data that
 //!     was allocated by the other crate will cause a segfault.
+//!     Using `dymod::alloc_marker!()` at the root of both crates, and
+//!     `#[checked(alloc)]` on the module, warns about this on reload.
 //! -   If you change the definition of a struct on either side of
 //!     the boundary, you could get undefined behaviour. (This
 //!     includes adding or removing enum variants.)
 //! -   If you specify the function signatures incorrectly in the
-//!     `dymod!` macro, you will get undefined behaviour.
+//!     `dymod!` macro, you will get undefined behaviour. (When the
+//!     module is statically linked, the signatures are checked at
+//!     compile time, so building in release mode will catch this.)
+//!
+//! One thing dymod does guard against is reloading a dylib while its own
+//! code is still running. If the dylib calls back into the host, and the
+//! host tries to reload from inside that callback, `force_reload()` returns
+//! `ReloadError::ReentrantReload` instead (as does `try_reload()`, and
+//! `reload()` panics, if the dylib has changed). A
+//! changed dylib isn't reloaded automatically in the meantime either. No
+//! dylib which is already loaded can be reloaded from a thread until its
+//! call into a dymod function has returned.
+//!
+//! The generated functions can be called from any number of threads at
+//! once, and the module reloaded from any of them. Each call holds a read
+//! lock on the loaded dylib until it returns, so a reload on another
+//! thread waits for calls into the old dylib to finish before unloading
+//! it, and calls made in the meantime wait for the new one. Anything
+//! returned which borrows from the dylib, such as a `&'static str`
+//! pointing into it, is still only valid until the next reload.
+//!
+//! To tell whether it still is, record the module's
+//! `current_generation()` along with it. Each load of the dylib is a
+//! new generation, and `is_generation_live()` returns whether the one
+//! it came from is still loaded:
+//!
+//! ```rust,ignore
+//! // Before the call, so a reload in between can only make it look stale
+//! let generation = subcrate::current_generation();
+//! let name = subcrate::count_sheep(3);
+//!
+//! // Later, possibly after a reload
+//! if subcrate::is_generation_live(generation) {
+//!     println!("{}", name);
+//! }
+//! ```
+//!
+//! By default, the last 2 old dylibs stay loaded after each reload, along
+//! with their copies, so a borrowed value stays valid across up to 2
+//! reloads. Once there are more than that, the oldest is unloaded and its
+//! copy removed, which invalidates anything still borrowed from it. To
+//! keep values for longer, or to unload each old dylib straight away,
+//! call `set_max_retained_libraries(n)`, where `n` must be more than the
+//! number of reloads any such value is kept across. An old dylib can only
+//! be retained if it's still loaded when the new one loads, so nothing is
+//! retained when it's loaded in place, as it is by default on Linux; see
+//! "Loading without copying" below.
+//!
+//! To have the compiler check this instead, call the functions through
+//! the module's `DynModule` handle. Its methods return values which
+//! borrow the handle, such as a `&str` rather than a `&'static str`,
+//! and its `reload()`, `try_reload()`, `force_reload()` and
+//! `reload_if_changed()` methods take `&mut self`, so holding on to one
+//! across a reload doesn't compile:
+//!
+//! ```rust,compile_fail,E0502
+//! # use dymod::dymod;
+//! #
+//! # dymod! {
+//! #     #[path = "../subcrate/src/lib.rs"]
+//! #     pub mod subcrate {
+//! #         fn count_sheep(sheep: u32) -> &'static str;
+//! #     }
+//! # }
+//! #
+//! # fn main() -> Result<(), dymod::ReloadError> {
+//! let mut handle = subcrate::DynModule::take().expect("Only taken once");
+//!
+//! let name = handle.count_sheep(3);
+//! handle.reload_if_changed()?; // Error: `handle` is still borrowed by `name`
+//! println!("{}", name);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! There's only one handle to each module, which `DynModule::take()`
+//! returns the first time it's called, so a second handle can't reload
+//! the dylib while something borrowed from the first is still in use.
+//!
+//! Its methods never reload the dylib themselves, but reloads made any
+//! other way, such as by calling the module's functions directly or
+//! with `dymod::trigger_reload()`, aren't prevented by it. A function
+//! whose return type isn't a primitive, a reference, a pointer, a
+//! `String`, a `Vec` or a `Box` can only be called through the handle
+//! if the type implements `dymod::Reborrow`. When the module is linked
+//! statically, the handle has no reload methods, like the module itself.
 //!
 //! Because of these limitations, it is recommended that you use
 //! a small number of dynamic functions, and pass types which are
@@ -119,58 +422,2176 @@
 //! application state at runtime, but the interface is simple enough
 //! that it is easy to maintain.
 //!
+//! Fixed-size arrays, such as a `[f32; 16]` matrix, can be passed and
+//! returned by value. Large arrays are returned through a hidden
+//! pointer rather than in registers, but both sides are compiled by
+//! rustc from the same signature, so they always agree on how. Rust
+//! warns that arrays aren't FFI-safe, since C can't pass them by value,
+//! which you can silence with `#[allow(improper_ctypes_definitions)]`
+//! on the function in your subcrate.
+//!
+//! ## Documentation and attributes
+//!
+//! Doc comments and other attributes on the module, or on any of
+//! its functions, are forwarded to the generated items. This way,
+//! `cargo doc` can document your hotswappable modules:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     /// Code for counting things, which can be hotswapped.
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         /// Describes how many sheep there are.
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! In release mode, the module attributes are applied to the
+//! statically linked module, and the functions are documented by
+//! the subcrate's own source. Lint attributes apply to the subcrate's
+//! code too, so `#[allow(dead_code)]` on the module quiets warnings
+//! about functions in the subcrate which the `dymod!` block doesn't
+//! declare, without editing the subcrate.
+//!
+//! ## Interface files
+//!
+//! A long list of functions can be kept in a file of its own, rather
+//! than in the `dymod!` block. Mark the module with
+//! `#[interface = "..."]`, and leave its body empty:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[interface = "../subcrate/interface.rs"]
+//!     pub mod subcrate {}
+//! }
+//! ```
+//!
+//! The file holds the functions inside `dymod::interface!`, written
+//! just as they would be in the module, along with any of their doc
+//! comments and attributes:
+//!
+//! ```rust,ignore
+//! // subcrate/interface.rs
+//! dymod::interface! {
+//!     /// Describes how many sheep there are.
+//!     fn count_sheep(sheep: u32) -> &'static str;
+//! }
+//! ```
+//!
+//! As with `include!()`, the path is relative to the file with the
+//! `dymod!` block. A module with an interface file can't declare any
+//! functions of its own, which fails to compile.
+//!
+//! The subcrate can use the same file, to check that it exports every
+//! function with the signature the host expects, as an `extern "C"`
+//! function of the same name. This fails to compile if any of them
+//! don't match:
+//!
+//! ```rust,ignore
+//! // subcrate/src/lib.rs
+//! dymod::check_interface!("../interface.rs");
+//! ```
+//!
+//! ## Depending on the subcrate
+//!
+//! A module with a `#[path]` includes the subcrate's source when it's
+//! linked statically, but not when it's loaded dynamically, so any types
+//! in its signatures have to be defined next to the `dymod!` block too,
+//! and kept in step with the subcrate's. Instead, the host can depend on
+//! the subcrate like any other crate, and name it with
+//! `#[dependency]`:
+//!
+//! ```toml
+//! # Cargo.toml
+//! [dependencies]
+//! subcrate = { path = "subcrate" }
+//! ```
+//!
+//! ```toml
+//! # subcrate/Cargo.toml
+//! [lib]
+//! crate-type = ["rlib", "cdylib"]
+//! ```
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[dependency = subcrate]
+//!     pub mod subcrate {
+//!         fn advance(state: GameState) -> GameState;
+//!     }
+//! }
+//!
+//! let state = subcrate::advance(subcrate::GameState::default());
+//! ```
+//!
+//! Everything the subcrate exports, such as `GameState`, is exported
+//! from the module too, in every linking mode, so there's only one
+//! definition of each type. Only the declared functions differ: they're
+//! the subcrate's own when it's linked statically, and are looked up in
+//! the dylib when it's loaded dynamically.
+//!
+//! The `rlib` is what the host links against, and the `cdylib` is the
+//! dylib it loads, which still has to be built by building the subcrate
+//! itself, as with `#[path]`. When it's loaded dynamically, the host is
+//! built with its own copy of the subcrate, so a type's definition comes
+//! from the host's build, and changes to it still need the host to be
+//! rebuilt.
+//!
+//! ## Callbacks
+//!
+//! Functions can take function pointers as arguments, so the dynamic
+//! code can call back into the host:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         fn each_entity(callback: extern "C" fn(u32));
+//!     }
+//! }
+//!
+//! extern "C" fn print_entity(id: u32) {
+//!     println!("Entity {}", id);
+//! }
+//!
+//! fn main() {
+//!     subcrate::each_entity(print_entity);
+//! }
+//! ```
+//!
+//! Plain `fn(...)` pointers work too, and closures which don't
+//! capture anything can be passed as either kind of pointer.
+//! A function pointer has nowhere to keep captured state, so to pass
+//! a capturing closure, declare the argument as `impl FnMut(...)` in
+//! a module marked `#[ffi_safe]`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[ffi_safe]
+//!     pub mod subcrate {
+//!         fn each_entity(callback: impl FnMut(u32));
+//!     }
+//! }
+//!
+//! let mut ids = Vec::new();
+//! subcrate::each_entity(|id| ids.push(id));
+//! ```
+//!
+//! The closure is passed as two arguments: an `extern "C"` function,
+//! followed by a pointer to the closure, which the dylib passes back
+//! as the function's first argument each time it calls it. So the
+//! function in the dylib must take those instead:
+//!
+//! ```rust
+//! #[no_mangle]
+//! pub extern "C" fn each_entity(
+//!     callback: extern "C" fn(*mut std::ffi::c_void, u32),
+//!     closure: *mut std::ffi::c_void,
+//! ) {
+//!     for id in [1, 2, 3] {
+//!         callback(closure, id);
+//!     }
+//! }
+//! ```
+//!
+//! The pointer is only valid until the function returns, so the dylib
+//! mustn't keep it to call later. A closure can return a value too,
+//! such as `impl FnMut(u32) -> bool`, and a panic inside it aborts
+//! the process rather than unwinding into the dylib.
+//!
 //! ## Manual reloading
 //!
 //! By default, the `auto-reload` feature is enabled, which will
 //! reload the dynamic library whenever it changes (at the point
 //! you try to call one of its functions).
 //!
+//! Checking for changes means reading the dylib's modified time on
+//! every call, which can be slow, for example on a network
+//! filesystem. The `set_change_check_interval()` function of the
+//! dymod module limits how often this happens:
+//!
+//! ```rust,ignore
+//! subcrate::set_change_check_interval(std::time::Duration::from_millis(500));
+//! ```
+//!
+//! Between checks, calls skip the check entirely, so changes may
+//! take up to that long to be picked up.
+//!
+//! To see how much each call costs on your platform, run `cargo bench`
+//! in the repository's `test_dymod` directory. It compares calls to a
+//! dymod function with calls to the same function linked directly.
+//!
+//! Alternatively, the `watch` feature has the OS report changes, with
+//! `notify-debouncer-mini`, so calls only need to read a flag. A change is
+//! picked up once the dylib has stopped changing for a short time,
+//! so a build which writes the dylib several times, or an editor which
+//! saves by renaming a temporary file, only causes a single reload.
+//! This defaults to 200ms, and can be set with `set_reload_debounce()`:
+//!
+//! ```rust,ignore
+//! subcrate::set_reload_debounce(std::time::Duration::from_millis(500));
+//! ```
+//!
+//! A rebuild can also write a dylib which is byte-for-byte the same
+//! as before, for example after touching a file without changing any
+//! code. With the `content-hash` feature, a changed modified time is
+//! followed by hashing the dylib, and it's only reloaded if its
+//! contents differ from the loaded one. This applies to
+//! `pending_reload()` and `reload_if_changed()` below too.
+//!
+//! As the dylib changes while it's being built, a module can watch a
+//! separate file instead, which your build script touches once the build
+//! has succeeded. With `#[trigger = "..."]`, only a change to that file's
+//! modified time reloads the dylib, so a half-written or failed build is
+//! never loaded. A relative path is relative to the dylib's directory:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[trigger = "reload.trigger"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! A trigger file which doesn't exist yet when the dylib is loaded is
+//! picked up once it's created. Without `#[trigger]`, the dylib itself
+//! is watched.
+//!
 //! If you would prefer to handle reloading yourself, you can disable
 //! the feature (`--no-default-features`) and reload it with the
 //! `reload()` function of the dymod module.
 //!
-//! For this same reason, it is currently not possible to define
-//! a function named `reload` within your dymod module.
-
-#[cfg(all(target_arch = "wasm32", feature = "force-dynamic"))]
-compile_error!("The force-dynamic feature is not supported on WASM targets.");
-
-#[cfg(any(
-    feature = "force-dynamic",
-    all(
-        not(feature = "force-static"),
-        not(target_arch = "wasm32"),
-        debug_assertions
-    )
-))]
-#[doc(hidden)]
-pub use libloading::{Library, Symbol};
-
-#[cfg(any(
-    feature = "force-dynamic",
-    all(not(feature = "force-static"), debug_assertions)
-))]
-#[doc(hidden)]
-pub const AUTO_RELOAD: bool = cfg!(feature = "auto-reload");
-
-#[cfg(any(
-    feature = "force-static",
-    all(not(feature = "force-dynamic"), not(debug_assertions))
-))]
-#[macro_export]
-macro_rules! dymod {
-    (
-        #[path = $libpath: tt]
-        pub mod $modname: ident {
-            $(fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
-        }
-    ) => {
-        #[path = $libpath]
-        pub mod $modname;
-    };
-}
-
-/// Takes a module definition and allows it to be hotswapped in debug
+//! The `try_reload()` function does the same, but returns a
+//! `ReloadError` instead of panicking if the new dylib can't be
+//! loaded. Either way, if the dylib is loaded from a copy, the
+//! previously loaded one stays in use if reloading fails. On Linux it
+//! isn't by default; see "Loading without copying" below.
+//!
+//! Both do nothing if the dylib's modified time hasn't changed since it
+//! was loaded, and `try_reload()` returns `Ok(false)`, so they're safe
+//! to call as often as you like, such as once per frame. To reload it
+//! anyway, such as to reset its statics, call `force_reload()`:
+//!
+//! ```rust,ignore
+//! if subcrate::try_reload()? {
+//!     println!("Reloaded subcrate");
+//! }
+//!
+//! subcrate::force_reload()?;
+//! ```
+//!
+//! To check that an edit took effect, `reload_with_diff()` reloads like
+//! `force_reload()`, and returns the names of the functions which are in
+//! a different place in the new dylib than in the old. This is only a
+//! rough guide: a function can move because one before it changed, and
+//! an edit which doesn't change its size may not move it at all.
+//!
+//! ```rust,ignore
+//! let changed = subcrate::reload_with_diff()?;
+//! println!("Changed: {:?}", changed);
+//! ```
+//!
+//! To reload when a block ends instead, such as after a block of edits
+//! in a test, hold the guard returned by `reload_on_drop()`:
+//!
+//! ```rust,ignore
+//! {
+//!     let _reload = subcrate::reload_on_drop();
+//!     edit_subcrate_source();
+//!     build_subcrate();
+//! }
+//! // The new dylib is loaded here
+//! ```
+//!
+//! As `Drop` can't return an error, one from reloading is ignored
+//! and the old dylib stays loaded, but it still shows up in
+//! `status().last_error`.
+//!
+//! To build the dylib as well, call the dymod module's `rebuild()`
+//! function. It runs `cargo build` in the subcrate's directory, and
+//! reloads the dylib if that succeeds. If the build fails, `rebuild()`
+//! returns a `ReloadError::BuildFailed` whose `stderr` holds the
+//! compiler's errors, and the old dylib stays loaded. Either way,
+//! `last_build_output()` returns everything the build printed, to show
+//! without switching to a terminal:
+//!
+//! ```rust,ignore
+//! if let Err(dymod::ReloadError::BuildFailed { stderr, .. }) = subcrate::rebuild() {
+//!     show_build_errors(&stderr);
+//! }
+//! ```
+//!
+//! To build it some other way, pass a function which does so to
+//! `set_builder()`, and `rebuild()` calls that instead. The builder can
+//! do anything that Rust can, such as setting environment variables or
+//! choosing a target:
+//!
+//! ```rust,ignore
+//! subcrate::set_builder(|| {
+//!     let output = std::process::Command::new("cargo")
+//!         .args(["build", "--manifest-path", "subcrate/Cargo.toml"])
+//!         .env("RUSTFLAGS", "-C debuginfo=1")
+//!         .output()
+//!         .map_err(|error| dymod::ReloadError::BuildFailed {
+//!             path: "subcrate".into(),
+//!             error,
+//!             stderr: String::new(),
+//!         })?;
+//!     if output.status.success() {
+//!         Ok(())
+//!     } else {
+//!         Err(dymod::ReloadError::BuildFailed {
+//!             path: "subcrate".into(),
+//!             error: std::io::Error::other("cargo build failed"),
+//!             stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+//!         })
+//!     }
+//! });
+//! ```
+//!
+//! The `stderr` of a builder's `BuildFailed` error is what
+//! `last_build_output()` returns after it fails.
+//!
+//! With the `autobuild` feature, you don't need to call `rebuild()`
+//! either. Once a module has loaded its dylib, a background thread
+//! watches the `.rs` files in its subcrate's `src` directory, and calls
+//! `rebuild()` whenever one of them changes. So saving a file is enough
+//! to build and reload the dylib. If the build fails, the old dylib
+//! stays loaded, and the compiler's errors are written as a warning, to
+//! stderr or the writer passed to `dymod::set_diagnostics_writer()`.
+//! This runs `cargo build` (or the builder) on its own, so it's opt-in.
+//!
+//! To find out whether the dylib has changed without reloading it,
+//! for example to show a "press R to reload" prompt, call
+//! `pending_reload()`:
+//!
+//! ```rust,ignore
+//! if subcrate::pending_reload() && reload_key_pressed {
+//!     subcrate::reload();
+//! }
+//! ```
+//!
+//! Where nothing can call `reload()` at the right time, such as in a
+//! headless server, `dymod::trigger_reload()` instead makes every
+//! dynamic module reload the next time it's called, on whichever thread
+//! calls it. It only sets a flag, so it's safe to call from a signal
+//! handler. With the `signal` feature enabled on Unix,
+//! `dymod::reload_on_signal()` installs a `SIGUSR1` handler which does
+//! exactly that, so you can reload a running program with
+//! `kill -USR1 <pid>`:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     dymod::reload_on_signal();
+//!     loop {
+//!         serve(subcrate::handle_request);
+//!     }
+//! }
+//! ```
+//!
+//! To reload a module as soon as it changes instead, without waiting
+//! for the next call, run its `run_watch_loop()` function on a thread of
+//! its own. It checks for changes at the given interval until the stop
+//! flag is set, and calls the callback after each reload, such as to
+//! rebuild state which depends on the old code:
+//!
+//! ```rust,ignore
+//! static STOP: AtomicBool = AtomicBool::new(false);
+//!
+//! std::thread::spawn(|| {
+//!     subcrate::run_watch_loop(Duration::from_millis(100), &STOP, || {
+//!         println!("subcrate reloaded");
+//!     })
+//! });
+//! ```
+//!
+//! This needs the `auto-reload` feature, which is enabled by default.
+//!
+//! To freeze one module at its current code while others keep
+//! hotswapping, such as while stepping through it in a debugger, call
+//! its `pin()` function. Changes to a pinned module's dylib are ignored
+//! by automatic reloading, `dymod::trigger_reload()`,
+//! `pending_reload()` and `reload_if_changed()`, until `unpin()` is
+//! called. Calling `reload()` or `try_reload()` still reloads it if
+//! it has changed, and `force_reload()` always does.
+//!
+//! A module which should never be reloaded behind your back, such as
+//! one whose data is referenced for a long time, can be marked
+//! `#[no_auto_reload]` instead:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[no_auto_reload]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Calling its functions then never reloads it, whether the dylib
+//! changes or `dymod::trigger_reload()` is called, even with the
+//! `auto-reload` feature, and its `status().auto_reload` is `false`.
+//! `pending_reload()` still reports changes, and it's reloaded by
+//! `reload()`, `try_reload()`, `force_reload()`, `reload_if_changed()`
+//! and `run_watch_loop()` as usual.
+//!
+//! The `status()` function returns a `dymod::ModuleStatus`, which
+//! says how many times the dylib has been loaded, when it was last
+//! reloaded, and the error from the last reload if it failed. This is
+//! useful for showing in a debug overlay:
+//!
+//! ```rust,ignore
+//! let status = subcrate::status();
+//! if let Some(error) = &status.last_error {
+//!     eprintln!("subcrate v{} failed to reload: {}", status.version, error);
+//! }
+//! ```
+//!
+//! `loaded_dylib_info()` returns a `dymod::DylibInfo` with the path and
+//! generation of the loaded dylib, and its size and modified time from
+//! when it was loaded. It's `None` until the dylib has been loaded.
+//!
+//! Even with the `watch` feature, each call still checks whether the
+//! dylib has changed. For a tight loop, every function also has a version
+//! in the generated `no_reload` module which skips that check, and keeps
+//! using whichever version of the dylib is loaded. Calling
+//! `reload_if_changed()` beforehand picks up any changes once, outside
+//! the loop:
+//!
+//! ```rust,ignore
+//! subcrate::reload_if_changed()?;
+//! for particle in &mut particles {
+//!     subcrate::no_reload::update_particle(particle);
+//! }
+//! ```
+//!
+//! When the module is linked statically or stubbed out, the `no_reload`
+//! functions are the same as the normal ones.
+//!
+//! Because these functions are generated inside the dymod module, it
+//! is currently not possible to define a function within your dymod
+//! module with the same name as one of the generated functions or
+//! modules, like `reload` or `no_reload`.
+//!
+//! ## Reload hooks
+//!
+//! Any state owned by the dylib, such as its statics, is lost when it
+//! is reloaded. To carry it over, register hooks with the dymod
+//! module's `on_before_reload()` and `on_reload()` functions:
+//!
+//! ```rust,ignore
+//! static SAVED_SCORE: AtomicU32 = AtomicU32::new(0);
+//!
+//! subcrate::on_before_reload(|| SAVED_SCORE.store(subcrate::score(), Ordering::SeqCst));
+//! subcrate::on_reload(|| subcrate::set_score(SAVED_SCORE.load(Ordering::SeqCst)));
+//! ```
+//!
+//! When the dylib is reloaded, the new one is loaded first. If that
+//! succeeds, the before-reload hooks are called while the old dylib is
+//! still in use, then it's replaced by the new one, and then the
+//! reload hooks are called. Neither kind of hook is called when the
+//! dylib is first loaded, or when reloading fails.
+//!
+//! Hooks can call the module's functions, but must not reload it, and
+//! changes to the dylib aren't picked up while they run.
+//!
+//! To watch reloads from elsewhere, such as a UI thread, pass a channel
+//! to `set_event_sender()` instead. Each time the module notices that
+//! the dylib has changed, starts loading it, and succeeds or fails, it
+//! sends a `dymod::DymodEvent` down the channel. Unlike hooks, these
+//! are sent for the first load too. Once the receiver is dropped, the
+//! module stops sending them.
+//!
+//! ```rust,ignore
+//! let (sender, receiver) = std::sync::mpsc::channel();
+//! subcrate::set_event_sender(sender);
+//!
+//! std::thread::spawn(move || {
+//!     for event in receiver {
+//!         if let dymod::DymodEvent::ReloadFailed { error } = event {
+//!             show_error_overlay(&error);
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! For a complete game loop which keeps its state this way, see the
+//! `state_migration` example, and run it with
+//! `cargo run --example state_migration`.
+//!
+//! For state which doesn't fit in a hook, the subcrate can export
+//! functions which save all of it into a snapshot and load it back:
+//!
+//! ```rust,ignore
+//! // subcrate/src/lib.rs
+//! static SNAPSHOT_LEN: AtomicUsize = AtomicUsize::new(0);
+//!
+//! #[no_mangle]
+//! pub extern "C" fn save_state() -> *mut u8 {
+//!     let snapshot: Box<[u8]> = serialize(&WORLD).into_boxed_slice();
+//!     SNAPSHOT_LEN.store(snapshot.len(), Ordering::SeqCst);
+//!     Box::into_raw(snapshot) as *mut u8
+//! }
+//!
+//! #[no_mangle]
+//! pub extern "C" fn state_len() -> usize {
+//!     SNAPSHOT_LEN.load(Ordering::SeqCst)
+//! }
+//!
+//! #[no_mangle]
+//! pub extern "C" fn load_state(snapshot: *const u8, len: usize) {
+//!     let snapshot = unsafe { std::slice::from_raw_parts(snapshot, len) };
+//!     *WORLD.lock().unwrap() = deserialize(snapshot);
+//! }
+//! ```
+//!
+//! Then calling the dymod module's `reload_preserving_state()` calls
+//! `save_state()` and `state_len()` on the old dylib, reloads it, and
+//! passes the snapshot to `load_state()` on the new one. If the dylib
+//! isn't loaded yet, it's just loaded. A missing function fails with
+//! `ReloadError::SymbolNotFound`, before anything is reloaded if it's
+//! one of the old dylib's.
+//!
+//! The snapshot must be a `Box<[u8]>` of `state_len()` bytes, turned
+//! into a pointer with `Box::into_raw()` (or null, for no state), as
+//! the host takes it over and frees it once `load_state()` has
+//! returned. As the old dylib is gone by then, `load_state()` must copy
+//! out anything it needs, and the format must be one which both
+//! versions of the subcrate can read, such as one with a version
+//! number. Anything the module's functions do between `save_state()`
+//! and the reload isn't carried over, so call it while nothing else
+//! is using the module.
+//!
+//! ## Forwarding panics
+//!
+//! A panic in the dylib is printed by the dylib's own panic hook, not
+//! the host's. To send it through the host's logging instead, the dylib
+//! can export a `__dymod_init` function, which is called each time it's
+//! loaded, and install a hook which forwards its panics:
+//!
+//! ```rust
+//! #[no_mangle]
+//! pub extern "C" fn __dymod_init(forward_panic: dymod::ForwardPanic) {
+//!     dymod::install_panic_forwarding(forward_panic);
+//! }
+//! ```
+//!
+//! Then the host chooses what's done with them:
+//!
+//! ```rust,ignore
+//! dymod::set_panic_handler(|message| log::error!("subcrate {}", message));
+//! ```
+//!
+//! `dymod::ForwardPanic` is an `extern "C" fn(*const u8, usize)`, which
+//! is passed the UTF-8 message of the panic and its length in bytes. The
+//! message is formatted like the default hook's, starting with
+//! `panicked at` and the location of the panic, and is only valid during
+//! the call. Without a handler, the host writes it as a warning, to
+//! stderr or the writer passed to `dymod::set_diagnostics_writer()`.
+//!
+//! A panic which unwinds out of an `extern "C"` function aborts, so the
+//! dylib still has to catch it, with `std::panic::catch_unwind`, to
+//! carry on:
+//!
+//! ```rust
+//! #[no_mangle]
+//! pub extern "C" fn checked_divide(a: u32, b: u32) -> u32 {
+//!     std::panic::catch_unwind(|| a / b).unwrap_or(0)
+//! }
+//! ```
+//!
+//! ## Calling with a timeout
+//!
+//! An infinite loop in the dylib hangs whatever calls into it. While
+//! working on a function which might have one, call it with
+//! `dymod::call_with_timeout()`, which calls it on a new thread and
+//! gives up waiting after a timeout:
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//!
+//! match dymod::call_with_timeout(|| subcrate::count_sheep(3), Duration::from_secs(1)) {
+//!     Ok(sheep) => println!("{}", sheep),
+//!     Err(dymod::CallError::Timeout) => eprintln!("count_sheep is stuck"),
+//! }
+//! ```
+//!
+//! A thread can't be stopped safely, so one which times out is leaked,
+//! and keeps running until it returns or the process exits. Until then,
+//! it also keeps the dylib locked, so the module can't be reloaded, but
+//! the rest of the host keeps going, and can report the hang.
+//!
+//! ## Async functions
+//!
+//! Calling a function which needs to reload the dylib blocks until the
+//! reload is done, which isn't welcome on an async executor. With the
+//! `async` feature enabled, marking a module `#[async]` also generates
+//! a `nonblocking` module inside it, with an async version of each
+//! function:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[async]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//!
+//! async fn handle_request() -> &'static str {
+//!     subcrate::nonblocking::count_sheep(3).await
+//! }
+//! ```
+//!
+//! If the dylib needs to be reloaded first, this happens on a new
+//! thread, so it works with any executor. The function itself is still
+//! called synchronously, and when the module is linked statically or
+//! stubbed out, the async versions just call the normal ones.
+//!
+//! ## Multiple modules
+//!
+//! A single `dymod!` block can declare several modules. If one
+//! module depends on the ABI of another, mark it with
+//! `#[depends_on(...)]`:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../core/src/lib.rs"]
+//!     pub mod core {
+//!         fn core_version() -> u32;
+//!     }
+//!
+//!     #[path = "../gameplay/src/lib.rs"]
+//!     #[depends_on(core)]
+//!     pub mod gameplay {
+//!         fn update();
+//!     }
+//! }
+//! ```
+//!
+//! A block with more than one module also generates a `reload_all()`
+//! function, which reloads every module in the block, each one after
+//! the modules it depends on. Depending on a module which isn't in
+//! the same block, or a cycle of dependencies, is a compile error:
+//!
+//! ```rust,compile_fail
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../core/src/lib.rs"]
+//!     #[depends_on(gameplay)]
+//!     pub mod core {}
+//!
+//!     #[path = "../gameplay/src/lib.rs"]
+//!     #[depends_on(core)]
+//!     pub mod gameplay {}
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! To reload every dymod module in the program, across all of its
+//! `dymod!` blocks, call `dymod::reload_all()`. This reloads each
+//! module which has been loaded so far, in the order they were first
+//! loaded, and returns the first error if any of them fail.
+//!
+//! ## Eager symbol resolution
+//!
+//! By default, each function is looked up in the dylib the first time
+//! it's called on each thread, and again after every reload. If you call
+//! `set_eager_symbol_resolution(true)` on the dymod module, every
+//! declared function is instead resolved as soon as the dylib is loaded.
+//!
+//! If any of them are missing, the reload fails with
+//! `ReloadError::SymbolNotFound` and the old dylib is kept. This
+//! way, a mismatch between the `dymod!` block and the dylib shows
+//! up at reload time, rather than the first time a rarely-used
+//! function is called.
+//!
+//! To choose between failing fast and caching separately, pass a
+//! `dymod::SymbolResolution` to `set_symbol_resolution()` instead:
+//!
+//! -   `Lazy`, the default, looks each function up the first time it's
+//!     called on each thread after a reload. Reloading costs nothing
+//!     extra, but a missing function isn't noticed until it's called.
+//! -   `Eager`, as with `set_eager_symbol_resolution(true)`, looks every
+//!     function up once on each reload, and every thread uses the
+//!     addresses it found, so no call ever looks one up.
+//! -   `ValidateEager` looks every function up on each reload, failing
+//!     it if any are missing, but then forgets what it found and looks
+//!     them up lazily as `Lazy` does. It costs the lookups of both, for
+//!     a reload which fails fast without every address being kept.
+//!
+//! ```rust,ignore
+//! subcrate::set_symbol_resolution(dymod::SymbolResolution::ValidateEager);
+//! ```
+//!
+//! To check this once up front instead, for example before
+//! entering your main loop, call the module's `check()` function.
+//! It loads the dylib if necessary and makes sure every declared
+//! function can be found, without calling any of them:
+//!
+//! ```rust,ignore
+//! subcrate::check().expect("subcrate dylib is missing functions");
+//! ```
+//!
+//! The names of the declared functions are also listed in the module's
+//! `FUNCTIONS` constant, in the order they were declared, such as for
+//! showing them in a debug panel:
+//!
+//! ```rust,ignore
+//! for name in subcrate::FUNCTIONS {
+//!     ui.label(name);
+//! }
+//! ```
+//!
+//! ## Missing functions
+//!
+//! Otherwise, calling a function which the dylib doesn't have panics.
+//! Give it a `#[missing_symbol = "..."]` attribute to choose what
+//! happens instead:
+//!
+//! -   `"panic"` panics, as without the attribute.
+//! -   `"abort"` aborts the whole process, such as for a host which
+//!     mustn't unwind through the code which called it.
+//! -   `"default"` returns the default value of the function's return
+//!     type, which must implement `Default`, without calling anything.
+//!
+//! This way, a long-running host can keep going while a function
+//! is still being written, or has been renamed in the subcrate:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         #[missing_symbol = "default"]
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Once the dylib has the function, it's called as usual, after the
+//! next reload. Eager symbol resolution and `check()` still expect
+//! every declared function to be there. Statically linked functions
+//! are always there, so this has no effect on them.
+//!
+//! ## Memoizing functions
+//!
+//! A function which is expensive to call, but always returns the same
+//! result for the same arguments, can be marked `#[memoize]`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         #[memoize]
+//!         fn level_layout(seed: u64) -> Layout;
+//!     }
+//! }
+//! ```
+//!
+//! While the subcrate is dynamically linked, the result of each call is
+//! cached by its arguments, and returned by later calls with the same
+//! ones instead of calling into the dylib again. Every cached result is
+//! forgotten when the dylib is reloaded, so the new code is always used.
+//!
+//! The return type must implement `Clone`, and the argument types must
+//! implement `Hash`, `Eq` and `Clone`. Borrowed arguments like `&str`
+//! can't be used as keys, so take an owned `String` instead. While
+//! statically linked or stubbed, `#[memoize]` does nothing.
+//!
+//! ## Symbol names
+//!
+//! Each function is looked up by its own name, unless it's marked
+//! `#[symbol = "..."]`. Then it's looked up by that instead, so more than
+//! one declaration can call the same symbol, each with its own name and
+//! signature. This gives typed views over a generic C entry point:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         #[symbol = "read_value"]
+//!         fn read_u32(key: u32, out: *mut u32) -> bool;
+//!
+//!         #[symbol = "read_value"]
+//!         fn read_f32(key: u32, out: *mut f32) -> bool;
+//!     }
+//! }
+//! ```
+//!
+//! The signatures must be ABI-compatible with the one in the dylib, such
+//! as by only changing which type a pointer points to. While statically
+//! linked, the function is linked by its symbol name, so its signature
+//! isn't checked against the one in the subcrate.
+//!
+//! If the dylib doesn't export a symbol by its plain name, it's looked up
+//! again with a leading underscore, so that `count_sheep` also finds
+//! `_count_sheep`, as some linkers name symbols on Mach-O.
+//!
+//! ## Versioned symbols
+//!
+//! If the dylib exports more than one version of a function, as C
+//! libraries using ELF symbol versioning do, looking it up by name finds
+//! the default version (`count_sheep@@V2`). To call a specific one
+//! instead, mark the function with `#[symbol_version]`:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         #[symbol_version = "V1"]
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! This is only supported on Linux with glibc. On other platforms, the
+//! plain name is looked up instead, and a warning is printed when it is.
+//! While statically linked, the function is linked by name as usual.
+//!
+//! ## Checking the dymod version
+//!
+//! A dylib built against a different version of dymod may not follow
+//! the same conventions. If your subcrate depends on dymod too, it can
+//! export a constant identifying that version with `abi_magic!()`:
+//!
+//! ```rust,ignore
+//! // subcrate/src/lib.rs
+//! dymod::abi_magic!();
+//! ```
+//!
+//! Loading a dylib which exports this checks that it matches, and fails
+//! with `ReloadError::AbiVersionMismatch` if not. Dylibs which don't
+//! export it are loaded as usual.
+//!
+//! Declaring a function with the wrong number of arguments is worse
+//! still, as calling it corrupts the stack. To catch that, define the
+//! subcrate's functions inside `arity!()`, which exports how many
+//! arguments each one takes:
+//!
+//! ```rust,ignore
+//! // subcrate/src/lib.rs
+//! dymod::arity! {
+//!     #[no_mangle]
+//!     pub extern "C" fn count_sheep(sheep: u32) -> &'static str {
+//!         "Many"
+//!     }
+//! }
+//! ```
+//!
+//! Loading the dylib then fails with `ReloadError::ArityMismatch` if a
+//! declared function takes a different number. Like `abi_magic!()`,
+//! functions which don't export one are loaded without the check.
+//!
+//! `arity!()` also exports each function's ABI, so that a function
+//! which is missing its `extern "C"` fails to load with
+//! `ReloadError::AbiMismatch`, rather than being called with the
+//! wrong calling convention. This is easy to miss, as a
+//! `#[no_mangle] pub fn` is still exported under its name. The ABI is
+//! checked against the module's `#[abi]`, which is `"C"` by default.
+//!
+//! A dylib built by a different version of rustc may lay out types
+//! which aren't `#[repr(C)]` differently, which is undefined behaviour
+//! that no signature check can catch. To get a warning about it, call
+//! `embed_rustc_version()` from the subcrate's build script, with dymod
+//! as a build dependency:
+//!
+//! ```rust,ignore
+//! // subcrate/build.rs
+//! fn main() {
+//!     dymod::build::embed_rustc_version();
+//! }
+//! ```
+//!
+//! Then `abi_magic!()` exports the version too, and loading a dylib
+//! built by a different version than your crate prints a warning. This
+//! doesn't fail the reload, since the versions often differ harmlessly.
+//!
+//! Warnings like this one are printed to stderr. A tool embedding dymod
+//! can collect them itself, or silence them, with
+//! `dymod::set_diagnostics_writer()`:
+//!
+//! ```rust,ignore
+//! dymod::set_diagnostics_writer(Box::new(std::io::sink()));
+//! ```
+//!
+//! A failed reload keeps the old dylib, and a missing function panics,
+//! either of which can be caught and carried on from. With the `strict`
+//! feature, they abort the process instead, once the problem has been
+//! printed as a warning. This applies to a dylib with the wrong
+//! `abi_magic!()`, a function with the wrong number of arguments or
+//! ABI, a function which eager symbol resolution can't find, and a
+//! missing function being called, unless it's
+//! `#[missing_symbol = "default"]`. A crash at the first sign of a
+//! mismatched dylib is easier to debug than whatever calling into it
+//! would have done.
+//!
+//! ## Using the dylib directly
+//!
+//! For symbols which aren't declared in the `dymod!` block, such as
+//! a function you're experimenting with, the module's unsafe
+//! `with_library()` function gives you the currently loaded
+//! `dymod::Library`:
+//!
+//! ```rust,ignore
+//! let goats = unsafe {
+//!     subcrate::with_library(|lib| {
+//!         let count_goats: dymod::Symbol<extern "C" fn(u32) -> u32> =
+//!             lib.get(b"count_goats").expect("No count_goats function");
+//!         count_goats(3)
+//!     })
+//! };
+//! ```
+//!
+//! Nothing obtained from the library may be used once the closure
+//! returns, because the dylib could be reloaded at any point after
+//! that. This function isn't available when the `force-stub` feature
+//! is enabled.
+//!
+//! Dylibs which don't belong to a `dymod!` module can be loaded too.
+//! `dymod::host::load_from_bytes()` loads one from its contents in
+//! memory, for example a plugin received over the network, and returns
+//! a handle which dereferences to a `dymod::Library`:
+//!
+//! ```rust,ignore
+//! let plugin = unsafe { dymod::host::load_from_bytes(&bytes)? };
+//! let count_goats: dymod::Symbol<extern "C" fn(u32) -> u32> =
+//!     unsafe { plugin.get(b"count_goats")? };
+//! ```
+//!
+//! On Linux the bytes are kept in an anonymous in-memory file. On other
+//! platforms they're written to a temporary file, readable only by the
+//! current user, which is removed when the handle is dropped. Like
+//! `dymod::Library`, this is only available when dynamic linking is
+//! supported, and not with the `force-static` feature.
+//!
+//! To compare two builds of a dylib side by side, such as the old and
+//! new versions of a function you're tuning, load each into a
+//! `dymod::host::LibrarySet` under a label. Every dylib in the set is
+//! loaded from its own copy, so both stay loaded even when they were
+//! built at the same path. The dymod module keeps calling whichever
+//! dylib it has loaded:
+//!
+//! ```rust,ignore
+//! let mut versions = dymod::host::LibrarySet::new();
+//! unsafe { versions.load("old", subcrate::dylib_path())? };
+//!
+//! // ...rebuild the subcrate...
+//! unsafe { versions.load("new", subcrate::dylib_path())? };
+//!
+//! for label in ["old", "new"] {
+//!     let position = unsafe {
+//!         versions.call_in(label, "animate_from_to", |f: extern "C" fn(f32, f32) -> f32| {
+//!             f(0.0, 1.0)
+//!         })
+//!     };
+//!     println!("{}: {:?}", label, position);
+//! }
+//! ```
+//!
+//! `call_in()` returns `None` if nothing is loaded under the label, or
+//! that dylib doesn't export the function.
+//!
+//! ## Custom loaders
+//!
+//! On some platforms, such as Android, opening a dylib takes more than
+//! `Library::new`. A module's `set_loader()` function replaces how its
+//! dylib is opened from the next reload on, with anything implementing
+//! `dymod::DymodLoader`:
+//!
+//! ```rust,ignore
+//! struct NamespacedLoader;
+//!
+//! impl dymod::DymodLoader for NamespacedLoader {
+//!     fn load(&self, path: &std::path::Path) -> Result<dymod::Library, dymod::ReloadError> {
+//!         // Open the dylib however the platform needs
+//!     }
+//! }
+//!
+//! subcrate::set_loader(NamespacedLoader);
+//! ```
+//!
+//! The dylib is copied beforehand if it would be anyway, and checked
+//! afterwards as usual. Like
+//! `with_library()`, this only exists while the module is dynamically
+//! linked.
+//!
+//! On Unix, if you only need different flags for `dlopen`, use
+//! `set_open_flags()` instead. For example, `RTLD_GLOBAL` lets dylibs
+//! loaded later see the symbols of this one:
+//!
+//! ```rust,ignore
+//! subcrate::set_open_flags(libc::RTLD_NOW | libc::RTLD_GLOBAL);
+//! ```
+//!
+//! ## Checked signatures
+//!
+//! Marking a module `#[checked]` makes it a compile error to use an
+//! argument or return type which doesn't implement `dymod::DymodSafe`.
+//! This is implemented for primitives, pointers, references,
+//! arrays and `extern "C"` function pointers, but not for types such
+//! as `String` or `&str`, whose layout isn't guaranteed to match
+//! between two compilations.
+//!
+//! Enums have the same problem unless they have an integer `#[repr]`,
+//! so a fieldless enum must be declared with `dymod::safe_enum!` to be
+//! used in a checked module:
+//!
+//! ```rust,ignore
+//! use dymod::{dymod, safe_enum};
+//!
+//! safe_enum! {
+//!     #[repr(u8)]
+//!     #[derive(Clone, Copy)]
+//!     pub enum Command {
+//!         Jump,
+//!         Run,
+//!     }
+//! }
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[checked]
+//!     pub mod subcrate {
+//!         fn handle(command: Command) -> bool;
+//!     }
+//! }
+//! ```
+//!
+//! `safe_enum!` rejects enums without an integer `#[repr]`:
+//!
+//! ```rust,compile_fail
+//! dymod::safe_enum! {
+//!     pub enum Command {
+//!         Jump,
+//!         Run,
+//!     }
+//! }
+//! ```
+//!
+//! Note that the enum still needs to be the same on both sides of
+//! the boundary. If you add a variant to the subcrate's copy only,
+//! the host can still be handed a value it has no variant for.
+//!
+//! A `bool` returned from the dylib of a checked module is compared
+//! with zero rather than used as is. Any byte other than 0 or 1 would be
+//! undefined behaviour as a `bool`, but a dylib written in C might
+//! return one for `true`, which is read as `true` instead.
+//!
+//! `i128` and `u128` are also rejected, even inside references, arrays
+//! or function pointers, because their `extern "C"` layout has changed
+//! between compiler versions. If both sides are always built with the
+//! same compiler, you can allow them explicitly:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[checked(allow(u128))]
+//!     pub mod subcrate {
+//!         fn total_bytes() -> u128;
+//!     }
+//! }
+//! ```
+//!
+//! Returning a reference, such as `&'static u8`, from a checked module
+//! is allowed, but warns at compile time. The reference points into the
+//! dylib, so it dangles as soon as the dylib is reloaded, whatever its
+//! lifetime says. The warning is a deprecation warning, so denying
+//! those makes it an error:
+//!
+//! ```rust,compile_fail
+//! #![deny(deprecated)]
+//!
+//! dymod::dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[checked]
+//!     pub mod subcrate {
+//!         fn default_config() -> &'static u8;
+//!     }
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! Either return the value itself, or a copy of it, or keep the old
+//! dylibs loaded with `set_max_retained_libraries()` and check the
+//! generation before using it, as described in "Safety". Once you've
+//! done that, allow references to silence the warning:
+//!
+//! ```rust,ignore
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[checked(allow(references))]
+//!     pub mod subcrate {
+//!         fn default_config() -> &'static u8;
+//!     }
+//! }
+//! ```
+//!
+//! A checked module can also compare the allocators of both crates,
+//! when each exports a marker with `dymod::alloc_marker!()` at its root.
+//! The crate with its own `#[global_allocator]` uses
+//! `dymod::alloc_marker!(custom)` instead. A module marked
+//! `#[checked(alloc)]`, or `#[checked(allow(u128), alloc)]`, then warns
+//! when it loads a dylib whose marker doesn't match the host's:
+//!
+//! ```rust,ignore
+//! dymod::alloc_marker!();
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[checked(alloc)]
+//!     pub mod subcrate {
+//!         fn total_bytes() -> u64;
+//!     }
+//! }
+//! ```
+//!
+//! ## Passing slices
+//!
+//! Slices can't be passed to an `extern "C"` function directly, but in
+//! a module marked `#[ffi_safe]`, any `&[T]` or `&mut [T]` argument is
+//! passed as a pointer to its first element, followed by its length:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[ffi_safe]
+//!     pub mod subcrate {
+//!         fn sum(values: &[f32]) -> f32;
+//!     }
+//! }
+//! ```
+//!
+//! So the function in the dylib must take those two arguments instead,
+//! and turn them back into a slice itself:
+//!
+//! ```rust
+//! #[no_mangle]
+//! pub extern "C" fn sum(values: *const f32, len: usize) -> f32 {
+//!     let values = unsafe { std::slice::from_raw_parts(values, len) };
+//!     values.iter().sum()
+//! }
+//! ```
+//!
+//! Or define it with `dymod::ffi_safe!`, which does that for you, so the
+//! function takes the same slices as it's declared with in the host:
+//!
+//! ```rust
+//! dymod::ffi_safe! {
+//!     pub fn sum(values: &[f32]) -> f32 {
+//!         values.iter().sum()
+//!     }
+//! }
+//! ```
+//!
+//! To get bulk data out of the dylib, pass it a `&mut [T]` to fill in,
+//! rather than returning something it allocated. The buffer is owned by
+//! the host, so it doesn't matter which allocator either crate uses:
+//!
+//! ```rust
+//! dymod::ffi_safe! {
+//!     /// Writes as much of the greeting as fits, returning how much did.
+//!     pub fn render_to(buffer: &mut [u8]) -> usize {
+//!         let greeting = b"Hello";
+//!         let len = greeting.len().min(buffer.len());
+//!         buffer[..len].copy_from_slice(&greeting[..len]);
+//!         len
+//!     }
+//! }
+//! ```
+//!
+//! This works in release mode too: the module is wrapped so that
+//! `subcrate::sum` still takes a slice. The wrapper functions are
+//! `#[inline(always)]`, so they compile to a direct call of the real
+//! function. Modules without `#[ffi_safe]` aren't wrapped, and their
+//! functions are the real ones, apart from those returning nullable
+//! pointers below.
+//!
+//! ## Returning nullable pointers
+//!
+//! A function returning a `*mut T` which may be null can instead be
+//! declared as returning `Option<NonNull<T>>`, so that a null pointer
+//! comes back as `None`:
+//!
+//! ```rust,ignore
+//! use std::ptr::NonNull;
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         fn try_make() -> Option<NonNull<Widget>>;
+//!     }
+//! }
+//! ```
+//!
+//! while the dylib keeps returning the raw pointer:
+//!
+//! ```rust
+//! # pub struct Widget;
+//! #[no_mangle]
+//! pub extern "C" fn try_make() -> *mut Widget {
+//!     std::ptr::null_mut()
+//! }
+//! ```
+//!
+//! The two have the same ABI, so a dynamically linked function needs no
+//! conversion. In release mode, the function is wrapped to check for null,
+//! as with slices above. Declaring the raw pointer instead still works too.
+//!
+//! ## C strings
+//!
+//! Raw pointers can be passed and returned like any other argument,
+//! which is how a dylib written in C, or following its conventions,
+//! passes strings. The host converts them with `std::ffi::CStr`:
+//!
+//! ```rust,ignore
+//! use std::ffi::{CStr, CString};
+//! use std::os::raw::c_char;
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         fn version_string() -> *const c_char;
+//!         fn set_name(name: *const c_char);
+//!     }
+//! }
+//!
+//! // Only valid until the dylib is reloaded, so copy it out straight away
+//! let version = unsafe { CStr::from_ptr(subcrate::version_string()) }
+//!     .to_string_lossy()
+//!     .into_owned();
+//!
+//! let name = CString::new("Dolly").unwrap();
+//! subcrate::set_name(name.as_ptr());
+//! ```
+//!
+//! ## Other ABIs
+//!
+//! Functions in the dylib are called with the `"C"` ABI by default.
+//! If the subcrate exports them with a different one, such as
+//! `extern "system"`, use the `#[abi]` attribute:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[abi = "system"]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> u32;
+//!     }
+//! }
+//! ```
+//!
+//! Any ABI which Rust accepts can be used, except for `"Rust"`, as
+//! its calling convention can change between compilations:
+//!
+//! ```rust,compile_fail
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[abi = "Rust"]
+//!     pub mod subcrate {}
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! ## Loading without copying
+//!
+//! On macOS and Windows, each time the dylib is loaded, it's first
+//! copied to a new path next to the original, to get around the dyld
+//! cache and the lock on a loaded file. Each copy's name includes the
+//! process ID and which module made it, so processes or modules loading
+//! the same dylib never copy over each other's copies. On Linux, where
+//! neither is a problem, it's loaded from where it was built instead, so
+//! nothing is left next to it. This is `CopyStrategy::PlatformDefault`.
+//!
+//! As the same path can't be loaded twice, loading in place means the
+//! old version has to be unloaded before the new one is loaded, so if
+//! the new one fails to load, there's no old one to fall back on. Until
+//! a reload succeeds, `try_reload()` returns the error, and calling the
+//! module's functions tries to load the dylib again, panicking if it
+//! still can't. And if the system won't unload the old version, as glibc
+//! won't for a Rust dylib with thread-local destructors, loading the
+//! path again returns it, so the reload succeeds but runs the old code.
+//!
+//! To copy it on Linux too, so that the old version can stay loaded
+//! while the new one is loaded, and is kept if the new one fails to
+//! load, choose `CopyStrategy::Versioned`:
+//!
+//! ```rust,ignore
+//! subcrate::set_copy_strategy(dymod::CopyStrategy::Versioned);
+//! ```
+//!
+//! Or, as most runs never reload, choose `CopyStrategy::OnReload` to
+//! load the first version in place and copy only the ones after it:
+//!
+//! ```rust,ignore
+//! subcrate::set_copy_strategy(dymod::CopyStrategy::OnReload);
+//! ```
+//!
+//! Where the dylib's directory can't be written to, such as a read-only
+//! mount in a container, copying can be turned off everywhere:
+//!
+//! ```rust,ignore
+//! subcrate::set_copy_strategy(dymod::CopyStrategy::Never);
+//! ```
+//!
+//! The dylib is then loaded from where it was built on every platform,
+//! with the same hazards as on Linux, and nothing is written next to it.
+//!
+//! Alternatively, the copies can go somewhere else. This also helps
+//! where the dylib's directory is on a slow or cramped filesystem:
+//!
+//! ```rust,ignore
+//! subcrate::set_copy_directory("/var/tmp/subcrate-dylibs");
+//! ```
+//!
+//! The directory is created if it doesn't exist. With
+//! `CopyStrategy::AtomicRename`, it has to be on the same filesystem as
+//! the dylib, since the new version is renamed into it.
+//!
+//! Copying the dylib has its own hazard: if the build is still writing
+//! it, a truncated copy can be loaded. To rule that out, have the build
+//! write each new version to a file with `.new` added to its name, and
+//! switch to `CopyStrategy::AtomicRename`:
+//!
+//! ```rust,ignore
+//! subcrate::set_copy_strategy(dymod::CopyStrategy::AtomicRename);
+//! ```
+//!
+//! Then the dylib counts as changed once `libsubcrate.so.new` (or the
+//! equivalent for your platform) appears, and reloading renames it to a
+//! path for that version, which is atomic, and loads it from there. The
+//! `.new` file must be complete by the time it appears, so the build
+//! should rename it into place too, for example:
+//!
+//! ```sh
+//! cargo build && cp target/debug/libsubcrate.so target/debug/libsubcrate.so.tmp \
+//!     && mv target/debug/libsubcrate.so.tmp target/debug/libsubcrate.so.new
+//! ```
+//!
+//! Once the new version has loaded, the copy of the old one is removed.
+//! If that fails, such as because another process has it open on
+//! Windows, the reload still succeeds, and dymod warns about the copy
+//! it left behind. To panic instead, so that leftover copies can't pile
+//! up unnoticed, call `set_cleanup_policy(dymod::CleanupPolicy::Strict)`.
+//!
+//! ## Dynamic linking in release mode
+//!
+//! The `force-dynamic` feature makes every dymod module dynamically
+//! linked, regardless of build mode. If you only want this for some
+//! modules, you can mark them with the `#[dynamic_in_release]`
+//! attribute instead:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     #[dynamic_in_release]
+//!     pub mod subcrate {
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! This module will be hotswappable even in release mode, while any
+//! other dymod modules are statically linked as usual. Note that the
+//! dylib is still loaded from the subcrate's `target/debug` directory.
+//!
+//! The `force-static` feature takes precedence over this attribute,
+//! and over `auto-reload` and `watch`: statically linked modules are
+//! never reloaded. Enabling both `force-static` and `force-dynamic`
+//! is a compile error.
+//!
+//! ## Stubbing out dynamic modules
+//!
+//! If the dylib can't be built at all (for example in a restricted
+//! CI environment), the `force-stub` feature replaces every dymod
+//! module with stub functions, so the host crate still compiles and
+//! links without the subcrate. It takes precedence over the other
+//! linking features.
+//!
+//! By default a stubbed function calls `unimplemented!()`, but you
+//! can provide a value to return instead with the `#[stub]`
+//! attribute:
+//!
+//! ```rust,ignore
+//! use dymod::dymod;
+//!
+//! dymod! {
+//!     #[path = "../subcrate/src/lib.rs"]
+//!     pub mod subcrate {
+//!         #[stub = "Stubbed"]
+//!         fn count_sheep(sheep: u32) -> &'static str;
+//!     }
+//! }
+//! ```
+//!
+//! Stubbed modules also provide a `reload()` function, which does
+//! nothing.
+//!
+//! ## Using dymod without std
+//!
+//! A statically linked module is just an included module, so it doesn't
+//! need the standard library. With both the `no_std` and `force-static`
+//! features, and without the default features, dymod is `#![no_std]`,
+//! so it can be used from a `#![no_std]` crate:
+//!
+//! ```toml
+//! [dependencies]
+//! dymod = { version = "0.4", default-features = false, features = ["force-static", "no_std"] }
+//! ```
+//!
+//! Only the modules themselves are generated, along with their
+//! `no_reload` modules and `FUNCTIONS`, and the checks of `#[checked]`
+//! modules. Everything to do with reloading, such as `ReloadError` and
+//! the `reload_all()` functions, needs std and is left out.
+
+#[cfg(all(feature = "no_std", not(feature = "force-static")))]
+compile_error!(
+    "The `no_std` feature of dymod requires `force-static`, as dynamic linking needs std"
+);
+
+#[cfg(not(feature = "no_std"))]
+pub mod build;
+
+mod callback;
+mod convert;
+#[cfg(not(feature = "no_std"))]
+mod copy;
+#[cfg(not(feature = "no_std"))]
+mod error;
+#[cfg(not(feature = "no_std"))]
+mod event;
+#[cfg(not(feature = "no_std"))]
+mod guard;
+mod handle;
+mod order;
+#[cfg(not(feature = "no_std"))]
+mod panic;
+#[cfg(not(feature = "no_std"))]
+mod resolution;
+mod safe;
+#[cfg(all(unix, feature = "signal", not(feature = "no_std")))]
+mod signal;
+#[cfg(not(feature = "no_std"))]
+mod status;
+#[cfg(not(feature = "no_std"))]
+mod timeout;
+#[cfg(not(feature = "no_std"))]
+mod version;
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+mod dynamic;
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub mod host;
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+mod loader;
+
+#[cfg(not(feature = "no_std"))]
+pub use crate::copy::{CleanupPolicy, CopyStrategy};
+#[cfg(not(feature = "no_std"))]
+pub use crate::error::ReloadError;
+#[cfg(not(feature = "no_std"))]
+pub use crate::event::DymodEvent;
+#[cfg(not(feature = "no_std"))]
+pub use crate::guard::ReloadOnDrop;
+pub use crate::handle::Reborrow;
+#[cfg(not(feature = "no_std"))]
+pub use crate::panic::{install_panic_forwarding, ForwardPanic};
+#[cfg(not(feature = "no_std"))]
+pub use crate::resolution::SymbolResolution;
+pub use crate::safe::DymodSafe;
+#[cfg(all(unix, feature = "signal", not(feature = "no_std")))]
+pub use crate::signal::reload_on_signal;
+#[cfg(not(feature = "no_std"))]
+pub use crate::status::{DylibInfo, ModuleStatus};
+#[cfg(not(feature = "no_std"))]
+pub use crate::timeout::{call_with_timeout, CallError};
+
+#[doc(hidden)]
+pub use crate::safe::{assert_dymod_safe, NotAReference, ReturnLint, UNSTABLE_I128, UNSTABLE_U128};
+
+#[doc(hidden)]
+pub use crate::convert::{slice_from_abi, slice_from_abi_mut, FromReturn};
+
+#[doc(hidden)]
+pub use crate::callback::Trampoline;
+
+#[doc(hidden)]
+pub use crate::order::reload_order;
+
+#[cfg(not(feature = "no_std"))]
+#[doc(hidden)]
+pub use crate::version::{RustcVersion, RUSTC_VERSION};
+
+#[cfg(all(target_arch = "wasm32", feature = "force-dynamic"))]
+compile_error!("The force-dynamic feature is not supported on WASM targets.");
+
+#[cfg(all(feature = "force-static", feature = "force-dynamic"))]
+compile_error!("The force-static and force-dynamic features can't be enabled together.");
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub use crate::loader::{DefaultLoader, DymodLoader};
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub use libloading::{Library, Symbol};
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+pub const AUTO_RELOAD: bool = cfg!(feature = "auto-reload");
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+pub use crate::dynamic::{
+    abort_missing_symbol, check_dylib_template, dylib_arch, dylib_path, exe_dylib_path,
+    new_dylib_path, target_dylib_path, template_dylib_path, CallGuard, DynamicModule, Memo,
+    SymbolCache,
+};
+
+/// Reloads every dynamically linked dymod module in the program which
+/// has been loaded so far, from any `dymod!` block.
+///
+/// Every module is reloaded even if some of them fail, and the first
+/// error is returned. Modules which haven't been loaded yet are
+/// skipped, as they'll be loaded the first time they're called anyway.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub fn reload_all() -> Result<(), ReloadError> {
+    crate::dynamic::reload_all()
+}
+
+/// Reloads every dynamically linked dymod module in the program which
+/// has been loaded so far, from any `dymod!` block.
+///
+/// Without dynamic linking support, there are no modules to reload,
+/// so this does nothing.
+#[cfg(not(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+)))]
+#[cfg(not(feature = "no_std"))]
+pub fn reload_all() -> Result<(), ReloadError> {
+    Ok(())
+}
+
+/// Sets the function which is called with the message of each panic
+/// in a dylib which forwards its panics to the host, as described in
+/// "Forwarding panics".
+///
+/// Until this is called, the messages are written as warnings, to
+/// stderr or the writer passed to `set_diagnostics_writer()`.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub fn set_panic_handler(handler: fn(&str)) {
+    crate::dynamic::set_panic_handler(handler)
+}
+
+/// Sets the function which is called with the message of each panic
+/// in a dylib which forwards its panics to the host.
+///
+/// Without dynamic linking support, no dylibs are loaded, so this does
+/// nothing.
+#[cfg(not(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+)))]
+#[cfg(not(feature = "no_std"))]
+pub fn set_panic_handler(_handler: fn(&str)) {}
+
+/// Asks every dynamically linked dymod module to reload the next time
+/// one of its functions is called, whether or not its dylib has
+/// changed.
+///
+/// This only sets a flag, and the reload itself happens on the thread
+/// that next calls into the module, so it's safe to call from a signal
+/// handler.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub fn trigger_reload() {
+    crate::dynamic::trigger_reload()
+}
+
+/// Asks every dynamically linked dymod module to reload the next time
+/// one of its functions is called, whether or not its dylib has
+/// changed.
+///
+/// Without dynamic linking support, there are no modules to reload,
+/// so this does nothing.
+#[cfg(not(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+)))]
+pub fn trigger_reload() {}
+
+/// Sends dymod's warnings to `writer` instead of stderr, for example
+/// to show them in a tool's own log.
+///
+/// Warnings are about things which don't stop a dylib from loading,
+/// such as one built by a different version of rustc. Errors are still
+/// returned as a `ReloadError`. To silence warnings entirely, pass
+/// `Box::new(std::io::sink())`.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+pub fn set_diagnostics_writer(writer: Box<dyn std::io::Write + Send>) {
+    crate::dynamic::diagnostics::set_diagnostics_writer(writer)
+}
+
+/// Sends dymod's warnings to `writer` instead of stderr, for example
+/// to show them in a tool's own log.
+///
+/// Without dynamic linking support, nothing is ever loaded, so there
+/// are no warnings and this does nothing.
+#[cfg(not(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+)))]
+#[cfg(not(feature = "no_std"))]
+#[allow(unused_variables)]
+pub fn set_diagnostics_writer(writer: Box<dyn std::io::Write + Send>) {}
+
+/// The value exported by `abi_magic!()`. This changes whenever the
+/// conventions for calling into a dymod dylib do.
+#[doc(hidden)]
+pub const ABI_MAGIC: u64 = 0x6479_6d6f_6400_0004;
+
+/// Identifies an ABI, such as `"C"`, as exported for each function by
+/// `arity!()`.
+#[doc(hidden)]
+pub const fn abi_hash(abi: &str) -> u64 {
+    // FNV-1a
+    let bytes = abi.as_bytes();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// Exports a constant from a subcrate which identifies the version of
+/// dymod it was built for.
+///
+/// If a dylib exports this, loading it checks that it matches the
+/// version of dymod that's loading it, and fails with
+/// `ReloadError::AbiVersionMismatch` if not. Dylibs which don't export
+/// it are loaded without the check.
+///
+/// If the subcrate's build script calls
+/// [`build::embed_rustc_version()`](crate::build::embed_rustc_version),
+/// this also exports the version of rustc it was built with. Loading a
+/// dylib built by a different version prints a warning, but still
+/// succeeds.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // subcrate/src/lib.rs
+/// dymod::abi_magic!();
+///
+/// #[no_mangle]
+/// pub extern "C" fn count_sheep(sheep: u32) -> &'static str {
+///     "Many"
+/// }
+/// ```
+///
+/// The exported symbol is named after the module it's used in, so it
+/// must be used at the root of the subcrate. That way, several
+/// subcrates using it can still be statically linked into one crate.
+#[macro_export]
+macro_rules! abi_magic {
+    () => {
+        #[doc(hidden)]
+        #[export_name = concat!("__DYMOD_ABI_", module_path!())]
+        pub static __DYMOD_ABI: u64 = $crate::ABI_MAGIC;
+
+        #[doc(hidden)]
+        #[export_name = concat!("__DYMOD_RUSTC_", module_path!())]
+        pub static __DYMOD_RUSTC: $crate::RustcVersion =
+            $crate::RustcVersion::new(option_env!("DYMOD_RUSTC_VERSION"));
+    };
+}
+
+/// Exports whether the crate it's used in uses the system allocator,
+/// for `#[checked(alloc)]` modules to compare.
+///
+/// Data allocated by one crate can only be dropped by the other if
+/// both use the same allocator, which in practice means both use the
+/// system one, as they do by default. Use this at the root of both the
+/// host crate and the subcrate, passing `custom` in one with its own
+/// `#[global_allocator]`. A `#[checked(alloc)]` module then warns when
+/// it loads a dylib whose marker doesn't match the host's. Dylibs
+/// without one are loaded without the check.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // subcrate/src/lib.rs
+/// dymod::alloc_marker!();
+///
+/// // src/main.rs
+/// #[global_allocator]
+/// static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
+///
+/// dymod::alloc_marker!(custom);
+/// ```
+#[macro_export]
+macro_rules! alloc_marker {
+    () => {
+        $crate::alloc_marker!(@system true);
+    };
+    (custom) => {
+        $crate::alloc_marker!(@system false);
+    };
+    (@system $system: literal) => {
+        #[doc(hidden)]
+        #[export_name = concat!("__DYMOD_SYSTEM_ALLOC_", module_path!())]
+        pub static __DYMOD_SYSTEM_ALLOC: bool = $system;
+    };
+}
+
+/// Declares the functions of a module marked `#[interface = "..."]`,
+/// in the file it names.
+///
+/// The file holds nothing else, so the same declarations can be shared
+/// by the host and the subcrate, which can check it exports them all
+/// with `check_interface!()`. They're written as they would be inside
+/// the `dymod!` block, along with any of their attributes.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // subcrate/interface.rs
+/// dymod::interface! {
+///     fn count_sheep(sheep: u32) -> &'static str;
+/// }
+/// ```
+#[macro_export]
+macro_rules! interface {
+    ($($fns: tt)*) => {
+        __dymod_interface_fns! { $($fns)* }
+    };
+}
+
+/// Checks that a subcrate exports every function of an interface file,
+/// as declared with `interface!()`.
+///
+/// Each function must be `extern "C"`, with the same name, argument
+/// types and return type as its declaration, or this fails to compile.
+/// The path is relative to the file this is in, as with `include!()`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // subcrate/src/lib.rs
+/// dymod::check_interface!("../interface.rs");
+///
+/// #[no_mangle]
+/// pub extern "C" fn count_sheep(sheep: u32) -> &'static str {
+///     "Many"
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_interface {
+    ($file: literal) => {
+        $crate::__dymod_check_interface! { [$] $file }
+    };
+}
+
+/// Reads the functions of an interface file into the module which
+/// precedes `$rest`, by defining the macro `interface!()` expands to
+/// just before including it. `$d` is a `$`, for the macro's matchers.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_interface {
+    ([$d: tt] $file: literal $head: tt $($rest: tt)*) => {
+        macro_rules! __dymod_interface_fns {
+            ($d($d fns: tt)*) => {
+                $crate::__dymod_splice! { $head [$d($d fns)*] [] $($rest)* }
+            };
+        }
+        include!($file);
+    };
+}
+
+/// Puts the functions `$fns` into the body of the next module, then
+/// carries on parsing the `dymod!` block with it.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_splice {
+    (
+        $head: tt $fns: tt [$($seen: tt)*]
+        #[$($attr: tt)*]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_splice! { $head $fns [$($seen)* #[$($attr)*]] $($rest)* }
+    };
+    (
+        [$($head: tt)*] [$($fns: tt)*] [$($seen: tt)*]
+        pub mod $modname: ident {}
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! { $($head)* $($seen)* pub mod $modname { $($fns)* } $($rest)* }
+    };
+    (
+        $head: tt $fns: tt $seen: tt
+        pub mod $modname: ident { $($body: tt)+ }
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` has an `#[interface]`, so its functions must be declared there instead"
+        ));
+    };
+}
+
+/// Checks each function of an interface file against the function of
+/// the same name in this crate.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_check_interface {
+    ([$d: tt] $file: literal) => {
+        macro_rules! __dymod_interface_fns {
+            ($d($d fns: tt)*) => {
+                $crate::__dymod_check_fns! { $d($d fns)* }
+            };
+        }
+        include!($file);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_check_fns {
+    () => {};
+    (
+        $(#[$attr: meta])*
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;
+        $($rest: tt)*
+    ) => {
+        const _: extern "C" fn($($argtype),*) $(-> $returntype)? = $fnname;
+        $crate::__dymod_check_fns! { $($rest)* }
+    };
+}
+
+/// Defines functions in a subcrate, and exports how many arguments
+/// each of them takes, along with its ABI.
+///
+/// Loading a dylib checks the number of arguments of each declared
+/// function which has one exported, and fails with
+/// `ReloadError::ArityMismatch` if it differs, rather than corrupting
+/// the stack by calling it with the wrong number. It fails with
+/// `ReloadError::AbiMismatch` if the function's ABI isn't the one the
+/// module calls it with, such as a function without `extern "C"`.
+/// Functions without them are loaded without the checks.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // subcrate/src/lib.rs
+/// dymod::arity! {
+///     #[no_mangle]
+///     pub extern "C" fn count_sheep(sheep: u32) -> &'static str {
+///         "Many"
+///     }
+/// }
+/// ```
+///
+/// A slice passed by a `#[ffi_safe]` module counts as two arguments,
+/// as it is in the dylib.
+#[macro_export]
+macro_rules! arity {
+    () => {};
+    (
+        $(#[$attr: meta])*
+        $vis: vis extern $abi: literal fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+            $(-> $returntype: ty)? $body: block
+        $($rest: tt)*
+    ) => {
+        $(#[$attr])*
+        $vis extern $abi fn $fnname($($argname: $argtype),*) $(-> $returntype)? $body
+
+        $crate::arity! { @export $abi $fnname [$($argname)*] }
+        $crate::arity! { $($rest)* }
+    };
+    (
+        $(#[$attr: meta])*
+        $vis: vis extern fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+            $(-> $returntype: ty)? $body: block
+        $($rest: tt)*
+    ) => {
+        $(#[$attr])*
+        $vis extern "C" fn $fnname($($argname: $argtype),*) $(-> $returntype)? $body
+
+        $crate::arity! { @export "C" $fnname [$($argname)*] }
+        $crate::arity! { $($rest)* }
+    };
+    (
+        $(#[$attr: meta])*
+        $vis: vis fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+            $(-> $returntype: ty)? $body: block
+        $($rest: tt)*
+    ) => {
+        $(#[$attr])*
+        $vis fn $fnname($($argname: $argtype),*) $(-> $returntype)? $body
+
+        $crate::arity! { @export "Rust" $fnname [$($argname)*] }
+        $crate::arity! { $($rest)* }
+    };
+    (@export $abi: literal $fnname: ident [$($argname: ident)*]) => {
+        const _: () = {
+            #[export_name = concat!("__DYMOD_ARITY_", stringify!($fnname))]
+            pub static ARITY: u8 = 0 $(+ $crate::__dymod_one!($argname))*;
+
+            #[export_name = concat!("__DYMOD_LINKAGE_", stringify!($fnname))]
+            pub static LINKAGE: u64 = $crate::abi_hash($abi);
+        };
+    };
+}
+
+/// Defines functions in a subcrate which take slices, for a host
+/// module marked `#[ffi_safe]`.
+///
+/// Each function is exported as `#[no_mangle] extern "C"`, taking a
+/// pointer and a length in place of each `&[T]` or `&mut [T]`, as the
+/// host passes it. The slices are rebuilt before the body runs, so it
+/// can use them as is. A `&mut [T]` lets the dylib write its results
+/// into memory the host owns, so nothing it allocates crosses the
+/// boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// dymod::ffi_safe! {
+///     /// Writes as much of the greeting as fits, returning how much did.
+///     pub fn render_to(buffer: &mut [u8]) -> usize {
+///         let greeting = b"Hello";
+///         let len = greeting.len().min(buffer.len());
+///         buffer[..len].copy_from_slice(&greeting[..len]);
+///         len
+///     }
+/// }
+///
+/// let mut buffer = [0; 3];
+/// assert_eq!(render_to(buffer.as_mut_ptr(), buffer.len()), 3);
+/// assert_eq!(&buffer, b"Hel");
+/// ```
+#[macro_export]
+macro_rules! ffi_safe {
+    () => {};
+    (
+        $(#[$attr: meta])*
+        $vis: vis fn $fnname: ident ( $($args: tt)* ) $(-> $returntype: ty)? $body: block
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_lift! {
+            [$(#[$attr])* $vis $fnname [$($returntype)?] $body] [] [] $($args)*
+        }
+        $crate::ffi_safe! { $($rest)* }
+    };
+}
+
+/// Builds the parameters of a function defined by `ffi_safe!()`, along
+/// with the statements which turn each pointer and length back into a
+/// slice. Each `len` is from a separate expansion, so they don't clash.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_lift {
+    (
+        [$(#[$attr: meta])* $vis: vis $fnname: ident [$($returntype: ty)?] $body: block]
+        [$($params: tt)*] [$($lifts: tt)*]
+    ) => {
+        $(#[$attr])*
+        // Safe as long as the host passes slices as `dymod!` does
+        #[allow(clippy::not_unsafe_ptr_arg_deref)]
+        #[no_mangle]
+        $vis extern "C" fn $fnname($($params)*) $(-> $returntype)? {
+            $($lifts)*
+            $body
+        }
+    };
+    (
+        $header: tt [$($params: tt)*] [$($lifts: tt)*]
+        $argname: ident : & $($lifetime: lifetime)? mut [$elemtype: ty] $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lift! {
+            $header
+            [$($params)* $argname: *mut $elemtype, len: usize,]
+            [$($lifts)* let $argname: &mut [$elemtype] = unsafe { $crate::slice_from_abi_mut($argname, len) };]
+            $($($rest)*)?
+        }
+    };
+    (
+        $header: tt [$($params: tt)*] [$($lifts: tt)*]
+        $argname: ident : & $($lifetime: lifetime)? [$elemtype: ty] $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lift! {
+            $header
+            [$($params)* $argname: *const $elemtype, len: usize,]
+            [$($lifts)* let $argname: &[$elemtype] = unsafe { $crate::slice_from_abi($argname, len) };]
+            $($($rest)*)?
+        }
+    };
+    (
+        $header: tt [$($params: tt)*] [$($lifts: tt)*]
+        $argname: ident : $argtype: ty $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lift! {
+            $header [$($params)* $argname: $argtype,] [$($lifts)*] $($($rest)*)?
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_one {
+    ($token: tt) => {
+        1
+    };
+}
+
+/// Counts the arguments a declared function takes in the dylib, where
+/// a slice in a `#[ffi_safe]` module is passed as two.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_arity {
+    ($ffi: tt) => {
+        0
+    };
+    (
+        [ffi_safe] $argname: ident : & $($lifetime: lifetime)? $(mut)? [$elemtype: ty]
+        $(, $($rest: tt)*)?
+    ) => {
+        2 + $crate::__dymod_arity!([ffi_safe] $($($rest)*)?)
+    };
+    (
+        [ffi_safe] $argname: ident : impl FnMut ( $($cbarg: ty),* $(,)? ) $(-> $cbret: ty)?
+        $(, $($rest: tt)*)?
+    ) => {
+        2 + $crate::__dymod_arity!([ffi_safe] $($($rest)*)?)
+    };
+    ($ffi: tt $argname: ident : $argtype: ty $(, $($rest: tt)*)?) => {
+        1 + $crate::__dymod_arity!($ffi $($($rest)*)?)
+    };
+}
+
+/// Takes a module definition and allows it to be hotswapped in debug
 /// mode.
 ///
 /// # Examples
@@ -191,10 +2612,10 @@ macro_rules! dymod {
 /// `subcrate` dylib. If that crate is recompiled, this function will
 /// use the updated code.
 ///
-/// In release mode, this module becomes just a regular Rust module
-/// with the contents of `../subcrate/src/lib.rs`. No dynamic linking
-/// is performed at all, and the functions are as safe as if they
-/// were included normally in this crate.
+/// In release mode, `../subcrate/src/lib.rs` is included as a regular
+/// Rust module, and this module re-exports its public items. No
+/// dynamic linking is performed at all, and the functions are as safe
+/// as if they were included normally in this crate.
 ///
 /// # Panics
 ///
@@ -206,132 +2627,2138 @@ macro_rules! dymod {
 ///
 /// # Safety
 ///
-/// As above, dynamic linking is inherently unsafe. In release mode,
-/// static linking occurs and everything is safe. In debug mode,
-/// a variety of undefined behavior is possible.
+/// As above, dynamic linking is inherently unsafe. In release mode,
+/// static linking occurs and everything is safe. In debug mode,
+/// a variety of undefined behavior is possible.
+///
+/// See the [crate-level documentation](index.html) for more information.
+#[macro_export]
+macro_rules! dymod {
+    ($($input: tt)*) => {
+        $crate::__dymod_select! { $($input)* }
+    };
+}
+
+// Which linking mode `dymod!` uses is decided here, based on the
+// features of this crate. Everything else is shared between modes, so
+// the grammar can't drift between them. `force-static` always wins over
+// the features which only affect reloading, like `auto-reload`.
+
+#[cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-static",
+        all(not(feature = "force-dynamic"), not(debug_assertions))
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_select {
+    ($($input: tt)*) => {
+        $crate::__dymod_module! { @attrs static [[] [static] [] [] ["C"] [] [] [] [manifest] [] [] []] [] {} $($input)* }
+    };
+}
+
+#[cfg(all(
+    not(feature = "force-stub"),
+    not(feature = "force-static"),
+    any(feature = "force-dynamic", debug_assertions)
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_select {
+    ($($input: tt)*) => {
+        $crate::__dymod_module! { @attrs dynamic [[] [static] [] [] ["C"] [] [] [] [manifest] [] [] []] [] {} $($input)* }
+    };
+}
+
+#[cfg(feature = "force-stub")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_select {
+    ($($input: tt)*) => {
+        $crate::__dymod_module! { @attrs stub [[] [static] [] [] ["C"] [] [] [] [manifest] [] [] []] [] {} $($input)* }
+    };
+}
+
+/// Sorts the module attributes into the ones dymod understands and
+/// the ones to forward to the generated module, one module at a time.
+///
+/// The attributes dymod understands are collected into a config of
+/// `[[path] [release mode] [dependencies] [checked] [abi] [ffi_safe]
+/// [debug_path] [async] [path_relative_to] [no_auto_reload] [trigger]
+/// [profile]]`, where the path is `[dependency crate]` for a module
+/// with a `#[dependency]`.
+/// Each generated module is recorded along with its dependencies, so
+/// that `reload_all()` can be generated once the block is finished.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_module {
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[path = $newpath: tt]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [[$newpath] $release $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[dependency = $krate: ident]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [[dependency $krate] $release $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[dependency = $krate: literal]
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "`#[dependency]` takes the name of the crate without quotes, as in `#[dependency = ",
+            $krate,
+            "]`"
+        ));
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[dynamic_in_release]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath [dynamic] $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt [$($deps: ident)*] $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[depends_on($($dep: ident),* $(,)?)]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release [$($deps)* $($dep)*] $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[abi = "Rust"]
+        $($rest: tt)*
+    ) => {
+        compile_error!("dymod functions can't use the `Rust` ABI, as it can change between compilations");
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[abi = $newabi: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked [$newabi] $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[ffi_safe]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi [ffi_safe] $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[async]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_require_async! {}
+
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug [nonblocking] $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[debug_path]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi [debug_path] $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[path_relative_to = "manifest"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking [manifest] $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[path_relative_to = "exe"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking [exe] $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[no_auto_reload]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking $relative [no_auto_reload] $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[target = $triple: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking [target $triple] $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[dylib = $template: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking [template $template] $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[runtime_path]
+        $($rest: tt)*
+    ) => {
+        // There's nothing to link statically, so it's dynamic in
+        // release mode too
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath [dynamic] $deps $checked $abi $ffi $debug $nonblocking [runtime] $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[trigger = $newtrigger: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking $relative $reload [$newtrigger] $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[dylib_build_profile = "debug"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger []] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[dylib_build_profile = "release"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger [release]] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[dylib_build_profile = $other: literal]
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "`dylib_build_profile` must be \"debug\" or \"release\", not ",
+            stringify!($other)
+        ));
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[path_relative_to = $other: literal]
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "`path_relative_to` must be \"manifest\" or \"exe\", not ",
+            stringify!($other)
+        ));
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[checked $((allow($($allowed: ident),* $(,)?)))?]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps [checked [$($($allowed)*)?] []] $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        #[checked($(allow($($allowed: ident),* $(,)?),)? alloc $(,)?)]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode [$libpath $release $deps [checked [$($($allowed)*)?] [alloc]] $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[interface = $file: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_interface! {
+            [$] $file [@attrs $mode $config [$($attrs)*] {$($done)*}] $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($attrs: tt)*] {$($done: tt)*}
+        #[$($attr: tt)*]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_module! {
+            @attrs $mode $config [$($attrs)* #[$($attr)*]] {$($done)*} $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [[] $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt [runtime] $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        pub mod $modname: ident { $($fns: tt)* }
+        $($rest: tt)*
+    ) => {
+        // A `#[runtime_path]` module is never linked statically, so it
+        // doesn't need a path to the subcrate's source
+        $crate::__dymod_module! {
+            @attrs $mode [[""] $release $deps $checked $abi $ffi $debug $nonblocking [runtime] $reload $trigger $profile] [$($attrs)*] {$($done)*}
+            pub mod $modname { $($fns)* }
+            $($rest)*
+        }
+    };
+    (
+        @attrs $mode: ident [[] $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        pub mod $modname: ident { $($fns: tt)* }
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` is missing a `#[path = \"...\"]` attribute"
+        ));
+    };
+    (
+        @attrs $mode: ident [$libpath: tt [$release: ident] [$($deps: ident)*] $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*] {$($done: tt)*}
+        pub mod $modname: ident { $($fns: tt)* }
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_emit! {
+            $mode [$libpath [$release] [$($deps)*] $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] pub mod $modname { $($fns)* }
+        }
+
+        $crate::__dymod_checks! { $checked $ffi $($fns)* }
+
+        $crate::__dymod_module! {
+            @attrs $mode [[] [static] [] [] ["C"] [] [] [] [manifest] [] [] []] [] {$($done)* [$release $modname [$($deps)*]]} $($rest)*
+        }
+    };
+    (@attrs $mode: ident [[] [static] [] [] ["C"] [] [] [] [manifest] [] [] []] [] {$($done: tt)*}) => {
+        $crate::__dymod_reload_all! { $mode $($done)* }
+    };
+}
+
+/// Checks that the argument and return types of a `#[checked]` module
+/// are all `DymodSafe`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_checks {
+    (
+        @lowered [$($allowed: ident)*] [$($returntype: ty)?]
+        $params: tt $ctypes: tt $cargs: tt [$($checktype: ty,)*]
+    ) => {
+        const _: () = {
+            const ALLOWED: u8 = $crate::__dymod_allowed!($($allowed)*);
+            $($crate::assert_dymod_safe::<$checktype>(ALLOWED);)*
+            $($crate::assert_dymod_safe::<$returntype>(ALLOWED);)?
+        };
+        $crate::__dymod_return_lint! { [$($allowed)*] $($returntype)? }
+    };
+    ([] $ffi: tt $($fns: tt)*) => {};
+    (
+        [checked [$($allowed: ident)*] $alloc: tt] []
+        $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+    ) => {
+        const _: () = {
+            const ALLOWED: u8 = $crate::__dymod_allowed!($($allowed)*);
+            $(
+            $($crate::assert_dymod_safe::<$argtype>(ALLOWED);)*
+            $($crate::assert_dymod_safe::<$returntype>(ALLOWED);)?
+            )*
+        };
+        $crate::__dymod_return_lint! { @each [$($allowed)*] $([$($returntype)?])* }
+    };
+    (
+        [checked $allowed: tt $alloc: tt] [ffi_safe]
+        $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($args: tt)* ) $(-> $returntype: ty)? ;)*
+    ) => {
+        $(
+        $crate::__dymod_lower! {
+            [__dymod_checks @lowered $allowed [$($returntype)?]] [] [] [] [] $($args)*
+        }
+        )*
+    };
+}
+
+/// Lowers the arguments of a function in an `#[ffi_safe]` module into
+/// the arguments of the exported C function, then passes them on to
+/// the `[callback]` macro.
+///
+/// Four lists are built up: the parameters of the generated function,
+/// the argument types of the exported function, the expressions passed
+/// to it, and the types which must be `DymodSafe` in a checked module.
+/// Slices are passed as a pointer and a length, and anything else is
+/// passed as is.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_lower {
+    (
+        [$callback: ident $($prefix: tt)*]
+        [$($params: tt)*] [$($ctypes: tt)*] [$($cargs: tt)*] [$($checktypes: tt)*]
+    ) => {
+        $crate::$callback! {
+            $($prefix)* [$($params)*] [$($ctypes)*] [$($cargs)*] [$($checktypes)*]
+        }
+    };
+    (
+        $callback: tt [$($params: tt)*] [$($ctypes: tt)*] [$($cargs: tt)*] [$($checktypes: tt)*]
+        $argname: ident : & $($lifetime: lifetime)? [$elemtype: ty] $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lower! {
+            $callback
+            [$($params)* $argname: & $($lifetime)? [$elemtype],]
+            [$($ctypes)* *const $elemtype, usize,]
+            [$($cargs)* $argname.as_ptr(), $argname.len(),]
+            [$($checktypes)* &$elemtype,]
+            $($($rest)*)?
+        }
+    };
+    (
+        $callback: tt [$($params: tt)*] [$($ctypes: tt)*] [$($cargs: tt)*] [$($checktypes: tt)*]
+        $argname: ident : & $($lifetime: lifetime)? mut [$elemtype: ty] $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lower! {
+            $callback
+            [$($params)* $argname: & $($lifetime)? mut [$elemtype],]
+            [$($ctypes)* *mut $elemtype, usize,]
+            [$($cargs)* $argname.as_mut_ptr(), $argname.len(),]
+            [$($checktypes)* &mut $elemtype,]
+            $($($rest)*)?
+        }
+    };
+    (
+        $callback: tt [$($params: tt)*] [$($ctypes: tt)*] [$($cargs: tt)*] [$($checktypes: tt)*]
+        $argname: ident : impl FnMut ( $($cbarg: ty),* $(,)? ) $(-> $cbret: ty)? $(, $($rest: tt)*)?
+    ) => {
+        // The closure is moved into a temporary, which lives until the
+        // call returns
+        $crate::__dymod_lower! {
+            $callback
+            [$($params)* $argname: impl FnMut($($cbarg),*) $(-> $cbret)?,]
+            [$($ctypes)* extern "C" fn(*mut core::ffi::c_void, $($cbarg),*) $(-> $cbret)?, *mut core::ffi::c_void,]
+            [$($cargs)*
+                $crate::Trampoline::<($($cbarg,)*), ($($cbret)?)>::trampoline(&$argname),
+                &mut { $argname } as *mut _ as *mut core::ffi::c_void,]
+            [$($checktypes)* extern "C" fn(*mut core::ffi::c_void, $($cbarg),*) $(-> $cbret)?, *mut core::ffi::c_void,]
+            $($($rest)*)?
+        }
+    };
+    (
+        $callback: tt [$($params: tt)*] [$($ctypes: tt)*] [$($cargs: tt)*] [$($checktypes: tt)*]
+        $argname: ident : $argtype: ty $(, $($rest: tt)*)?
+    ) => {
+        $crate::__dymod_lower! {
+            $callback
+            [$($params)* $argname: $argtype,]
+            [$($ctypes)* $argtype,]
+            [$($cargs)* $argname,]
+            [$($checktypes)* $argtype,]
+            $($($rest)*)?
+        }
+    };
+}
+
+/// Checks the dependencies between the modules of a block, and
+/// generates `reload_all()` if there is more than one module.
+#[cfg(not(feature = "no_std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_reload_all {
+    ($mode: ident [$release: ident $modname: ident [$($dep: ident)*]]) => {
+        const _: () = {
+            $crate::reload_order([(stringify!($modname), &[$(stringify!($dep)),*])]);
+        };
+    };
+    ($mode: ident $([$release: ident $modname: ident [$($dep: ident)*]])*) => {
+        const _: () = {
+            $crate::reload_order([$((stringify!($modname), &[$(stringify!($dep)),*])),*]);
+        };
+
+        /// Reloads every module declared in this `dymod!` block, with
+        /// each module reloaded after the modules it depends on.
+        ///
+        /// Stops at the first module that fails to reload, so that no
+        /// module is reloaded on top of a dependency that failed.
+        pub fn reload_all() -> Result<(), $crate::ReloadError> {
+            const ORDER: &[usize] = &$crate::reload_order([$((stringify!($modname), &[$(stringify!($dep)),*])),*]);
+
+            let reloads: &[fn() -> Result<(), $crate::ReloadError>] = &[
+                $($crate::__dymod_reload_fn!($mode [$release] $modname)),*
+            ];
+
+            for &index in ORDER {
+                reloads[index]()?;
+            }
+            Ok(())
+        }
+    };
+}
+
+/// Without std, only checks the dependencies between the modules of a
+/// block, as there's no `ReloadError` for `reload_all()` to return.
+#[cfg(feature = "no_std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_reload_all {
+    ($mode: ident $([$release: ident $modname: ident [$($dep: ident)*]])*) => {
+        const _: () = {
+            $crate::reload_order([$((stringify!($modname), &[$(stringify!($dep)),*])),*]);
+        };
+    };
+}
+
+/// The function that reloads a module generated in the given mode.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_reload_fn {
+    (static [static] $modname: ident) => {
+        || Ok(())
+    };
+    (static [dynamic] $modname: ident) => {
+        $crate::__dymod_dynamic!(@reload_fn $modname)
+    };
+    ($mode: ident [$release: ident] $modname: ident) => {
+        $modname::force_reload
+    };
+}
+
+/// Generates the `DynModule` handle of a module, whose methods return
+/// values borrowing the handle, and which is only reloaded through
+/// `&mut self`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_handle {
+    (
+        $mode: ident
+        $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+    ) => {
+        /// A handle to this module, whose methods call its functions
+        /// without checking whether the dylib has changed.
+        ///
+        /// What they return can only be used while the handle is
+        /// borrowed, and reloading through the handle borrows it
+        /// mutably, so a reference into the dylib can't be held across
+        /// a reload made through it. Reloads made any other way, such
+        /// as by calling this module's functions directly, aren't
+        /// prevented.
+        #[derive(Debug)]
+        pub struct DynModule {
+            _private: (),
+        }
+
+        impl DynModule {
+            /// Returns the handle to this module the first time it's
+            /// called, and `None` after that, so that there's only ever
+            /// one handle to borrow from and reload through.
+            pub fn take() -> Option<Self> {
+                static TAKEN: ::core::sync::atomic::AtomicBool =
+                    ::core::sync::atomic::AtomicBool::new(false);
+                if TAKEN.swap(true, ::core::sync::atomic::Ordering::AcqRel) {
+                    None
+                } else {
+                    Some(DynModule { _private: () })
+                }
+            }
+
+            $(
+            $crate::__dymod_handle! {
+                @method $fnname [$($argname: $argtype),*] $($returntype)?
+            }
+            )*
+
+            $crate::__dymod_handle! { @reload $mode }
+        }
+    };
+    (@method $fnname: ident [$($argname: ident : $argtype: ty),*]) => {
+        /// Calls this module's function of the same name.
+        pub fn $fnname(&self, $($argname: $argtype),*) {
+            no_reload::$fnname($($argname),*)
+        }
+    };
+    (@method $fnname: ident [$($argname: ident : $argtype: ty),*] $returntype: ty) => {
+        /// Calls this module's function of the same name, returning
+        /// a value which borrows the handle.
+        pub fn $fnname<'a>(&'a self, $($argname: $argtype),*) -> <$returntype as $crate::Reborrow<'a>>::Output
+        where
+            $returntype: $crate::Reborrow<'a>,
+        {
+            $crate::Reborrow::reborrow(no_reload::$fnname($($argname),*))
+        }
+    };
+    (@reload static) => {};
+    (@reload stub) => {
+        /// Does nothing, as this module is stubbed out.
+        pub fn reload(&mut self) {}
+
+        /// Does nothing, as this module is stubbed out.
+        pub fn try_reload(&mut self) -> Result<bool, $crate::ReloadError> {
+            Ok(false)
+        }
+
+        /// Does nothing, as this module is stubbed out.
+        pub fn force_reload(&mut self) -> Result<(), $crate::ReloadError> {
+            Ok(())
+        }
+
+        /// Does nothing, as this module is stubbed out.
+        pub fn reload_if_changed(&mut self) -> Result<bool, $crate::ReloadError> {
+            Ok(false)
+        }
+    };
+    (@reload dynamic) => {
+        /// Calls this module's `reload()` function.
+        pub fn reload(&mut self) {
+            reload()
+        }
+
+        /// Calls this module's `try_reload()` function.
+        pub fn try_reload(&mut self) -> Result<bool, $crate::ReloadError> {
+            try_reload()
+        }
+
+        /// Calls this module's `force_reload()` function.
+        pub fn force_reload(&mut self) -> Result<(), $crate::ReloadError> {
+            force_reload()
+        }
+
+        /// Calls this module's `reload_if_changed()` function, which
+        /// is how automatic reloading is done through the handle.
+        pub fn reload_if_changed(&mut self) -> Result<bool, $crate::ReloadError> {
+            reload_if_changed()
+        }
+    };
+}
+
+/// Generates the `nonblocking` module of a module marked `#[async]`,
+/// with an async version of each function.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_nonblocking {
+    (@ready dynamic) => {
+        super::DYMOD.ready().await
+    };
+    (@ready $mode: ident) => {};
+    ($mode: ident [] $($fns: tt)*) => {};
+    (
+        $mode: ident [nonblocking]
+        $(fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+    ) => {
+        /// Async versions of the functions in this module, which reload
+        /// the dylib on a background thread if necessary, rather than
+        /// blocking the executor.
+        pub mod nonblocking {
+            use super::*;
+
+            $(
+            pub async fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+                $crate::__dymod_nonblocking!(@ready $mode);
+                super::$fnname($($argname),*)
+            }
+            )*
+        }
+    };
+}
+
+/// Emits `run_watch_loop()` for a dynamically linked module, which is
+/// only available with the `auto-reload` feature.
+#[cfg(feature = "auto-reload")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_watch_loop {
+    () => {
+        /// Reloads the dylib whenever it changes, checking every
+        /// `interval` until `stop` is set, and calls `on_reload` after
+        /// each successful reload, including the first load. This
+        /// blocks, so it's usually run on its own thread.
+        pub fn run_watch_loop(
+            interval: std::time::Duration,
+            stop: &std::sync::atomic::AtomicBool,
+            on_reload: impl FnMut(),
+        ) {
+            DYMOD.run_watch_loop(interval, stop, on_reload)
+        }
+    };
+}
+
+#[cfg(not(feature = "auto-reload"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_watch_loop {
+    () => {};
+}
+
+#[cfg(feature = "async")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_require_async {
+    () => {};
+}
+
+#[cfg(not(feature = "async"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_require_async {
+    () => {
+        compile_error!("`#[async]` dymod modules need the `async` feature of dymod");
+    };
+}
+
+/// Checks the functions of a statically linked module against the real
+/// ones, since a mismatch is undefined behaviour when dynamically linked.
 ///
-/// See the [crate-level documentation](index.html) for more information.
+/// Functions declared as returning `Option<NonNull<T>>` are replaced with
+/// ones which return `None` for a null pointer, so that they can be
+/// declared the same way against a function returning `*mut T`. The rest
+/// are left as the real functions.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_static_fns {
+    ([$abi: tt]) => {};
+    (
+        [$abi: tt]
+        $(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+        -> Option<NonNull<$pointee: ty>> ;
+        $($rest: tt)*
+    ) => {
+        // `NonNull` is whatever it was imported as alongside the `dymod!`
+        // block, so that import is still used
+        $crate::__dymod_function! {
+            @attrs static [$abi [] [] [] [] []] [] []
+            $(#[$($fnattr)*])*
+            fn $fnname ( $($argname : $argtype),* ) -> Option<super::NonNull<$pointee>>
+        }
+        $crate::__dymod_static_fns! { [$abi] $($rest)* }
+    };
+    (
+        [$abi: tt]
+        $(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+        -> Option<$root: ident :: ptr :: NonNull<$pointee: ty>> ;
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! {
+            @attrs static [$abi [] [] [] [] []] [] []
+            $(#[$($fnattr)*])*
+            fn $fnname ( $($argname : $argtype),* ) -> Option<::core::ptr::NonNull<$pointee>>
+        }
+        $crate::__dymod_static_fns! { [$abi] $($rest)* }
+    };
+    (
+        [$abi: tt]
+        $(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? )
+        $(-> $returntype: ty)? ;
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_static_fn! {
+            [$abi] []
+            $(#[$($fnattr)*])* fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)?
+        }
+        $crate::__dymod_static_fns! { [$abi] $($rest)* }
+    };
+}
+
+/// Checks the signature of one function of a statically linked module,
+/// which is used as is, unless it has a `#[symbol]`. Then it needs a
+/// wrapper to call it by its symbol name.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_static_fn {
+    (
+        [$abi: tt] [$($attrs: tt)*]
+        #[symbol = $symbol: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! {
+            @attrs static [$abi [] [] [] [] []] [] []
+            $($attrs)* #[symbol = $symbol] $($rest)*
+        }
+    };
+    (
+        [$abi: tt] [$($attrs: tt)*]
+        #[$($attr: tt)*]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_static_fn! { [$abi] [$($attrs)* #[$($attr)*]] $($rest)* }
+    };
+    (
+        [$abi: tt] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* ) $(-> $returntype: ty)?
+    ) => {
+        const _: () = {
+            #[allow(dead_code)]
+            fn check_signature() {
+                let _: extern $abi fn($($argtype),*) $(-> $returntype)? = __dymod_static::$fnname;
+            }
+        };
+    };
+}
+
+/// The name of the symbol a function is looked up by, which is its
+/// `#[symbol]` if it has one, or its own name.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_symbol_name {
+    ($fnname: ident #[symbol = $symbol: literal] $($rest: tt)*) => {
+        $symbol
+    };
+    ($fnname: ident #[$($attr: tt)*] $($rest: tt)*) => {
+        $crate::__dymod_symbol_name!($fnname $($rest)*)
+    };
+    ($fnname: ident) => {
+        stringify!($fnname)
+    };
+}
+
+/// The real module of a statically linked module, as `__dymod_static`,
+/// which is either included from its `#[path]`, or is the crate named
+/// by its `#[dependency]`. Everything in it is exported from the module.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_static_source {
+    ([dependency $krate: ident]) => {
+        use ::$krate as __dymod_static;
+
+        pub use ::$krate::*;
+    };
+    ([$libpath: tt]) => {
+        #[path = $libpath]
+        mod __dymod_static;
+
+        pub use self::__dymod_static::*;
+    };
+}
+
+/// Exports everything from the crate named by a module's
+/// `#[dependency]`, such as its types, for a module which isn't linked
+/// statically. The module's own functions take precedence over the
+/// crate's.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dependency {
+    ([dependency $krate: ident]) => {
+        #[allow(unused_imports)]
+        pub use ::$krate::*;
+    };
+    ($libpath: tt) => {};
+}
+
+/// Generates a module in the given linking mode.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_emit {
+    (
+        static [$libpath: tt [static] $deps: tt $checked: tt [$abi: tt] [] $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident {
+            $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+        }
+        { $($fns: tt)* }
+    ) => {
+        // The real module is included inside a wrapper, to make room for
+        // the generated modules like `no_reload`. The `path` of "." keeps
+        // `$libpath` relative to this file.
+        $($attrs)*
+        #[path = "."]
+        pub mod $modname {
+            $crate::__dymod_static_source! { $libpath }
+
+            // The declared types are named as they would be next to the
+            // `dymod!` block, as in a dynamic module
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::__dymod_static_fns! { [$abi] $($fns)* }
+
+            /// The names of the functions declared in this module, in
+            /// the order they were declared.
+            pub const FUNCTIONS: &[&str] = &[$(stringify!($fnname)),*];
+
+            /// The functions in this module, which are never reloaded
+            /// anyway while it's statically linked.
+            pub mod no_reload {
+                pub use super::{$($fnname),*};
+            }
+
+            $crate::__dymod_nonblocking! {
+                static $nonblocking
+                $(fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)? ;)*
+            }
+
+            $crate::__dymod_handle! {
+                static
+                $(fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)? ;)*
+            }
+        }
+    };
+    (
+        static [$libpath: tt [static] $deps: tt $checked: tt $abi: tt [] $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident { $($fns: tt)* }
+    ) => {
+        // Passes the functions along a second time as raw tokens, so their
+        // return types can still be matched on
+        $crate::__dymod_emit! {
+            static [$libpath [static] $deps $checked $abi [] $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*]
+            pub mod $modname { $($fns)* }
+            { $($fns)* }
+        }
+    };
+    (
+        static [$libpath: tt [static] $deps: tt $checked: tt [$abi: tt] [ffi_safe] $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident {
+            $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($args: tt)* ) $(-> $returntype: ty)? ;)*
+        }
+    ) => {
+        // As above, but the functions taking slices are also replaced
+        // with ones which lower them.
+        $($attrs)*
+        #[path = "."]
+        pub mod $modname {
+            $crate::__dymod_static_source! { $libpath }
+
+            // The declared types are named as they would be next to the
+            // `dymod!` block, as in a dynamic module
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+            $crate::__dymod_function! {
+                @attrs static [$abi [ffi_safe] [] [] [] []] [] []
+                $(#[$($fnattr)*])*
+                fn $fnname ( $($args)* ) $(-> $returntype)?
+            }
+            )*
+
+            /// The names of the functions declared in this module, in
+            /// the order they were declared.
+            pub const FUNCTIONS: &[&str] = &[$(stringify!($fnname)),*];
+
+            /// The functions in this module, which are never reloaded
+            /// anyway while it's statically linked.
+            pub mod no_reload {
+                pub use super::{$($fnname),*};
+            }
+
+            $crate::__dymod_nonblocking! {
+                static $nonblocking
+                $(fn $fnname ( $($args)* ) $(-> $returntype)? ;)*
+            }
+
+            $crate::__dymod_handle! {
+                static
+                $(fn $fnname ( $($args)* ) $(-> $returntype)? ;)*
+            }
+        }
+    };
+    (
+        static [$libpath: tt [dynamic] $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident { $($fns: tt)* }
+    ) => {
+        $crate::__dymod_dynamic! {
+            [$libpath [dynamic] $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] pub mod $modname { $($fns)* }
+        }
+    };
+    (
+        dynamic $config: tt [$($attrs: tt)*]
+        pub mod $modname: ident { $($fns: tt)* }
+    ) => {
+        $crate::__dymod_dynamic! { $config [$($attrs)*] pub mod $modname { $($fns)* } }
+    };
+    (
+        stub [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident {
+            $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+        }
+    ) => {
+        $($attrs)*
+        pub mod $modname {
+            use super::*;
+
+            $crate::__dymod_dependency! { $libpath }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn reload() {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn try_reload() -> Result<bool, $crate::ReloadError> {
+                Ok(false)
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn force_reload() -> Result<(), $crate::ReloadError> {
+                Ok(())
+            }
+
+            /// Returns no functions, as this module is stubbed out.
+            pub fn reload_with_diff() -> Result<Vec<&'static str>, $crate::ReloadError> {
+                Ok(Vec::new())
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn reload_preserving_state() -> Result<(), $crate::ReloadError> {
+                Ok(())
+            }
+
+            /// Does nothing when dropped, as this module is stubbed out.
+            pub fn reload_on_drop() -> $crate::ReloadOnDrop {
+                $crate::ReloadOnDrop::new(force_reload)
+            }
+
+            /// Does nothing, as this module is never reloaded.
+            pub fn on_before_reload(hook: fn()) {}
+
+            /// Does nothing, as this module is never reloaded.
+            pub fn on_reload(hook: fn()) {}
+
+            /// Drops `sender`, as this module is never reloaded.
+            pub fn set_event_sender(sender: std::sync::mpsc::Sender<$crate::DymodEvent>) {}
+
+            /// Does nothing, as this module is never reloaded.
+            pub fn pin() {}
+
+            /// Does nothing, as this module is never reloaded.
+            pub fn unpin() {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_eager_symbol_resolution(eager: bool) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_symbol_resolution(resolution: $crate::SymbolResolution) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_change_check_interval(interval: std::time::Duration) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_reload_debounce(debounce: std::time::Duration) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_copy_strategy(strategy: $crate::CopyStrategy) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_copy_directory(dir: impl Into<std::path::PathBuf>) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_cleanup_policy(policy: $crate::CleanupPolicy) {}
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn set_path_resolver(
+                resolver: impl Fn() -> std::path::PathBuf + Send + Sync + 'static,
+            ) {
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn load_from(
+                path: impl Into<std::path::PathBuf>,
+            ) -> Result<(), $crate::ReloadError> {
+                Ok(())
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn reload_from(
+                path: impl AsRef<std::path::Path>,
+            ) -> Result<(), $crate::ReloadError> {
+                Ok(())
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn check() -> Result<(), $crate::ReloadError> {
+                Ok(())
+            }
+
+            /// Returns the status of this module, which is never loaded
+            /// as it's stubbed out.
+            pub fn status() -> $crate::ModuleStatus {
+                $crate::ModuleStatus::STUB
+            }
+
+            /// Always returns `None`, as this module is never loaded.
+            pub fn loaded_dylib_info() -> Option<$crate::DylibInfo> {
+                None
+            }
+
+            /// Always returns 0, as this module is never loaded.
+            pub fn current_generation() -> usize {
+                0
+            }
+
+            /// Always returns `true`, as nothing this module returns
+            /// comes from a dylib.
+            pub fn is_generation_live(generation: usize) -> bool {
+                true
+            }
+
+            /// Does nothing, as this module is never reloaded.
+            pub fn set_max_retained_libraries(max: usize) {}
+
+            /// Always returns `false`, as this module is stubbed out.
+            pub fn pending_reload() -> bool {
+                false
+            }
+
+            /// Does nothing, as this module is stubbed out.
+            pub fn reload_if_changed() -> Result<bool, $crate::ReloadError> {
+                Ok(false)
+            }
+
+            /// Returns straight away, as this module is stubbed out.
+            pub fn run_watch_loop(
+                interval: std::time::Duration,
+                stop: &std::sync::atomic::AtomicBool,
+                on_reload: impl FnMut(),
+            ) {
+            }
+
+            /// The names of the functions declared in this module, in
+            /// the order they were declared.
+            pub const FUNCTIONS: &[&str] = &[$(stringify!($fnname)),*];
+
+            /// The functions in this module, which are stubbed out.
+            pub mod no_reload {
+                pub use super::{$($fnname),*};
+            }
+
+            $(
+            $crate::__dymod_function! {
+                @attrs stub ["C" [] [] [] [] []] [] []
+                $(#[$($fnattr)*])*
+                fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)?
+            }
+            )*
+
+            $crate::__dymod_nonblocking! {
+                stub $nonblocking
+                $(fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)? ;)*
+            }
+
+            $crate::__dymod_handle! {
+                stub
+                $(fn $fnname ( $($argname : $argtype),* ) $(-> $returntype)? ;)*
+            }
+        }
+    };
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_debug_path {
+    ([] $relative: tt $profile: tt $modname: ident) => {};
+    ([debug_path] [exe] $profile: tt $modname: ident) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` can't be `#[debug_path]`, as a path relative to the executable is only known at run time"
+        ));
+    };
+    ([debug_path] [template $template: literal] $profile: tt $modname: ident) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` can't be `#[debug_path]`, as a `#[dylib]` path template is only filled in at run time"
+        ));
+    };
+    ([debug_path] [runtime] $profile: tt $modname: ident) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` can't be `#[debug_path]`, as a `#[runtime_path]` module's path is only given at run time"
+        ));
+    };
+    ([debug_path] [target $triple: literal] $profile: tt $modname: ident) => {
+        /// The path the dylib is loaded from, as resolved at compile
+        /// time. This matches `dylib_path()`.
+        pub const RESOLVED_DYLIB_PATH: &str = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            $crate::__dymod_path_separator!(),
+            stringify!($modname),
+            $crate::__dymod_path_separator!(),
+            "target",
+            $crate::__dymod_path_separator!(),
+            $triple,
+            $crate::__dymod_path_separator!(),
+            $crate::__dymod_profile_dir!($profile),
+            $crate::__dymod_path_separator!(),
+            $crate::__dymod_dll_prefix!(),
+            stringify!($modname),
+            $crate::__dymod_dll_suffix!(),
+        );
+    };
+    ([debug_path] [manifest] $profile: tt $modname: ident) => {
+        /// The path the dylib is loaded from, as resolved at compile
+        /// time. This matches `dylib_path()`.
+        pub const RESOLVED_DYLIB_PATH: &str = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            $crate::__dymod_path_separator!(),
+            stringify!($modname),
+            $crate::__dymod_path_separator!(),
+            "target",
+            $crate::__dymod_path_separator!(),
+            $crate::__dymod_profile_dir!($profile),
+            $crate::__dymod_path_separator!(),
+            $crate::__dymod_dll_prefix!(),
+            stringify!($modname),
+            $crate::__dymod_dll_suffix!(),
+        );
+    };
+}
+
+/// Makes a `DynamicModule` look for its dylib relative to whatever
+/// `#[path_relative_to]` says, wherever its `#[dylib]` template does, or in
+/// the directory for its `#[target]`.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_relative {
+    ([manifest] $module: expr) => {
+        $module
+    };
+    ([exe] $module: expr) => {
+        $module.relative_to_exe()
+    };
+    ([template $template: literal] $module: expr) => {
+        $module.dylib_template($template)
+    };
+    ([target $triple: literal] $module: expr) => {
+        $module.target_triple($triple)
+    };
+    ([runtime] $module: expr) => {
+        $module.runtime_path()
+    };
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! __dymod_alloc_check {
+    ([checked $allowed: tt [alloc]] $module: expr) => {
+        $module.host_system_alloc(crate::__DYMOD_SYSTEM_ALLOC)
+    };
+    ($checked: tt $module: expr) => {
+        $module
+    };
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_auto_reload {
+    ([] $module: expr) => {
+        $module
+    };
+    ([no_auto_reload] $module: expr) => {
+        $module.no_auto_reload()
+    };
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_trigger {
+    ([] $module: expr) => {
+        $module
+    };
+    ([$trigger: literal] $module: expr) => {
+        $module.trigger($trigger)
+    };
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_profile {
+    ([] $module: expr) => {
+        $module
+    };
+    ([release] $module: expr) => {
+        $module.release_build()
+    };
+}
+
+/// The directory under `target` which a module's `#[dylib_build_profile]`
+/// builds into, for use in `concat!`.
+#[cfg(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_profile_dir {
+    ([]) => {
+        "debug"
+    };
+    ([release]) => {
+        "release"
+    };
+}
+
+// Literal versions of `std::path::MAIN_SEPARATOR` and
+// `std::env::consts::{DLL_PREFIX, DLL_SUFFIX}`, for use in `concat!`.
+#[cfg(windows)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_path_separator {
+    () => {
+        "\\"
+    };
+}
+
+#[cfg(not(windows))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_path_separator {
+    () => {
+        "/"
+    };
+}
+
+#[cfg(windows)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dll_prefix {
+    () => {
+        ""
+    };
+}
+
+#[cfg(not(windows))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dll_prefix {
+    () => {
+        "lib"
+    };
+}
+
+#[cfg(windows)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dll_suffix {
+    () => {
+        ".dll"
+    };
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dll_suffix {
+    () => {
+        ".dylib"
+    };
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "ios")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dll_suffix {
+    () => {
+        ".so"
+    };
+}
+
 #[cfg(any(
     feature = "force-dynamic",
-    all(not(feature = "force-static"), debug_assertions)
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
 ))]
+#[doc(hidden)]
 #[macro_export]
-macro_rules! dymod {
+macro_rules! __dymod_dynamic {
+    (@reload_fn $modname: ident) => {
+        $modname::force_reload
+    };
     (
-        #[path = $libpath: tt]
+        [$libpath: tt $release: tt $deps: tt $checked: tt [$abi: tt] $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
         pub mod $modname: ident {
-            $(fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)? ;)*
+            $($(#[$($fnattr: tt)*])* fn $fnname: ident ( $($args: tt)* ) $(-> $returntype: ty)? ;)*
         }
     ) => {
+        $($attrs)*
         pub mod $modname {
             use super::*;
 
-            use $crate::{Library, Symbol};
-
-            static mut VERSION: usize = 0;
-
-            static mut DYLIB: Option<Library> = None;
-            static mut MODIFIED_TIME: Option<std::time::SystemTime> = None;
-
-            #[cfg(target_os = "macos")]
-            const DYLIB_PATH: &'static str = concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/",
-                stringify!($modname),
-                "/target/debug/lib",
-                stringify!($modname),
-                ".dylib");
-
-            #[cfg(all(unix, not(target_os = "macos")))]
-            const DYLIB_PATH: &'static str = concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/",
-                stringify!($modname),
-                "/target/debug/lib",
-                stringify!($modname),
-                ".so");
-
-            #[cfg(windows)]
-            const DYLIB_PATH: &'static str = concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/",
-                stringify!($modname),
-                "/target/debug/",
-                stringify!($modname),
-                ".dll");
+            $crate::__dymod_dependency! { $libpath }
+
+            #[doc(hidden)]
+            pub static DYMOD: $crate::DynamicModule = $crate::__dymod_alloc_check! {
+                $checked
+                $crate::__dymod_profile! {
+                $profile
+                $crate::__dymod_trigger! {
+                $trigger
+                $crate::__dymod_auto_reload! {
+                $reload
+                $crate::__dymod_relative! {
+                    $relative
+                    $crate::DynamicModule::new(
+                        env!("CARGO_MANIFEST_DIR"),
+                        stringify!($modname),
+                        &[$($crate::__dymod_symbol_name!($fnname $(#[$($fnattr)*])*)),*],
+                    )
+                    .arities(&[$($crate::__dymod_arity!($ffi $($args)*)),*])
+                    .abi($abi)
+                }
+                }
+                }
+                }
+            };
+
+            /// The names of the functions declared in this module, in
+            /// the order they were declared.
+            pub const FUNCTIONS: &[&str] = &[$(stringify!($fnname)),*];
+
+            $crate::__dymod_debug_path! { $debug $relative $profile $modname }
+
+            /// Returns the path the dylib is loaded from.
+            pub fn dylib_path() -> &'static std::path::Path {
+                DYMOD.dylib_path()
+            }
 
+            /// Reloads the dylib unless its modified time hasn't changed
+            /// since it was loaded, panicking if it can't be loaded.
             pub fn reload() {
-                let path = unsafe {
-                    let delete_old = DYLIB.is_some();
-
-                    // Drop the old
-                    DYLIB = None;
-
-                    // Clean up the old
-                    if delete_old {
-                        let old_path = format!("{}{}", DYLIB_PATH, VERSION - 1);
-                        std::fs::remove_file(&old_path).expect("Failed to delete old dylib");
-                    }
-
-                    // Create the new
-                    let new_path = format!("{}{}", DYLIB_PATH, VERSION);
-                    std::fs::copy(DYLIB_PATH, &new_path).expect("Failed to copy new dylib");
-                    new_path
-                };
-
-                // Clear install name to confuse dyld cache
-                #[cfg(target_os = "macos")]
-                {
-                    let output = std::process::Command::new("install_name_tool")
-                        .arg("-id")
-                        .arg("")
-                        .arg(&path)
-                        .output()
-                        .expect("Failed to start install_name_tool");
-
-                    assert!(output.status.success(), "install_name_tool failed: {:#?}", output);
+                if let Err(error) = try_reload() {
+                    panic!("Failed to reload dylib: {}", error);
                 }
+            }
+
+            /// Reloads the dylib unless its modified time hasn't changed
+            /// since it was loaded, returning whether it was reloaded.
+            /// If it can't be loaded, returns an error and keeps the
+            /// previously loaded one, unless it was loaded in place, as
+            /// it is by default on Linux, and had to be unloaded first.
+            pub fn try_reload() -> Result<bool, $crate::ReloadError> {
+                DYMOD.reload_if_modified()
+            }
+
+            /// Reloads the dylib even if it hasn't changed, or returns an
+            /// error and keeps the previously loaded one if it can't be
+            /// loaded, unless it was loaded in place and had to be
+            /// unloaded first.
+            pub fn force_reload() -> Result<(), $crate::ReloadError> {
+                DYMOD.force_reload()
+            }
+
+            /// Reloads the dylib like `force_reload()`, and returns the
+            /// names of the functions which moved within it, a rough
+            /// sign of which ones changed.
+            pub fn reload_with_diff() -> Result<Vec<&'static str>, $crate::ReloadError> {
+                DYMOD.reload_with_diff()
+            }
+
+            /// Reloads the dylib, carrying its state over by passing what
+            /// the old dylib's `save_state` function returns to the new
+            /// dylib's `load_state` function.
+            pub fn reload_preserving_state() -> Result<(), $crate::ReloadError> {
+                DYMOD.reload_preserving_state()
+            }
+
+            /// Returns a guard which reloads the dylib when it's dropped,
+            /// such as at the end of a block. If reloading fails, the
+            /// error is ignored and the old dylib stays loaded.
+            pub fn reload_on_drop() -> $crate::ReloadOnDrop {
+                $crate::ReloadOnDrop::new(force_reload)
+            }
+
+            /// Registers a function to be called just before the dylib is
+            /// replaced by a newly loaded one, while the old one can still
+            /// be called.
+            pub fn on_before_reload(hook: fn()) {
+                DYMOD.on_before_reload(hook)
+            }
+
+            /// Registers a function to be called just after the dylib is
+            /// replaced by a newly loaded one.
+            pub fn on_reload(hook: fn()) {
+                DYMOD.on_reload(hook)
+            }
+
+            /// Sends a `dymod::DymodEvent` to `sender` whenever the dylib
+            /// changes, starts loading, and loads or fails to, until the
+            /// receiver is dropped.
+            pub fn set_event_sender(sender: std::sync::mpsc::Sender<$crate::DymodEvent>) {
+                DYMOD.set_event_sender(sender)
+            }
+
+            /// Sets whether every declared function is resolved as soon
+            /// as the dylib is loaded, rather than when it's called.
+            pub fn set_eager_symbol_resolution(eager: bool) {
+                DYMOD.set_eager_symbol_resolution(eager)
+            }
 
-                // Load new version
-                unsafe {
-                    VERSION += 1;
-                    DYLIB = Some(Library::new(&path).expect("Failed to load dylib"))
+            /// Sets when the declared functions are looked up in the
+            /// dylib, which is `SymbolResolution::Lazy` by default.
+            pub fn set_symbol_resolution(resolution: $crate::SymbolResolution) {
+                DYMOD.set_symbol_resolution(resolution)
+            }
+
+            /// Sets the minimum time between checks for changes to the
+            /// dylib, when auto-reloading. By default, it's checked on
+            /// every call.
+            pub fn set_change_check_interval(interval: std::time::Duration) {
+                DYMOD.set_change_check_interval(interval)
+            }
+
+            /// Sets how long the dylib must stop changing for before the
+            /// `watch` feature reloads it. This has no effect without
+            /// that feature.
+            pub fn set_reload_debounce(debounce: std::time::Duration) {
+                DYMOD.set_reload_debounce(debounce)
+            }
+
+            /// Sets how the dylib is copied before it's loaded, which
+            /// takes effect from the next reload.
+            pub fn set_copy_strategy(strategy: $crate::CopyStrategy) {
+                DYMOD.set_copy_strategy(strategy)
+            }
+
+            /// Sets the directory each version of the dylib is copied
+            /// to, which is created if it doesn't exist, instead of the
+            /// dylib's own directory. It takes effect from the next
+            /// reload.
+            pub fn set_copy_directory(dir: impl Into<std::path::PathBuf>) {
+                DYMOD.set_copy_directory(dir)
+            }
+
+            /// Sets what happens when the copy of the old dylib can't be
+            /// removed after a reload, which is to warn by default.
+            pub fn set_cleanup_policy(policy: $crate::CleanupPolicy) {
+                DYMOD.set_cleanup_policy(policy)
+            }
+
+            /// Sets how the dylib is opened, which takes effect from the
+            /// next reload.
+            pub fn set_loader(loader: impl $crate::DymodLoader + 'static) {
+                DYMOD.set_loader(loader)
+            }
+
+            /// Sets the function which works out the path of the dylib,
+            /// instead of looking in the manifest or executable
+            /// directory. It's called the next time the path is needed,
+            /// and its result is used from then on.
+            pub fn set_path_resolver(
+                resolver: impl Fn() -> std::path::PathBuf + Send + Sync + 'static,
+            ) {
+                DYMOD.set_path_resolver(resolver)
+            }
+
+            /// Loads the dylib at `path`, replacing any which is already
+            /// loaded, and keeps reloading it from there. A
+            /// `#[runtime_path]` module can't be used until this has
+            /// been called.
+            pub fn load_from(
+                path: impl Into<std::path::PathBuf>,
+            ) -> Result<(), $crate::ReloadError> {
+                DYMOD.load_from(path)
+            }
+
+            /// Reloads the dylib from `path` this once, without changing
+            /// `dylib_path()`. The next reload, such as when the dylib at
+            /// `dylib_path()` changes, loads that one again.
+            pub fn reload_from(
+                path: impl AsRef<std::path::Path>,
+            ) -> Result<(), $crate::ReloadError> {
+                DYMOD.reload_from(path.as_ref())
+            }
+
+            /// Keeps using the loaded dylib even if it changes, until
+            /// `unpin()` is called. It can still be reloaded explicitly,
+            /// such as with `reload()`.
+            pub fn pin() {
+                DYMOD.set_pinned(true)
+            }
+
+            /// Goes back to reloading the dylib when it changes, after
+            /// `pin()`.
+            pub fn unpin() {
+                DYMOD.set_pinned(false)
+            }
+
+            /// Sets the function which `rebuild()` calls to build the
+            /// dylib, such as by running `cargo build` in the subcrate.
+            pub fn set_builder(
+                builder: impl Fn() -> Result<(), $crate::ReloadError> + Send + Sync + 'static,
+            ) {
+                DYMOD.set_builder(builder)
+            }
+
+            /// Builds the dylib with the function passed to
+            /// `set_builder()`, or by running `cargo build` in the
+            /// subcrate, then reloads it. If either fails, the old dylib
+            /// stays loaded.
+            pub fn rebuild() -> Result<(), $crate::ReloadError> {
+                DYMOD.rebuild()
+            }
+
+            /// Returns what the last `cargo build` run by `rebuild()`
+            /// printed, or the `stderr` of the last build which failed.
+            pub fn last_build_output() -> Option<String> {
+                DYMOD.last_build_output()
+            }
+
+            /// Sets the flags passed to `dlopen` when the dylib is opened,
+            /// such as `RTLD_NOW | RTLD_GLOBAL`, from the next reload on.
+            /// This replaces any loader set with `set_loader()`.
+            #[cfg(unix)]
+            pub fn set_open_flags(flags: i32) {
+                DYMOD.set_open_flags(flags)
+            }
+
+            /// Loads the dylib if necessary, and checks that every
+            /// declared function can be found in it, without calling any
+            /// of them.
+            pub fn check() -> Result<(), $crate::ReloadError> {
+                DYMOD.check()
+            }
+
+            /// Returns the current status of this module, such as how
+            /// many times it has been loaded and whether the last reload
+            /// failed.
+            pub fn status() -> $crate::ModuleStatus {
+                DYMOD.status()
+            }
+
+            /// Returns the path, size and modified time of the loaded
+            /// dylib, or `None` if it hasn't been loaded.
+            pub fn loaded_dylib_info() -> Option<$crate::DylibInfo> {
+                DYMOD.loaded_dylib_info()
+            }
+
+            /// Returns the generation of the loaded dylib, which is
+            /// different for each load, or 0 if it hasn't been loaded.
+            /// Record it alongside anything borrowed from the dylib.
+            pub fn current_generation() -> usize {
+                DYMOD.current_generation()
+            }
+
+            /// Returns whether the dylib loaded as `generation` is still
+            /// loaded, so that anything borrowed from it, such as a
+            /// `&'static str`, is still valid.
+            pub fn is_generation_live(generation: usize) -> bool {
+                DYMOD.is_generation_live(generation)
+            }
+
+            /// Keeps up to `max` of the previously loaded dylibs loaded
+            /// after each reload, so that what was borrowed from them
+            /// stays valid, and unloads the oldest beyond that. This is
+            /// 2 by default.
+            pub fn set_max_retained_libraries(max: usize) {
+                DYMOD.set_max_retained_libraries(max)
+            }
+
+            /// Returns whether the dylib has changed since it was last
+            /// loaded, without reloading it.
+            pub fn pending_reload() -> bool {
+                DYMOD.pending_reload()
+            }
+
+            /// Reloads the dylib if it has changed since it was last
+            /// loaded, or hasn't been loaded yet, returning whether it
+            /// was reloaded.
+            pub fn reload_if_changed() -> Result<bool, $crate::ReloadError> {
+                DYMOD.reload_if_changed()
+            }
+
+            $crate::__dymod_watch_loop! {}
+
+            /// Calls `f` with the currently loaded dylib, loading it
+            /// first if necessary, so you can look up symbols which
+            /// aren't declared in the `dymod!` block.
+            ///
+            /// The dylib can't be reloaded until `f` returns.
+            ///
+            /// # Safety
+            ///
+            /// Nothing obtained from the dylib, such as symbols or
+            /// references to its data, may be used after `f` returns,
+            /// as the dylib may be reloaded at any point after that.
+            /// Any symbol looked up must also have the correct type.
+            pub unsafe fn with_library<R>(f: impl FnOnce(&$crate::Library) -> R) -> R {
+                DYMOD.with_library(f)
+            }
+
+            $(
+            $crate::__dymod_function! {
+                @attrs dynamic [$abi $ffi $checked [] [] []] [] []
+                $(#[$($fnattr)*])*
+                fn $fnname ( $($args)* ) $(-> $returntype)?
+            }
+            )*
+
+            /// Versions of the functions in this module which never check
+            /// whether the dylib has changed, and keep using whichever
+            /// version is loaded. Only the first call to any of them
+            /// loads the dylib, if necessary.
+            pub mod no_reload {
+                use super::*;
+
+                $(
+                $crate::__dymod_function! {
+                    @attrs no_reload [$abi $ffi $checked [] [] []] [] []
+                    $(#[$($fnattr)*])*
+                    fn $fnname ( $($args)* ) $(-> $returntype)?
                 }
+                )*
+            }
+
+            $crate::__dymod_nonblocking! {
+                dynamic $nonblocking
+                $(fn $fnname ( $($args)* ) $(-> $returntype)? ;)*
+            }
+
+            $crate::__dymod_handle! {
+                dynamic
+                $(fn $fnname ( $($args)* ) $(-> $returntype)? ;)*
+            }
+        }
+    };
+}
+
+// Without dynamic loading support, modules marked `#[dynamic_in_release]`
+// are linked statically like any other.
+#[cfg(not(any(
+    feature = "force-dynamic",
+    all(
+        not(feature = "force-static"),
+        not(target_arch = "wasm32"),
+        feature = "libloading"
+    )
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_dynamic {
+    (@reload_fn $modname: ident) => {
+        || Ok(())
+    };
+    (
+        [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt [runtime] $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident { $($fns: tt)* }
+    ) => {
+        compile_error!(concat!(
+            "dymod module `",
+            stringify!($modname),
+            "` is `#[runtime_path]`, so it can't be linked statically, which it would be without dynamic loading"
+        ));
+    };
+    (
+        [$libpath: tt $release: tt $deps: tt $checked: tt $abi: tt $ffi: tt $debug: tt $nonblocking: tt $relative: tt $reload: tt $trigger: tt $profile: tt] [$($attrs: tt)*]
+        pub mod $modname: ident { $($fns: tt)* }
+    ) => {
+        $crate::__dymod_emit! {
+            static [$libpath [static] $deps $checked $abi $ffi $debug $nonblocking $relative $reload $trigger $profile] [$($attrs)*] pub mod $modname { $($fns)* }
+        }
+    };
+}
+
+/// Generates a single function of a dynamic or stubbed module,
+/// forwarding any attributes dymod doesn't understand.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_function {
+    (
+        @attrs $mode: ident $config: tt [$($stub: tt)*] [$($attrs: tt)*]
+        #[stub = $value: expr]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode $config [$value] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident [$abi: tt $ffi: tt $checked: tt $oldversion: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        #[symbol_version = $version: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode [$abi $ffi $checked [$version] $symbol $missing] [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident [$abi: tt $ffi: tt $checked: tt $version: tt $oldsymbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        #[symbol = $symbol: literal]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode [$abi $ffi $checked $version [$symbol] $missing] [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident [$abi: tt $ffi: tt $checked: tt $version: tt $symbol: tt $oldmissing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        #[missing_symbol = "panic"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode [$abi $ffi $checked $version $symbol []] [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident [$abi: tt $ffi: tt $checked: tt $version: tt $symbol: tt $oldmissing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        #[missing_symbol = "abort"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode [$abi $ffi $checked $version $symbol [abort]] [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident [$abi: tt $ffi: tt $checked: tt $version: tt $symbol: tt $oldmissing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        #[missing_symbol = "default"]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode [$abi $ffi $checked $version $symbol [default]] [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($stub: tt)*] [$($attrs: tt)*]
+        #[missing_symbol = $other: literal]
+        $($rest: tt)*
+    ) => {
+        compile_error!(concat!(
+            "unknown `missing_symbol` value `",
+            $other,
+            "`, expected \"panic\", \"abort\" or \"default\""
+        ));
+    };
+    (
+        @attrs dynamic $config: tt [$($stub: tt)*] [$($attrs: tt)*]
+        #[memoize]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs memoized $config [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($stub: tt)*] [$($attrs: tt)*]
+        #[memoize]
+        $($rest: tt)*
+    ) => {
+        // Only dynamically linked functions are worth memoizing
+        $crate::__dymod_function! { @attrs $mode $config [$($stub)*] [$($attrs)*] $($rest)* }
+    };
+    (
+        @attrs $mode: ident $config: tt [$($stub: tt)*] [$($attrs: tt)*]
+        #[$($attr: tt)*]
+        $($rest: tt)*
+    ) => {
+        $crate::__dymod_function! { @attrs $mode $config [$($stub)*] [$($attrs)* #[$($attr)*]] $($rest)* }
+    };
+    (
+        @attrs stub $config: tt [] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $($attrs)*
+        #[allow(unused_variables)]
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            unimplemented!(
+                "`{}` is stubbed out by the `force-stub` feature",
+                stringify!($fnname)
+            )
+        }
+    };
+    (
+        @attrs stub $config: tt [$value: expr] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $($attrs)*
+        #[allow(unused_variables)]
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            $value
+        }
+    };
+    (
+        @attrs dynamic [$abi: tt [] $checked: tt $version: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $crate::__dymod_function! {
+            @lowered dynamic $abi $checked $version $symbol $missing [$($attrs)*] $fnname [$($returntype)?]
+            [$($argname: $argtype,)*] [$($argtype,)*] [$($argname,)*] [$($argtype,)*]
+        }
+    };
+    (
+        @attrs memoized [$abi: tt [] $checked: tt $version: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $crate::__dymod_function! {
+            @lowered memoized $abi $checked $version $symbol $missing [$($attrs)*] $fnname [$($returntype)?]
+            [$($argname: $argtype,)*] [$($argtype,)*] [$($argname,)*] [$($argtype,)*]
+        }
+    };
+    (
+        @attrs no_reload [$abi: tt [] $checked: tt $version: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $crate::__dymod_function! {
+            @lowered no_reload $abi $checked $version $symbol $missing [$($attrs)*] $fnname [$($returntype)?]
+            [$($argname: $argtype,)*] [$($argtype,)*] [$($argname,)*] [$($argtype,)*]
+        }
+    };
+    (
+        @attrs static [$abi: tt [] $checked: tt $version: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        fn $fnname: ident ( $($argname: ident : $argtype: ty),* $(,)? ) $(-> $returntype: ty)?
+    ) => {
+        $crate::__dymod_function! {
+            @lowered static $abi $checked $version $symbol $missing [$($attrs)*] $fnname [$($returntype)?]
+            [$($argname: $argtype,)*] [$($argtype,)*] [$($argname,)*] [$($argtype,)*]
+        }
+    };
+    (
+        @attrs $mode: ident [$abi: tt [ffi_safe] $checked: tt $version: tt $symbol: tt $missing: tt] [$($stub: tt)*] [$($attrs: tt)*]
+        fn $fnname: ident ( $($args: tt)* ) $(-> $returntype: ty)?
+    ) => {
+        $crate::__dymod_lower! {
+            [__dymod_function @lowered $mode $abi $checked $version $symbol $missing [$($attrs)*] $fnname [$($returntype)?]]
+            [] [] [] [] $($args)*
+        }
+    };
+    (
+        @lowered dynamic $abi: tt $checked: tt $version: tt $symbol: tt $missing: tt [$($attrs: tt)*] $fnname: ident [$($returntype: ty)?]
+        [$($argname: ident : $argtype: ty,)*] [$($ctype: ty,)*] [$($carg: expr,)*] $checktypes: tt
+    ) => {
+        $($attrs)*
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            std::thread_local! {
+                static SYMBOL: $crate::SymbolCache = const { std::cell::Cell::new((0, 0)) };
             }
 
-            fn dymod_file_changed() -> bool {
-                fn file_changed() -> Result<bool, std::io::Error> {
-                    let metadata = std::fs::metadata(&DYLIB_PATH)?;
-                    let modified_time = metadata.modified()?;
-                    unsafe {
-                        let changed = MODIFIED_TIME.is_some() && MODIFIED_TIME != Some(modified_time);
-                        MODIFIED_TIME = Some(modified_time);
-                        Ok(changed)
-                    }
+            let lib = DYMOD.library();
+            unsafe {
+                $crate::__dymod_call! {
+                    $checked $missing $version $symbol $abi lib $fnname [$($ctype,)*] [$($returntype)?] [$($carg,)*]
                 }
+            }
+        }
+    };
+    (
+        @lowered memoized $abi: tt $checked: tt $version: tt $symbol: tt $missing: tt [$($attrs: tt)*] $fnname: ident [$($returntype: ty)?]
+        [$($argname: ident : $argtype: ty,)*] [$($ctype: ty,)*] [$($carg: expr,)*] $checktypes: tt
+    ) => {
+        $($attrs)*
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            std::thread_local! {
+                static SYMBOL: $crate::SymbolCache = const { std::cell::Cell::new((0, 0)) };
+            }
+            static RESULTS: $crate::Memo<($($argtype,)*), ($($returntype)?)> = $crate::Memo::new();
 
-                $crate::AUTO_RELOAD && file_changed().unwrap_or(false)
+            let lib = DYMOD.library();
+            let args = ($($argname.clone(),)*);
+            if let Some(result) = RESULTS.get(lib.version(), &args) {
+                return result;
             }
 
-            fn dymod_get_lib() -> &'static Library {
-                unsafe {
-                    if DYLIB.is_none() || dymod_file_changed() {
-                        reload();
-                    }
-                    DYLIB.as_ref().unwrap()
+            let result = unsafe {
+                $crate::__dymod_call! {
+                    $checked $missing $version $symbol $abi lib $fnname [$($ctype,)*] [$($returntype)?] [$($carg,)*]
                 }
+            };
+            RESULTS.insert(lib.version(), args, result.clone());
+            result
+        }
+    };
+    (
+        @lowered no_reload $abi: tt $checked: tt $version: tt $symbol: tt $missing: tt [$($attrs: tt)*] $fnname: ident [$($returntype: ty)?]
+        [$($argname: ident : $argtype: ty,)*] [$($ctype: ty,)*] [$($carg: expr,)*] $checktypes: tt
+    ) => {
+        $($attrs)*
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            std::thread_local! {
+                static SYMBOL: $crate::SymbolCache = const { std::cell::Cell::new((0, 0)) };
             }
 
-            $(
-            pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
-                let lib = dymod_get_lib();
-                unsafe {
-                    let symbol: Symbol<extern "C" fn($($argtype),*) $(-> $returntype)?> =
-                        lib.get(stringify!($fnname).as_bytes()).expect("Failed to get symbol from dylib");
-                    symbol($($argname),*)
+            let lib = super::DYMOD.loaded_library();
+            unsafe {
+                $crate::__dymod_call! {
+                    $checked $missing $version $symbol $abi lib $fnname [$($ctype,)*] [$($returntype)?] [$($carg,)*]
                 }
             }
-            )*
         }
-    }
+    };
+    (
+        @lowered static $abi: tt $checked: tt $version: tt [$symbol: literal] $missing: tt [$($attrs: tt)*] $fnname: ident [$($returntype: ty)?]
+        [$($argname: ident : $argtype: ty,)*] [$($ctype: ty,)*] [$($carg: expr,)*] $checktypes: tt
+    ) => {
+        $($attrs)*
+        #[inline(always)]
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            // The function is linked in under its symbol name, even
+            // though it can't be named in Rust from here
+            #[allow(clashing_extern_declarations)]
+            extern $abi {
+                #[link_name = $symbol]
+                fn symbol($(_: $ctype),*) $(-> $returntype)?;
+            }
+            unsafe { symbol($($carg),*) }
+        }
+    };
+    (
+        @lowered static $abi: tt $checked: tt $version: tt [] $missing: tt [$($attrs: tt)*] $fnname: ident [$($returntype: ty)?]
+        [$($argname: ident : $argtype: ty,)*] [$($ctype: ty,)*] [$($carg: expr,)*] $checktypes: tt
+    ) => {
+        $($attrs)*
+        #[inline(always)]
+        pub fn $fnname($($argname: $argtype),*) $(-> $returntype)? {
+            $crate::FromReturn::from_return(__dymod_static::$fnname($($carg),*))
+        }
+
+        // The return type is checked by `from_return` instead, which only
+        // converts between types with the same ABI
+        const _: () = {
+            #[allow(dead_code)]
+            fn check_signature() {
+                let _: extern $abi fn($($ctype),*) -> _ = __dymod_static::$fnname;
+            }
+        };
+    };
+}
+
+/// Calls the function `$fnname` in the dylib, during which reloading
+/// from the same thread is refused. In a checked module, the
+/// return value is read with `DymodSafe::from_abi`, so that a `bool`
+/// which isn't 0 or 1 is normalized rather than undefined behaviour.
+/// A `#[missing_symbol = "default"]` function returns the default value
+/// instead, if the dylib doesn't have it.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_call {
+    (
+        [checked $allowed: tt $alloc: tt] [default] $version: tt $symbol: tt $abi: tt $lib: ident $fnname: ident
+        [$($ctype: ty,)*] [$returntype: ty] [$($carg: expr,)*]
+    ) => {{
+        let symbol: Option<extern $abi fn($($ctype),*) -> std::mem::MaybeUninit<$returntype>> =
+            $crate::__dymod_symbol!([default] $version $symbol $lib $fnname);
+        match symbol {
+            Some(symbol) => {
+                let _call = $crate::CallGuard::enter();
+                <$returntype as $crate::DymodSafe>::from_abi(symbol($($carg),*))
+            }
+            None => <$returntype as Default>::default(),
+        }
+    }};
+    (
+        $checked: tt [default] $version: tt $symbol: tt $abi: tt $lib: ident $fnname: ident
+        [$($ctype: ty,)*] [$($returntype: ty)?] [$($carg: expr,)*]
+    ) => {{
+        let symbol: Option<extern $abi fn($($ctype),*) $(-> $returntype)?> =
+            $crate::__dymod_symbol!([default] $version $symbol $lib $fnname);
+        match symbol {
+            Some(symbol) => {
+                let _call = $crate::CallGuard::enter();
+                symbol($($carg),*)
+            }
+            None => Default::default(),
+        }
+    }};
+    (
+        [checked $allowed: tt $alloc: tt] $missing: tt $version: tt $symbol: tt $abi: tt $lib: ident $fnname: ident
+        [$($ctype: ty,)*] [$returntype: ty] [$($carg: expr,)*]
+    ) => {{
+        let symbol: extern $abi fn($($ctype),*) -> std::mem::MaybeUninit<$returntype> =
+            $crate::__dymod_symbol!($missing $version $symbol $lib $fnname);
+        let _call = $crate::CallGuard::enter();
+        <$returntype as $crate::DymodSafe>::from_abi(symbol($($carg),*))
+    }};
+    (
+        $checked: tt $missing: tt $version: tt $symbol: tt $abi: tt $lib: ident $fnname: ident
+        [$($ctype: ty,)*] [$($returntype: ty)?] [$($carg: expr,)*]
+    ) => {{
+        let symbol: extern $abi fn($($ctype),*) $(-> $returntype)? =
+            $crate::__dymod_symbol!($missing $version $symbol $lib $fnname);
+        let _call = $crate::CallGuard::enter();
+        symbol($($carg),*)
+    }};
+}
+
+/// Looks up the function `$fnname` in the dylib, by its `#[symbol]` if
+/// it has one, and as the given version of the symbol if it has a
+/// `#[symbol_version]`. If it can't be found, this panics or aborts, as
+/// its `#[missing_symbol]` says, or is `None` for `"default"`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_symbol {
+    ($missing: tt $version: tt [] $lib: ident $fnname: ident) => {
+        $crate::__dymod_symbol!($missing $version [stringify!($fnname)] $lib $fnname)
+    };
+    ([] [] [$symbol: expr] $lib: ident $fnname: ident) => {
+        $lib.function(&SYMBOL, $symbol)
+    };
+    ([] [$version: literal] [$symbol: expr] $lib: ident $fnname: ident) => {
+        $lib.versioned_function(&SYMBOL, $symbol, $version)
+    };
+    ([abort] $version: tt [$symbol: expr] $lib: ident $fnname: ident) => {
+        match $crate::__dymod_symbol!([default] $version [$symbol] $lib $fnname) {
+            Some(function) => function,
+            None => $crate::abort_missing_symbol($symbol),
+        }
+    };
+    ([default] [] [$symbol: expr] $lib: ident $fnname: ident) => {
+        $lib.try_function(&SYMBOL, $symbol)
+    };
+    ([default] [$version: literal] [$symbol: expr] $lib: ident $fnname: ident) => {
+        $lib.try_versioned_function(&SYMBOL, $symbol, $version)
+    };
 }
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use libloading::Library;
+
+use crate::ReloadError;
+
+/// Opens a dylib on behalf of a dymod module.
+///
+/// By default, dylibs are opened with [`DefaultLoader`]. A module's
+/// `set_loader()` function replaces it, for platforms where opening a
+/// dylib needs more than a plain `dlopen`, such as a linker namespace
+/// on Android.
+///
+/// Whatever is loaded is still checked the same way, such as for the
+/// constant exported by [`abi_magic!()`](crate::abi_magic).
+///
+/// To pass different flags to `dlopen`, such as `RTLD_GLOBAL`, use
+/// the module's `set_open_flags()` function instead.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// struct NamespacedLoader;
+///
+/// impl dymod::DymodLoader for NamespacedLoader {
+///     fn load(&self, path: &std::path::Path) -> Result<dymod::Library, dymod::ReloadError> {
+///         // Open the dylib however the platform needs
+///     }
+/// }
+///
+/// subcrate::set_loader(NamespacedLoader);
+/// ```
+pub trait DymodLoader: Send {
+    /// Opens the dylib at `path`, which may be a copy of the one the
+    /// subcrate built.
+    fn load(&self, path: &Path) -> Result<Library, ReloadError>;
+}
+
+/// Opens dylibs with `Library::new`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultLoader;
+
+impl DymodLoader for DefaultLoader {
+    fn load(&self, path: &Path) -> Result<Library, ReloadError> {
+        Library::new(path.as_os_str()).map_err(|error| ReloadError::LoadFailed {
+            path: path.to_owned(),
+            error,
+        })
+    }
+}
+
+/// Opens dylibs with `dlopen`, passing the given flags.
+#[cfg(unix)]
+pub(crate) struct OpenFlagsLoader {
+    pub flags: i32,
+}
+
+#[cfg(unix)]
+impl DymodLoader for OpenFlagsLoader {
+    fn load(&self, path: &Path) -> Result<Library, ReloadError> {
+        libloading::os::unix::Library::open(Some(path.as_os_str()), self.flags)
+            .map(Library::from)
+            .map_err(|error| ReloadError::LoadFailed {
+                path: path.to_owned(),
+                error,
+            })
+    }
+}
+
+/// Looks up `name` as the given version of the symbol in the already
+/// loaded dylib at `path`, such as `V2` for `name@@V2`.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub(crate) unsafe fn versioned_symbol(path: &Path, name: &str, version: &str) -> Option<usize> {
+    use std::ffi::{c_char, c_int, c_void, CString};
+
+    const RTLD_LAZY: c_int = 0x1;
+    const RTLD_NOLOAD: c_int = 0x4;
+
+    extern "C" {
+        fn dlvsym(
+            handle: *mut c_void,
+            symbol: *const c_char,
+            version: *const c_char,
+        ) -> *mut c_void;
+    }
+
+    // libloading doesn't expose the handle of a library, but opening one
+    // which is already loaded returns the same handle, and closing it
+    // again leaves it loaded
+    let library =
+        libloading::os::unix::Library::open(Some(path.as_os_str()), RTLD_LAZY | RTLD_NOLOAD)
+            .ok()?;
+    let handle = library.into_raw();
+    let name = CString::new(name).ok()?;
+    let version = CString::new(version).ok()?;
+    let address = dlvsym(handle, name.as_ptr(), version.as_ptr());
+    drop(libloading::os::unix::Library::from_raw(handle));
+
+    if address.is_null() {
+        None
+    } else {
+        Some(address as usize)
+    }
+}
@@ -0,0 +1,78 @@
+/// Returns the order in which to reload a block of dymod modules, so
+/// that each module is reloaded after the modules it depends on.
+///
+/// Each module is given as its name and the names of its dependencies.
+/// Independent modules keep the order they were declared in.
+///
+/// This is evaluated at compile time, so an unknown dependency or a
+/// dependency cycle is a compile error.
+pub const fn reload_order<const N: usize>(modules: [(&str, &[&str]); N]) -> [usize; N] {
+    let mut order = [0; N];
+    let mut reloaded = [false; N];
+    let mut count = 0;
+
+    while count < N {
+        let mut progressed = false;
+        let mut module = 0;
+        while module < N {
+            if !reloaded[module] && dependencies_reloaded(&modules, &reloaded, module) {
+                reloaded[module] = true;
+                order[count] = module;
+                count += 1;
+                progressed = true;
+            }
+            module += 1;
+        }
+
+        if !progressed {
+            panic!("dymod modules have a dependency cycle");
+        }
+    }
+
+    order
+}
+
+const fn dependencies_reloaded<const N: usize>(
+    modules: &[(&str, &[&str]); N],
+    reloaded: &[bool; N],
+    module: usize,
+) -> bool {
+    let dependencies = modules[module].1;
+    let mut dependency = 0;
+    while dependency < dependencies.len() {
+        match position(modules, dependencies[dependency]) {
+            Some(index) if !reloaded[index] => return false,
+            Some(_) => (),
+            None => panic!("dymod module depends on a module not declared in the same block"),
+        }
+        dependency += 1;
+    }
+    true
+}
+
+const fn position<const N: usize>(modules: &[(&str, &[&str]); N], name: &str) -> Option<usize> {
+    let mut module = 0;
+    while module < N {
+        if str_eq(modules[module].0, name) {
+            return Some(module);
+        }
+        module += 1;
+    }
+    None
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
@@ -0,0 +1,32 @@
+/// The function a host passes to a dylib's `__dymod_init`, for the
+/// dylib to call with the message of each of its panics.
+///
+/// It's called with a pointer to the message and its length in bytes.
+/// The message is UTF-8, formatted like the default panic hook's, such
+/// as `panicked at src/lib.rs:4:5:\nattempt to divide by zero`. It's
+/// only valid for the duration of the call.
+pub type ForwardPanic = extern "C" fn(message: *const u8, len: usize);
+
+/// Sets a panic hook which passes the message of every panic in this
+/// crate to `forward`, instead of printing it.
+///
+/// This is for the dylib of a dymod module, whose `__dymod_init`
+/// function is passed a `ForwardPanic` by the host when it's loaded.
+/// The panic still unwinds as usual, so it should be caught with
+/// `std::panic::catch_unwind` before it reaches an `extern "C"`
+/// function, which would abort.
+///
+/// # Examples
+///
+/// ```rust
+/// #[no_mangle]
+/// pub extern "C" fn __dymod_init(forward_panic: dymod::ForwardPanic) {
+///     dymod::install_panic_forwarding(forward_panic);
+/// }
+/// ```
+pub fn install_panic_forwarding(forward: ForwardPanic) {
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.to_string();
+        forward(message.as_ptr(), message.len());
+    }));
+}
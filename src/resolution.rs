@@ -0,0 +1,26 @@
+/// When a dymod module's functions are looked up in its dylib, as set
+/// with its `set_symbol_resolution()` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum SymbolResolution {
+    /// Look each function up the first time it's called on each thread
+    /// after a reload, and cache it for that thread. Reloading costs
+    /// nothing extra, and only the functions which are called are ever
+    /// looked up, but a missing one isn't found until it's called.
+    #[default]
+    Lazy,
+
+    /// Look every declared function up when the dylib is loaded, failing
+    /// the reload with `ReloadError::SymbolNotFound` if any are missing,
+    /// and keep their addresses for every thread to use. Each reload
+    /// looks up every function once, after which a call never needs to
+    /// look one up, even on a new thread.
+    Eager,
+
+    /// Look every declared function up when the dylib is loaded, failing
+    /// the reload as with `Eager`, but then look them up again lazily as
+    /// with `Lazy`. This costs both the lookups of `Eager` on each
+    /// reload and those of `Lazy` on each thread's first call, in
+    /// exchange for failing fast without keeping every address.
+    ValidateEager,
+}
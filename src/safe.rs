@@ -0,0 +1,322 @@
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr::NonNull;
+
+/// Types which can safely be passed to, or returned from, a dymod
+/// function.
+///
+/// Modules marked `#[checked]` require every argument and return type
+/// to implement this trait. It's implemented for primitives, pointers,
+/// references to `DymodSafe` types, arrays of them, and `extern "C"`
+/// function pointers taking and returning them.
+///
+/// For fieldless enums, use the [`safe_enum!`](crate::safe_enum) macro.
+///
+/// # Safety
+///
+/// The type must have a layout which can't change between two
+/// compilations of the same definition, such as a `#[repr(C)]` struct
+/// made of `DymodSafe` fields.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't safely be passed to or returned from a checked dymod function",
+    note = "enums must be declared with `dymod::safe_enum!`, and structs must be `#[repr(C)]` and implement `DymodSafe`"
+)]
+pub unsafe trait DymodSafe {
+    /// Which of the types with a historically unstable ABI this type
+    /// contains, as a combination of the `UNSTABLE_*` flags.
+    #[doc(hidden)]
+    const UNSTABLE_ABI: u8 = 0;
+
+    /// Reads a value returned from the dylib by a checked module.
+    ///
+    /// # Safety
+    ///
+    /// The value must have been returned by a function declared to
+    /// return `Self`.
+    #[doc(hidden)]
+    unsafe fn from_abi(value: MaybeUninit<Self>) -> Self
+    where
+        Self: Sized,
+    {
+        value.assume_init()
+    }
+}
+
+#[doc(hidden)]
+pub const UNSTABLE_I128: u8 = 1;
+
+#[doc(hidden)]
+pub const UNSTABLE_U128: u8 = 2;
+
+macro_rules! impl_dymod_safe {
+    ($($t: ty),* $(,)?) => {
+        $(unsafe impl DymodSafe for $t {})*
+    };
+}
+
+impl_dymod_safe! {
+    (), char, f32, f64,
+    i8, i16, i32, i64, isize,
+    u8, u16, u32, u64, usize,
+    c_void,
+}
+
+// Any byte other than 0 or 1 is undefined behaviour as a `bool`, and a
+// dylib built by another compiler might return one, so it's compared
+// instead of being trusted
+unsafe impl DymodSafe for bool {
+    unsafe fn from_abi(value: MaybeUninit<Self>) -> Self {
+        *value.as_ptr().cast::<u8>() != 0
+    }
+}
+
+// The layout of 128-bit integers has changed between compiler versions
+// on some targets, so they're only allowed when asked for
+unsafe impl DymodSafe for i128 {
+    const UNSTABLE_ABI: u8 = UNSTABLE_I128;
+}
+
+unsafe impl DymodSafe for u128 {
+    const UNSTABLE_ABI: u8 = UNSTABLE_U128;
+}
+
+unsafe impl<T> DymodSafe for *const T {}
+unsafe impl<T> DymodSafe for *mut T {}
+unsafe impl<T> DymodSafe for NonNull<T> {}
+unsafe impl<T> DymodSafe for Option<NonNull<T>> {}
+unsafe impl<T: DymodSafe> DymodSafe for &T {
+    const UNSTABLE_ABI: u8 = T::UNSTABLE_ABI;
+}
+unsafe impl<T: DymodSafe> DymodSafe for &mut T {
+    const UNSTABLE_ABI: u8 = T::UNSTABLE_ABI;
+}
+unsafe impl<T: DymodSafe> DymodSafe for Option<&T> {
+    const UNSTABLE_ABI: u8 = T::UNSTABLE_ABI;
+}
+unsafe impl<T: DymodSafe> DymodSafe for Option<&mut T> {
+    const UNSTABLE_ABI: u8 = T::UNSTABLE_ABI;
+}
+unsafe impl<T: DymodSafe, const N: usize> DymodSafe for [T; N] {
+    const UNSTABLE_ABI: u8 = T::UNSTABLE_ABI;
+}
+
+macro_rules! impl_dymod_safe_fn {
+    ($($arg: ident),*) => {
+        impl_dymod_safe_fn!(@impl [$($arg),*] extern "C" fn($($arg),*) -> R);
+        impl_dymod_safe_fn!(@impl [$($arg),*] unsafe extern "C" fn($($arg),*) -> R);
+        impl_dymod_safe_fn!(@impl [$($arg),*] Option<extern "C" fn($($arg),*) -> R>);
+        impl_dymod_safe_fn!(@impl [$($arg),*] Option<unsafe extern "C" fn($($arg),*) -> R>);
+    };
+    (@impl [$($arg: ident),*] $t: ty) => {
+        unsafe impl<R: DymodSafe, $($arg: DymodSafe),*> DymodSafe for $t {
+            const UNSTABLE_ABI: u8 = R::UNSTABLE_ABI $(| $arg::UNSTABLE_ABI)*;
+        }
+    };
+}
+
+impl_dymod_safe_fn!();
+impl_dymod_safe_fn!(A);
+impl_dymod_safe_fn!(A, B);
+impl_dymod_safe_fn!(A, B, C);
+impl_dymod_safe_fn!(A, B, C, D);
+impl_dymod_safe_fn!(A, B, C, D, E);
+impl_dymod_safe_fn!(A, B, C, D, E, F);
+
+#[doc(hidden)]
+pub const fn assert_dymod_safe<T: DymodSafe>(allowed: u8) {
+    if T::UNSTABLE_ABI & !allowed != 0 {
+        panic!(
+            "`i128` and `u128` have had an unstable ABI between compiler versions, \
+             so they must be allowed with `#[checked(allow(i128, u128))]`"
+        );
+    }
+}
+
+/// Warns about a checked function which returns `T`, if it's a
+/// reference, which points into the dylib and dangles once it's
+/// reloaded.
+///
+/// A macro has no way to emit a warning of its own, so calling
+/// `check()` on a reference is a use of a deprecated method, and on
+/// anything else, it's `NotAReference::check()` instead.
+#[doc(hidden)]
+pub struct ReturnLint<T>(PhantomData<T>);
+
+#[allow(clippy::new_without_default)]
+impl<T> ReturnLint<T> {
+    pub const fn new() -> Self {
+        ReturnLint(PhantomData)
+    }
+}
+
+macro_rules! impl_return_lint {
+    ($($t: ty),* $(,)?) => {
+        $(
+        impl<'a, T: ?Sized> ReturnLint<$t> {
+            #[deprecated(
+                note = "this checked dymod function returns a reference into the dylib, which dangles once \
+                        it's reloaded. Return an owned or copied value instead, keep old dylibs loaded with \
+                        `set_max_retained_libraries()`, or allow it with `#[checked(allow(references))]`"
+            )]
+            pub fn check(&self) {}
+        }
+        )*
+    };
+}
+
+impl_return_lint! {
+    &'a T, &'a mut T, Option<&'a T>, Option<&'a mut T>,
+}
+
+#[doc(hidden)]
+pub trait NotAReference {
+    fn check(&self) {}
+}
+
+impl<T> NotAReference for ReturnLint<T> {}
+
+/// Warns if `$returntype` is a reference, unless `references` is among
+/// the types allowed by a `#[checked(allow(...))]` attribute.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_return_lint {
+    (@each $allowed: tt $([$($returntype: ty)?])*) => {
+        $($crate::__dymod_return_lint! { $allowed $($returntype)? })*
+    };
+    ([references $($allowed: ident)*] $($returntype: ty)?) => {};
+    ([$other: ident $($allowed: ident)*] $($returntype: ty)?) => {
+        $crate::__dymod_return_lint! { [$($allowed)*] $($returntype)? }
+    };
+    ([] $returntype: ty) => {
+        const _: fn() = || {
+            use $crate::NotAReference as _;
+            $crate::ReturnLint::<$returntype>::new().check();
+        };
+    };
+    ([]) => {};
+}
+
+/// Returns the `UNSTABLE_*` flags for the types allowed by a
+/// `#[checked(allow(...))]` attribute.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_allowed {
+    () => {
+        0
+    };
+    (i128 $($rest: ident)*) => {
+        $crate::UNSTABLE_I128 | $crate::__dymod_allowed!($($rest)*)
+    };
+    (u128 $($rest: ident)*) => {
+        $crate::UNSTABLE_U128 | $crate::__dymod_allowed!($($rest)*)
+    };
+    (references $($rest: ident)*) => {
+        $crate::__dymod_allowed!($($rest)*)
+    };
+    ($other: ident $($rest: ident)*) => {
+        compile_error!(concat!(
+            "`",
+            stringify!($other),
+            "` can't be allowed in a checked module, only `i128`, `u128` and `references` can"
+        ))
+    };
+}
+
+/// Declares a fieldless enum which can be used in a `#[checked]` dymod
+/// module.
+///
+/// The enum must have an integer (or `C`) `#[repr]`, so that its layout
+/// can't change between compilations. Enums with the default layout are
+/// rejected at compile time.
+///
+/// # Examples
+///
+/// ```rust
+/// dymod::safe_enum! {
+///     #[repr(u8)]
+///     #[derive(Debug, Clone, Copy, PartialEq)]
+///     pub enum Command {
+///         Jump,
+///         Run = 5,
+///     }
+/// }
+/// ```
+///
+/// ```rust,compile_fail
+/// dymod::safe_enum! {
+///     pub enum Command {
+///         Jump,
+///         Run,
+///     }
+/// }
+/// ```
+///
+/// Note that adding a variant on only one side of the boundary is still
+/// a mismatch: the other side may receive a value it has no variant
+/// for.
+#[macro_export]
+macro_rules! safe_enum {
+    ($($input: tt)*) => {
+        $crate::__dymod_safe_enum! { @attrs [] [] $($input)* }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dymod_safe_enum {
+    (@attrs [$($repr: ident)?] [$($attrs: tt)*] #[repr($newrepr: ident)] $($rest: tt)*) => {
+        $crate::__dymod_safe_enum! { @attrs [$newrepr] [$($attrs)*] $($rest)* }
+    };
+    (@attrs [$($repr: ident)?] [$($attrs: tt)*] #[$($attr: tt)*] $($rest: tt)*) => {
+        $crate::__dymod_safe_enum! { @attrs [$($repr)?] [$($attrs)* #[$($attr)*]] $($rest)* }
+    };
+    (
+        @attrs [$repr: ident] [$($attrs: tt)*]
+        $vis: vis enum $name: ident {
+            $($(#[$($variantattr: tt)*])* $variant: ident $(= $discriminant: expr)?),* $(,)?
+        }
+    ) => {
+        $crate::__dymod_safe_enum! { @repr $repr }
+
+        #[repr($repr)]
+        $($attrs)*
+        $vis enum $name {
+            $($(#[$($variantattr)*])* $variant $(= $discriminant)?),*
+        }
+
+        unsafe impl $crate::DymodSafe for $name {}
+    };
+    (@attrs [] [$($attrs: tt)*] $vis: vis enum $name: ident $body: tt) => {
+        compile_error!(concat!(
+            "`",
+            stringify!($name),
+            "` needs an integer `#[repr]`, as the layout of a `repr(Rust)` enum can change between compilations"
+        ));
+    };
+    (@attrs [$repr: ident] [$($attrs: tt)*] $vis: vis enum $name: ident $body: tt) => {
+        compile_error!(concat!(
+            "`",
+            stringify!($name),
+            "` must be a fieldless enum to be used with dymod"
+        ));
+    };
+    (@repr u8) => {};
+    (@repr u16) => {};
+    (@repr u32) => {};
+    (@repr u64) => {};
+    (@repr usize) => {};
+    (@repr i8) => {};
+    (@repr i16) => {};
+    (@repr i32) => {};
+    (@repr i64) => {};
+    (@repr isize) => {};
+    (@repr C) => {};
+    (@repr $repr: ident) => {
+        compile_error!(concat!(
+            "`#[repr(",
+            stringify!($repr),
+            ")]` isn't an integer repr"
+        ));
+    };
+}
@@ -0,0 +1,65 @@
+//! Reloading when the process receives `SIGUSR1`.
+
+use std::os::raw::c_int;
+
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(any(
+        target_arch = "mips",
+        target_arch = "mips64",
+        target_arch = "sparc",
+        target_arch = "sparc64"
+    ))
+))]
+const SIGUSR1: c_int = 10;
+
+#[cfg(any(
+    all(
+        any(target_os = "linux", target_os = "android"),
+        any(target_arch = "mips", target_arch = "mips64")
+    ),
+    target_os = "solaris",
+    target_os = "illumos"
+))]
+const SIGUSR1: c_int = 16;
+
+// Linux on SPARC numbers its signals like the BSDs
+#[cfg(any(
+    not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "solaris",
+        target_os = "illumos"
+    )),
+    all(
+        any(target_os = "linux", target_os = "android"),
+        any(target_arch = "sparc", target_arch = "sparc64")
+    )
+))]
+const SIGUSR1: c_int = 30;
+
+const SIG_ERR: usize = !0;
+
+extern "C" {
+    fn signal(signum: c_int, handler: usize) -> usize;
+}
+
+extern "C" fn handle_signal(_: c_int) {
+    crate::trigger_reload();
+}
+
+/// Installs a `SIGUSR1` handler which calls `dymod::trigger_reload()`,
+/// so that `kill -USR1 <pid>` reloads every dynamic module on its next
+/// call.
+///
+/// This replaces any existing `SIGUSR1` handler. To use a different
+/// signal, or to do something else as well, install your own handler
+/// and call `trigger_reload()` from it.
+///
+/// # Panics
+///
+/// Panics if the handler can't be installed.
+pub fn reload_on_signal() {
+    let result = unsafe { signal(SIGUSR1, handle_signal as extern "C" fn(c_int) as usize) };
+    assert!(result != SIG_ERR, "Failed to install SIGUSR1 handler");
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A snapshot of the state of a dymod module, as returned by its
+/// `status()` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ModuleStatus {
+    /// The number of times the dylib has been successfully loaded.
+    pub version: usize,
+
+    /// Whether a dylib is currently loaded.
+    pub loaded: bool,
+
+    /// When the dylib was last successfully loaded.
+    pub last_reload: Option<SystemTime>,
+
+    /// The error from the last reload, if it failed. This is cleared
+    /// by the next successful reload.
+    pub last_error: Option<String>,
+
+    /// Whether the dylib is reloaded automatically when it changes.
+    pub auto_reload: bool,
+}
+
+impl ModuleStatus {
+    /// The status of a module stubbed out by the `force-stub` feature.
+    #[doc(hidden)]
+    pub const STUB: ModuleStatus = ModuleStatus {
+        version: 0,
+        loaded: false,
+        last_reload: None,
+        last_error: None,
+        auto_reload: false,
+    };
+}
+
+/// The file a dymod module's dylib was loaded from, as returned by its
+/// `loaded_dylib_info()` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DylibInfo {
+    /// The path of the dylib, rather than of the copy which was loaded.
+    pub path: PathBuf,
+
+    /// The size of the dylib in bytes, when it was loaded.
+    pub size: u64,
+
+    /// When the dylib was last modified before it was loaded.
+    pub modified: SystemTime,
+
+    /// The generation the dylib was loaded as, the same as
+    /// `current_generation()` while it's still loaded.
+    pub generation: usize,
+}
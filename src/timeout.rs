@@ -0,0 +1,81 @@
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+/// An error that occurred while calling a function with
+/// `dymod::call_with_timeout()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CallError {
+    /// The function didn't return within the timeout. It's left running
+    /// on its thread.
+    Timeout,
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallError::Timeout => write!(
+                f,
+                "call timed out, and was left running on its own thread; \
+                 check the dylib for an infinite loop or a deadlock"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Calls `f` on a new thread, and returns what it returns, or
+/// `CallError::Timeout` if it hasn't returned within `timeout`.
+///
+/// This is for catching an infinite loop in a function that's being
+/// worked on, without hanging the host. A thread can't be stopped
+/// safely, so one which times out is abandoned, and keeps running
+/// until it returns or the process exits. That leaks the thread, and
+/// anything it holds.
+///
+/// That includes the lock on the dylib held by a call to a dynamic
+/// module, so the module can't be reloaded until the call returns; a
+/// reload would wait for it. Other modules, and the rest of the host,
+/// keep running, so the hang can at least be reported.
+///
+/// If `f` panics, the panic is resumed on the calling thread.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// let result = dymod::call_with_timeout(
+///     || std::thread::sleep(Duration::from_secs(60)),
+///     Duration::from_millis(10),
+/// );
+/// assert_eq!(result, Err(dymod::CallError::Timeout));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the thread can't be spawned.
+pub fn call_with_timeout<T, F>(f: F, timeout: Duration) -> Result<T, CallError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let call = std::thread::Builder::new()
+        .name("dymod-call".to_owned())
+        .spawn(move || {
+            let _ = sender.send(f());
+        })
+        .expect("Failed to spawn dymod call thread");
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => Ok(result),
+        Err(RecvTimeoutError::Timeout) => Err(CallError::Timeout),
+        Err(RecvTimeoutError::Disconnected) => match call.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("dymod call thread returned without a result"),
+        },
+    }
+}
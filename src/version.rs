@@ -0,0 +1,49 @@
+/// The version of rustc which built dymod, and so the crate using it,
+/// if the build script could find it.
+pub const RUSTC_VERSION: Option<&str> = option_env!("DYMOD_RUSTC_VERSION");
+
+/// The version of rustc which built a subcrate, as exported by
+/// `abi_magic!()`.
+///
+/// This is `#[repr(C)]` rather than a `&str`, so that it can be read
+/// by a crate built with any other version.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct RustcVersion {
+    ptr: *const u8,
+    len: usize,
+}
+
+// It only ever points to a `&'static str`
+unsafe impl Sync for RustcVersion {}
+
+impl RustcVersion {
+    /// Wraps the version embedded by `build::embed_rustc_version()`,
+    /// or `None` if it wasn't.
+    pub const fn new(version: Option<&'static str>) -> Self {
+        match version {
+            Some(version) => RustcVersion {
+                ptr: version.as_ptr(),
+                len: version.len(),
+            },
+            None => RustcVersion {
+                ptr: std::ptr::null(),
+                len: 0,
+            },
+        }
+    }
+
+    /// Returns the version, or `None` if it wasn't embedded.
+    ///
+    /// # Safety
+    ///
+    /// The dylib this was read from must still be loaded.
+    pub unsafe fn read(self) -> Option<String> {
+        if self.ptr.is_null() {
+            None
+        } else {
+            let bytes = std::slice::from_raw_parts(self.ptr, self.len);
+            Some(String::from_utf8_lossy(bytes).into_owned())
+        }
+    }
+}
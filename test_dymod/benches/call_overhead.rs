@@ -0,0 +1,70 @@
+//! Measures how long a call to a dymod function takes, compared with
+//! calling the same function directly.
+//!
+//! Run with `cargo bench` in this directory for the dynamically linked
+//! module, or `cargo bench --features force-static` for the statically
+//! linked one.
+
+#[cfg(not(feature = "force-stub"))]
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dynamic_in_release]
+    pub mod plugin {
+        fn is_even(n: u32) -> bool;
+    }
+}
+
+// Without `force-static`, the dymod module doesn't include the plugin's
+// source, so it can be linked directly as well
+#[cfg(not(any(feature = "force-static", feature = "force-stub")))]
+#[path = "../plugin/src/lib.rs"]
+#[allow(
+    dead_code,
+    improper_ctypes_definitions,
+    clippy::not_unsafe_ptr_arg_deref
+)]
+mod direct;
+
+#[cfg(not(any(feature = "force-static", feature = "force-stub")))]
+fn call_overhead(c: &mut Criterion) {
+    // Load the dylib before measuring anything
+    plugin::is_even(0);
+
+    let mut group = c.benchmark_group("is_even");
+    group.bench_function("direct", |b| b.iter(|| direct::is_even(black_box(7))));
+    group.bench_function("dynamic", |b| b.iter(|| plugin::is_even(black_box(7))));
+    group.bench_function("dynamic, no_reload", |b| {
+        b.iter(|| plugin::no_reload::is_even(black_box(7)))
+    });
+
+    // What every call would cost without the per-thread symbol cache
+    group.bench_function("dynamic, uncached", |b| {
+        b.iter(|| unsafe {
+            plugin::with_library(|lib| {
+                let is_even: dymod::Symbol<extern "C" fn(u32) -> bool> =
+                    lib.get(b"is_even").expect("Failed to get symbol.");
+                is_even(black_box(7))
+            })
+        })
+    });
+    group.finish();
+}
+
+#[cfg(feature = "force-static")]
+fn call_overhead(c: &mut Criterion) {
+    let mut group = c.benchmark_group("is_even");
+    group.bench_function("static", |b| b.iter(|| plugin::is_even(black_box(7))));
+    group.finish();
+}
+
+// Nothing to measure, as the module is stubbed out
+#[cfg(feature = "force-stub")]
+fn call_overhead(_c: &mut Criterion) {}
+
+criterion_group!(benches, call_overhead);
+criterion_main!(benches);
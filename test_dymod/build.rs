@@ -21,16 +21,27 @@ pub extern "C" fn count_sheep(sheep: u32) -> &'static str {
             .expect("Failed to write test lib source.");
     }
 
-    // Compile it (as a dylib)
+    // Compile it, and the test plugin (as dylibs)
     {
         use std::process::Command;
 
-        let _ = Command::new("cargo")
-            .arg("build")
-            .current_dir("subcrate")
-            .output()
-            .unwrap();
+        for dir in ["subcrate", "plugin"] {
+            dymod::build::check_crate_type(dir);
+
+            let _ = Command::new("cargo")
+                .arg("build")
+                .current_dir(dir)
+                .output()
+                .unwrap();
+        }
     }
 
+    // Both are built against this checkout of dymod
+    dymod::build::check_shared_dependency("plugin", "dymod");
+
     println!("cargo:rerun-if-changed=subcrate/src/lib.rs");
+    println!("cargo:rerun-if-changed=plugin/src/lib.rs");
+    println!("cargo:rerun-if-changed=plugin/build.rs");
+    println!("cargo:rerun-if-changed=plugin/plugin.map");
+    println!("cargo::rustc-check-cfg=cfg(versioned_symbols)");
 }
@@ -0,0 +1,6 @@
+#[no_mangle]
+pub extern "C" fn average(samples: *const u16, len: usize) -> u16 {
+    let samples = unsafe { core::slice::from_raw_parts(samples, len) };
+    let total: u32 = samples.iter().map(|&sample| u32::from(sample)).sum();
+    (total / len.max(1) as u32) as u16
+}
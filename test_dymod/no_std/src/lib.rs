@@ -0,0 +1,27 @@
+//! A `#![no_std]` host crate, for testing that statically linked
+//! modules don't need std. It's only ever built, by `no_std_tests`.
+
+#![no_std]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "sensor.rs"]
+    #[checked]
+    pub mod sensor {
+        fn read(channel: u8) -> u16;
+        fn last_sample() -> Option<core::ptr::NonNull<u16>>;
+    }
+
+    #[path = "filter.rs"]
+    #[ffi_safe]
+    #[depends_on(sensor)]
+    pub mod filter {
+        fn average(samples: &[u16]) -> u16;
+    }
+}
+
+pub fn filtered(channel: u8) -> u16 {
+    let samples = [sensor::read(channel), sensor::no_reload::read(channel)];
+    filter::average(&samples)
+}
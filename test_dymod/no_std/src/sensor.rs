@@ -0,0 +1,13 @@
+use core::ptr::NonNull;
+
+static mut SAMPLE: u16 = 0;
+
+#[no_mangle]
+pub extern "C" fn read(channel: u8) -> u16 {
+    u16::from(channel) * 2
+}
+
+#[no_mangle]
+pub extern "C" fn last_sample() -> Option<NonNull<u16>> {
+    NonNull::new(core::ptr::addr_of_mut!(SAMPLE))
+}
@@ -0,0 +1,15 @@
+fn main() {
+    dymod::build::embed_rustc_version();
+
+    // Exports two versions of `versioned_answer`, where symbol versions
+    // are supported
+    println!("cargo::rustc-check-cfg=cfg(versioned_symbols)");
+    let os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let env = std::env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    if os == "linux" && env == "gnu" {
+        let map = concat!(env!("CARGO_MANIFEST_DIR"), "/plugin.map");
+        println!("cargo:rustc-cdylib-link-arg=-Wl,--version-script={}", map);
+        println!("cargo:rustc-cfg=versioned_symbols");
+        println!("cargo:rerun-if-changed=plugin.map");
+    }
+}
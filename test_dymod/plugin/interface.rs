@@ -0,0 +1,9 @@
+dymod::interface! {
+    fn store_value(value: u32);
+
+    /// Returns the last value passed to `store_value()`.
+    fn stored_value() -> u32;
+
+    #[stub = false]
+    fn is_even(n: u32) -> bool;
+}
@@ -0,0 +1,279 @@
+//! A dylib exercising the kinds of functions dymod can bind to.
+//!
+//! Unlike `subcrate`, the tests never modify this crate.
+
+dymod::abi_magic!();
+dymod::alloc_marker!();
+dymod::check_interface!("../interface.rs");
+
+/// An ABI constant for a version of dymod other than this one, for
+/// testing that a mismatch is caught when this dylib is loaded under
+/// the name `mismatched`.
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static __DYMOD_ABI_mismatched: u64 = 0;
+
+/// A rustc version other than the real one, for testing that loading
+/// this dylib under the name `old_rustc` only warns about it.
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static __DYMOD_RUSTC_old_rustc: dymod::RustcVersion =
+    dymod::RustcVersion::new(Some("rustc 1.0.0 (a59807601 2015-05-14)"));
+
+#[no_mangle]
+pub extern "C" fn for_each_up_to(limit: u32, callback: extern "C" fn(u32)) {
+    for i in 0..limit {
+        callback(i);
+    }
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn apply_twice(f: fn(u32) -> u32, value: u32) -> u32 {
+    f(f(value))
+}
+
+static STORED_VALUE: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[no_mangle]
+pub extern "C" fn store_value(value: u32) {
+    STORED_VALUE.store(value, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub extern "C" fn stored_value() -> u32 {
+    STORED_VALUE.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+#[repr(u8)]
+pub enum Direction {
+    Left,
+    Right = 4,
+}
+
+#[no_mangle]
+pub extern "C" fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn add_system(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[no_mangle]
+pub extern "C" fn sum(values: *const f32, len: usize) -> f32 {
+    let values = unsafe { std::slice::from_raw_parts(values, len) };
+    values.iter().sum()
+}
+
+#[no_mangle]
+pub extern "C" fn double_all(values: *mut u32, len: usize) {
+    let values = unsafe { std::slice::from_raw_parts_mut(values, len) };
+    for value in values {
+        *value *= 2;
+    }
+}
+
+dymod::ffi_safe! {
+    /// Writes a frame's label into the host's buffer, returning how many
+    /// bytes it took, without allocating.
+    pub fn render_to(buffer: &mut [u8], frame: u8) -> usize {
+        let label = [b'f', b'r', b'a', b'm', b'e', b' ', b'0' + frame % 10];
+        let len = label.len().min(buffer.len());
+        buffer[..len].copy_from_slice(&label[..len]);
+        len
+    }
+
+    pub fn copy_values(to: &mut [u32], from: &[u32]) -> usize {
+        let len = to.len().min(from.len());
+        to[..len].copy_from_slice(&from[..len]);
+        len
+    }
+}
+
+/// Calls the host's closure with each odd number up to `limit`, as
+/// passed by a `#[ffi_safe]` module's `impl FnMut(u32)` argument.
+#[no_mangle]
+pub extern "C" fn for_each_odd_up_to(
+    limit: u32,
+    callback: extern "C" fn(*mut std::ffi::c_void, u32),
+    closure: *mut std::ffi::c_void,
+) {
+    for n in (1..=limit).step_by(2) {
+        callback(closure, n);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn count_matching(
+    limit: u32,
+    predicate: extern "C" fn(*mut std::ffi::c_void, u32) -> bool,
+    closure: *mut std::ffi::c_void,
+) -> u32 {
+    (0..limit).filter(|&n| predicate(closure, n)).count() as u32
+}
+
+#[no_mangle]
+pub extern "C" fn square_wide(value: u64) -> u128 {
+    value as u128 * value as u128
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C" fn transpose(matrix: [f32; 16]) -> [f32; 16] {
+    let mut transposed = [0.0; 16];
+    for row in 0..4 {
+        for column in 0..4 {
+            transposed[column * 4 + row] = matrix[row * 4 + column];
+        }
+    }
+    transposed
+}
+
+static FOUND_VALUE: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(42);
+
+#[no_mangle]
+pub extern "C" fn find_value(key: u32) -> *mut u32 {
+    match key {
+        0 => FOUND_VALUE.as_ptr(),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Returns the first byte of whatever `data` points to, for declaring
+/// with a different pointer type for each caller.
+#[no_mangle]
+pub extern "C" fn first_byte(data: *const std::ffi::c_void) -> u8 {
+    unsafe { *data.cast::<u8>() }
+}
+
+#[no_mangle]
+pub extern "C" fn version_string() -> *const std::os::raw::c_char {
+    c"plugin 0.1.0".as_ptr()
+}
+
+static NAME_LEN: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+#[no_mangle]
+pub extern "C" fn set_name(name: *const std::os::raw::c_char) {
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    NAME_LEN.store(name.to_bytes().len(), std::sync::atomic::Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub extern "C" fn name_len() -> usize {
+    NAME_LEN.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+static CALLS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+#[no_mangle]
+pub extern "C" fn counted_square(value: u32) -> u32 {
+    CALLS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    value * value
+}
+
+#[no_mangle]
+pub extern "C" fn call_count() -> u32 {
+    CALLS.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+#[no_mangle]
+pub extern "C" fn is_even(n: u32) -> bool {
+    n.is_multiple_of(2)
+}
+
+// Returns any non-zero byte for true, as C code might
+#[no_mangle]
+pub extern "C" fn is_even_byte(n: u32) -> u8 {
+    if n.is_multiple_of(2) {
+        2
+    } else {
+        0
+    }
+}
+
+// `versioned_answer@PLUGIN_1` returns 1, and the default
+// `versioned_answer@@PLUGIN_2` returns 2
+#[cfg(versioned_symbols)]
+mod versioned {
+    #[no_mangle]
+    pub extern "C" fn versioned_answer_v1() -> u32 {
+        1
+    }
+
+    #[no_mangle]
+    pub extern "C" fn versioned_answer_v2() -> u32 {
+        2
+    }
+
+    std::arch::global_asm!(
+        ".symver versioned_answer_v1, versioned_answer@PLUGIN_1",
+        ".symver versioned_answer_v2, versioned_answer@@PLUGIN_2",
+    );
+}
+
+dymod::arity! {
+    #[no_mangle]
+    pub extern "C" fn add_pair(a: u32, b: u32) -> u32 {
+        a + b
+    }
+
+    /// Missing its `extern "C"`, for testing that loading it is refused.
+    #[no_mangle]
+    pub fn rust_abi_pair(a: u32, b: u32) -> u32 {
+        a + b
+    }
+}
+
+static STATE_LEN: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Snapshots the stored value, for `reload_preserving_state()`.
+#[no_mangle]
+pub extern "C" fn save_state() -> *mut u8 {
+    let state: Box<[u8]> = Box::new(stored_value().to_le_bytes());
+    STATE_LEN.store(state.len(), std::sync::atomic::Ordering::SeqCst);
+    Box::into_raw(state) as *mut u8
+}
+
+#[no_mangle]
+pub extern "C" fn state_len() -> usize {
+    STATE_LEN.load(std::sync::atomic::Ordering::SeqCst)
+}
+
+#[no_mangle]
+pub extern "C" fn load_state(state: *const u8, len: usize) {
+    let state = unsafe { std::slice::from_raw_parts(state, len) };
+    store_value(u32::from_le_bytes(state.try_into().unwrap()));
+}
+
+/// Forwards the panics caught by `checked_divide()` to the host.
+#[no_mangle]
+pub extern "C" fn __dymod_init(forward_panic: dymod::ForwardPanic) {
+    dymod::install_panic_forwarding(forward_panic);
+}
+
+/// Divides `a` by `b`, or returns 0 after panicking if `b` is 0.
+#[no_mangle]
+pub extern "C" fn checked_divide(a: u32, b: u32) -> u32 {
+    std::panic::catch_unwind(|| a / b).unwrap_or(0)
+}
+
+/// Named with a leading underscore, as some linkers name symbols, for
+/// testing that `prefixed_answer` is found too.
+#[no_mangle]
+pub extern "C" fn _prefixed_answer() -> u32 {
+    42
+}
+
+/// Never returns, for testing that a call can time out.
+#[no_mangle]
+pub extern "C" fn hang(_value: u32) -> u32 {
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,90 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use dymod::{DynamicModule, ReloadError};
+
+const MANIFEST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod abi magic tests");
+
+static PLUGIN: DynamicModule =
+    DynamicModule::new(env!("CARGO_MANIFEST_DIR"), "plugin", &["stored_value"]);
+
+static MISMATCHED: DynamicModule = DynamicModule::new(MANIFEST_DIR, "mismatched", &[]);
+
+static OLD_RUSTC: DynamicModule = DynamicModule::new(MANIFEST_DIR, "old_rustc", &[]);
+
+fn copy_plugin_as(name: &str) {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(MANIFEST_DIR, name);
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+}
+
+#[test]
+fn matching_abi_magic_is_loaded() {
+    PLUGIN.force_reload().expect("Failed to load dylib.");
+    PLUGIN.check().expect("Failed to check dylib.");
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn mismatched_abi_magic_fails_to_load() {
+    // The plugin also exports a wrong constant for a module called `mismatched`
+    copy_plugin_as("mismatched");
+
+    match MISMATCHED.force_reload() {
+        Err(ReloadError::AbiVersionMismatch {
+            expected, found, ..
+        }) => {
+            assert_eq!(expected, dymod::ABI_MAGIC);
+            assert_eq!(found, 0);
+        }
+        other => panic!("Expected AbiVersionMismatch, got {:?}", other),
+    }
+    assert!(!MISMATCHED.status().loaded);
+}
+
+#[test]
+fn rustc_version_is_embedded() {
+    let version = PLUGIN.with_library(|lib| unsafe {
+        let version = lib
+            .get::<*const dymod::RustcVersion>(b"__DYMOD_RUSTC_plugin")
+            .expect("Failed to find rustc version.");
+        (**version).read()
+    });
+
+    assert!(version.is_some());
+    assert_eq!(version.as_deref(), dymod::RUSTC_VERSION);
+}
+
+/// Collects diagnostics written by dymod.
+#[derive(Clone, Default)]
+struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Diagnostics {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn mismatched_rustc_version_is_warned_about_but_still_loads() {
+    let diagnostics = Diagnostics::default();
+    dymod::set_diagnostics_writer(Box::new(diagnostics.clone()));
+
+    // The plugin also exports an old version for a module called `old_rustc`
+    copy_plugin_as("old_rustc");
+
+    OLD_RUSTC.force_reload().expect("Failed to load dylib.");
+    assert!(OLD_RUSTC.status().loaded);
+
+    let diagnostics = String::from_utf8(diagnostics.0.lock().unwrap().clone()).unwrap();
+    assert!(diagnostics.starts_with("dymod: "));
+    assert!(diagnostics.contains("was built by rustc 1.0.0"));
+}
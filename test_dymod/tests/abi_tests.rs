@@ -0,0 +1,16 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[abi = "system"]
+    pub mod plugin {
+        fn add_system(a: u32, b: u32) -> u32;
+    }
+}
+
+#[test]
+fn system_abi_function_is_called() {
+    assert_eq!(plugin::add_system(2, 3), 5);
+}
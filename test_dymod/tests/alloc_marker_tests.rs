@@ -0,0 +1,51 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use dymod::dymod;
+
+// The plugin uses the system allocator, so this claims not to
+dymod::alloc_marker!(custom);
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[checked(alloc)]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+/// Collects diagnostics written by dymod.
+#[derive(Clone, Default)]
+struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Diagnostics {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn mismatched_allocator_is_warned_about_but_still_loads() {
+    let diagnostics = Diagnostics::default();
+    dymod::set_diagnostics_writer(Box::new(diagnostics.clone()));
+
+    plugin::force_reload().expect("Failed to load dylib.");
+    assert_eq!(plugin::stored_value(), 0);
+
+    let diagnostics = String::from_utf8(diagnostics.0.lock().unwrap().clone()).unwrap();
+    assert!(diagnostics
+        .contains("uses the system allocator, but is loaded by a crate using a custom allocator"));
+}
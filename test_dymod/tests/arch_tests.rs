@@ -0,0 +1,49 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::{DynamicModule, ReloadError};
+
+const MANIFEST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod arch tests");
+
+static FOREIGN: DynamicModule = DynamicModule::new(MANIFEST_DIR, "plugin", &["stored_value"]);
+
+#[test]
+fn built_dylib_matches_this_process() {
+    let dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    assert_eq!(dymod::dylib_arch(&dylib), Some(std::env::consts::ARCH));
+}
+
+#[test]
+fn unknown_file_has_no_architecture() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    assert_eq!(dymod::dylib_arch(&path), None);
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(all(target_os = "linux", not(feature = "strict")))]
+#[test]
+fn dylib_for_another_architecture_is_refused() {
+    let (machine, found) = if std::env::consts::ARCH == "aarch64" {
+        (62u16, "x86_64")
+    } else {
+        (183u16, "aarch64")
+    };
+
+    let mut bytes = std::fs::read(dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin"))
+        .expect("Failed to read dylib.");
+    bytes[18..20].copy_from_slice(&machine.to_le_bytes());
+    let dylib = dymod::dylib_path(MANIFEST_DIR, "plugin");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::write(&dylib, bytes).expect("Failed to write dylib.");
+
+    match FOREIGN.force_reload() {
+        Err(ReloadError::ArchitectureMismatch {
+            expected,
+            found: actual,
+            ..
+        }) => {
+            assert_eq!(expected, std::env::consts::ARCH);
+            assert_eq!(actual, found);
+        }
+        other => panic!("Expected ArchitectureMismatch, got {:?}", other),
+    }
+}
@@ -0,0 +1,74 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::{dymod, DynamicModule, ReloadError};
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn add_pair(a: u32, b: u32) -> u32;
+        fn stored_value() -> u32;
+    }
+}
+
+static MISDECLARED: DynamicModule =
+    DynamicModule::new(env!("CARGO_MANIFEST_DIR"), "plugin", &["add_pair"]).arities(&[1]);
+
+mod linkage {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn rust_abi_pair(a: u32, b: u32) -> u32;
+        }
+    }
+}
+
+#[test]
+fn matching_arity_loads() {
+    assert_eq!(plugin::add_pair(2, 3), 5);
+    plugin::check().expect("Failed to check dylib.");
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn mismatched_arity_fails_to_load() {
+    match MISDECLARED.force_reload() {
+        Err(ReloadError::ArityMismatch {
+            symbol,
+            expected,
+            found,
+            ..
+        }) => {
+            assert_eq!(symbol, "add_pair");
+            assert_eq!(expected, 1);
+            assert_eq!(found, 2);
+        }
+        other => panic!("Expected ArityMismatch, got {:?}", other),
+    }
+    assert!(!MISDECLARED.status().loaded);
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn function_without_extern_c_fails_to_load() {
+    match linkage::plugin::force_reload() {
+        Err(ReloadError::AbiMismatch {
+            function, expected, ..
+        }) => {
+            assert_eq!(function, "rust_abi_pair");
+            assert_eq!(expected, "C");
+        }
+        other => panic!("Expected AbiMismatch, got {:?}", other),
+    }
+    assert!(!linkage::plugin::status().loaded);
+}
@@ -0,0 +1,68 @@
+#![cfg(all(
+    feature = "async",
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::Thread;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[async]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+const CALLS: usize = 8;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn concurrent_calls_after_a_change_reload_once() {
+    block_on(plugin::nonblocking::stored_value());
+    let version = plugin::status().version;
+
+    // Start every call before any of them finishes, so that they all
+    // see the same change
+    dymod::trigger_reload();
+    let mut calls: Vec<_> = (0..CALLS)
+        .map(|_| Box::pin(plugin::nonblocking::stored_value()))
+        .collect();
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    calls.retain_mut(|call| call.as_mut().poll(&mut cx).is_pending());
+    for call in calls {
+        block_on(call);
+    }
+
+    assert_eq!(plugin::status().version, version + 1);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "async")]
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::Thread;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[async]
+    pub mod plugin {
+        #[stub = ()]
+        fn store_value(value: u32);
+        #[stub = 0]
+        fn stored_value() -> u32;
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn async_functions_call_into_module() {
+    block_on(plugin::nonblocking::store_value(5));
+    let value = block_on(plugin::nonblocking::stored_value());
+
+    if cfg!(feature = "force-stub") {
+        assert_eq!(value, 0);
+    } else {
+        assert_eq!(value, 5);
+    }
+}
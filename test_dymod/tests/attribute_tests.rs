@@ -0,0 +1,28 @@
+//! Tests that attributes are forwarded to the generated items.
+
+#![cfg_attr(
+    all(not(feature = "force-static"), debug_assertions),
+    deny(missing_docs)
+)]
+// The subcrate returns a `&str` from an `extern "C"` function, which is
+// only allowed by the attribute on the module while statically linked
+#![deny(improper_ctypes_definitions)]
+
+use dymod::dymod;
+
+dymod! {
+    /// Documentation for the module.
+    #[path = "../subcrate/src/lib.rs"]
+    #[allow(dead_code, improper_ctypes_definitions)]
+    pub mod subcrate {
+        /// Documentation for the function.
+        #[inline]
+        #[stub = "Stubbed"]
+        fn count_sheep(sheep: u32) -> &'static str;
+    }
+}
+
+#[test]
+fn documented_module_is_usable() {
+    assert!(!subcrate::count_sheep(3).is_empty());
+}
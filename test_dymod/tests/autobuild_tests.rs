@@ -0,0 +1,88 @@
+#![cfg(feature = "autobuild")]
+
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use dymod::{DynamicModule, ReloadError};
+
+const MANIFEST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod autobuild tests");
+
+static PLUGIN: DynamicModule = DynamicModule::new(MANIFEST_DIR, "plugin", &["stored_value"]);
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Collects diagnostics written by dymod.
+#[derive(Clone, Default)]
+struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Diagnostics {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the subcrate's source, as if it had been saved `offset`
+/// seconds from now.
+fn save_source(offset: u64) {
+    let lib = Path::new(MANIFEST_DIR).join("plugin/src/lib.rs");
+    std::fs::write(&lib, format!("// Saved {}", offset)).expect("Failed to write lib.");
+    std::fs::File::options()
+        .write(true)
+        .open(&lib)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(offset)))
+        .expect("Failed to touch lib.");
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out.");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn saving_source_rebuilds_and_reloads() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(MANIFEST_DIR, "plugin");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::create_dir_all(Path::new(MANIFEST_DIR).join("plugin/src"))
+        .expect("Failed to create test src dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+    save_source(0);
+
+    let diagnostics = Diagnostics::default();
+    dymod::set_diagnostics_writer(Box::new(diagnostics.clone()));
+    PLUGIN.set_builder(|| {
+        BUILDS.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+    PLUGIN.force_reload().expect("Failed initial load.");
+
+    save_source(10);
+    wait_until(|| PLUGIN.status().version == 2);
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+
+    // A failed build keeps the old dylib, and says why
+    PLUGIN.set_builder(|| {
+        Err(ReloadError::BuildFailed {
+            path: "plugin".into(),
+            error: std::io::Error::other("cargo build failed"),
+            stderr: "error: expected `;`".to_owned(),
+        })
+    });
+    save_source(20);
+    wait_until(|| PLUGIN.status().last_error.is_some());
+    assert_eq!(PLUGIN.status().version, 2);
+
+    let diagnostics = String::from_utf8(diagnostics.0.lock().unwrap().clone()).unwrap();
+    assert!(diagnostics.contains("automatic rebuild failed"));
+    assert!(diagnostics.contains("error: expected `;`"));
+}
@@ -0,0 +1,102 @@
+use dymod::build::{crate_types, locked_versions};
+
+#[test]
+fn crate_types_are_read_from_lib_section() {
+    let manifest = r#"
+[package]
+name = "subcrate"
+crate-type = ["bin"]
+
+[lib]
+crate-type = ["cdylib"] # For hotswapping
+
+[dependencies]
+"#;
+
+    assert_eq!(crate_types(manifest), ["cdylib"]);
+}
+
+#[test]
+fn crate_types_can_span_several_lines() {
+    let manifest = r#"
+[lib]
+name = "subcrate"
+crate-type = [
+    "rlib",
+    "dylib",
+]
+"#;
+
+    assert_eq!(crate_types(manifest), ["rlib", "dylib"]);
+}
+
+#[test]
+fn crate_types_can_use_dotted_key() {
+    assert_eq!(crate_types(r#"lib.crate-type = ["dylib"]"#), ["dylib"]);
+}
+
+#[test]
+fn crate_types_are_empty_without_lib_section() {
+    let manifest = r#"
+[package]
+name = "subcrate"
+"#;
+
+    assert!(crate_types(manifest).is_empty());
+}
+
+#[test]
+fn locked_versions_are_read_from_matching_packages() {
+    let lockfile = r#"
+# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "game_types"
+version = "0.3.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "game_types_derive"
+version = "1.0.0"
+
+[[package]]
+name = "game_types"
+version = "0.2.1"
+
+[[package]]
+name = "host"
+version = "0.1.0"
+dependencies = [
+ "game_types 0.2.1",
+ "game_types 0.3.0",
+]
+"#;
+
+    assert_eq!(locked_versions(lockfile, "game_types"), ["0.2.1", "0.3.0"]);
+    assert_eq!(locked_versions(lockfile, "game_types_derive"), ["1.0.0"]);
+    assert!(locked_versions(lockfile, "other").is_empty());
+}
+
+#[test]
+fn scaffold_creates_dylib_subcrate_without_overwriting() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("dymod scaffold tests")
+        .join("scaffolded");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    dymod::build::scaffold(&dir).expect("Failed to scaffold subcrate.");
+
+    let manifest = std::fs::read_to_string(dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains(r#"name = "scaffolded""#));
+    assert_eq!(crate_types(&manifest), ["dylib"]);
+    assert!(dir.join("src").join("lib.rs").exists());
+
+    std::fs::write(dir.join("src").join("lib.rs"), "// Mine").unwrap();
+    dymod::build::scaffold(&dir).expect("Failed to scaffold subcrate again.");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("src").join("lib.rs")).unwrap(),
+        "// Mine"
+    );
+}
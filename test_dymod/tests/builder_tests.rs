@@ -0,0 +1,115 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dymod::{dymod, DynamicModule, ReloadError};
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn rebuild_runs_builder_before_reloading() {
+    // Without a builder, it runs `cargo build`
+    plugin::rebuild().expect("Failed initial build.");
+    assert_eq!(plugin::status().version, 1);
+    assert!(plugin::last_build_output().is_some());
+
+    plugin::set_builder(|| {
+        BUILDS.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+    plugin::rebuild().expect("Failed to rebuild.");
+    assert_eq!(BUILDS.load(Ordering::SeqCst), 1);
+    assert_eq!(plugin::status().version, 2);
+
+    // A failed build keeps the old dylib
+    plugin::set_builder(|| {
+        Err(ReloadError::BuildFailed {
+            path: "plugin".into(),
+            error: std::io::Error::other("cargo build failed"),
+            stderr: "error: expected `;`".to_owned(),
+        })
+    });
+    match plugin::rebuild() {
+        Err(ReloadError::BuildFailed { error, stderr, .. }) => {
+            assert_eq!(error.to_string(), "cargo build failed");
+            assert_eq!(stderr, "error: expected `;`");
+        }
+        other => panic!("Expected BuildFailed, got {:?}", other),
+    }
+
+    let status = plugin::status();
+    assert!(status.loaded);
+    assert_eq!(status.version, 2);
+    assert!(status
+        .last_error
+        .unwrap()
+        .contains("failed to build subcrate in plugin: cargo build failed"));
+    assert_eq!(
+        plugin::last_build_output().as_deref(),
+        Some("error: expected `;`")
+    );
+}
+
+const BROKEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod builder tests");
+
+static BROKEN: DynamicModule = DynamicModule::new(BROKEN_DIR, "broken", &[]);
+
+#[test]
+fn failed_cargo_build_reports_compiler_errors() {
+    let src = std::path::Path::new(BROKEN_DIR).join("broken/src");
+    std::fs::create_dir_all(&src).expect("Failed to create test crate.");
+    std::fs::write(
+        src.join("../Cargo.toml"),
+        "[package]\nname = \"broken\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n",
+    )
+    .expect("Failed to write manifest.");
+    std::fs::write(src.join("lib.rs"), "pub fn broken() -> u32 { 1 + }\n")
+        .expect("Failed to write lib.");
+
+    match BROKEN.rebuild() {
+        Err(ReloadError::BuildFailed { stderr, .. }) => assert!(stderr.contains("error")),
+        other => panic!("Expected BuildFailed, got {:?}", other),
+    }
+    assert!(BROKEN.last_build_output().unwrap().contains("broken"));
+    assert!(!BROKEN.status().loaded);
+}
+
+mod optimized {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[dylib_build_profile = "release"]
+        #[debug_path]
+        pub mod plugin {
+            fn store_value(value: u32);
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+#[test]
+fn release_profile_module_builds_and_loads_release_dylib() {
+    use optimized::plugin;
+
+    let path = plugin::dylib_path();
+    assert!(path.parent().unwrap().ends_with("plugin/target/release"));
+    assert_eq!(std::path::Path::new(plugin::RESOLVED_DYLIB_PATH), path);
+
+    plugin::rebuild().expect("Failed release build.");
+    assert!(path.exists());
+    plugin::store_value(5);
+    assert_eq!(plugin::stored_value(), 5);
+}
@@ -0,0 +1,83 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::{dymod, safe_enum};
+
+safe_enum! {
+    #[repr(u8)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Direction {
+        Left,
+        Right = 4,
+    }
+}
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[checked(allow(u128))]
+    pub mod plugin {
+        fn opposite(direction: Direction) -> Direction;
+        fn square_wide(value: u64) -> u128;
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+        fn is_even(n: u32) -> bool;
+
+        // Declared as returning `bool`, but really returns `u8`
+        fn is_even_byte(n: u32) -> bool;
+    }
+}
+
+#[test]
+fn repr_int_enum_is_passed_to_checked_module() {
+    assert_eq!(plugin::opposite(Direction::Left), Direction::Right);
+    assert_eq!(plugin::opposite(Direction::Right), Direction::Left);
+}
+
+#[test]
+fn safe_enum_keeps_discriminants() {
+    assert_eq!(Direction::Right as u8, 4);
+}
+
+#[test]
+fn allowed_u128_is_returned_from_checked_module() {
+    assert_eq!(
+        plugin::square_wide(u64::MAX),
+        u64::MAX as u128 * u64::MAX as u128
+    );
+}
+
+#[test]
+fn bool_is_returned_from_checked_module() {
+    assert!(plugin::is_even(4));
+    assert!(!plugin::is_even(5));
+}
+
+#[test]
+fn bool_returned_as_other_byte_is_normalized() {
+    let even = plugin::is_even_byte(4);
+    assert!(even);
+    assert_eq!(even as u8, 1);
+    assert!(!plugin::is_even_byte(5));
+}
+
+mod references {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[checked(allow(references))]
+        pub mod plugin {
+            fn find_value(key: u32) -> Option<&'static u32>;
+        }
+    }
+}
+
+#[test]
+fn allowed_reference_is_returned_from_checked_module() {
+    assert_eq!(references::plugin::find_value(0), Some(&42));
+    assert_eq!(references::plugin::find_value(1), None);
+}
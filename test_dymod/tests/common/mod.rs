@@ -0,0 +1,28 @@
+//! Helpers for the tests which change the plugin's dylib.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Copies the plugin's dylib to `dylib`, where a module loads it from,
+/// so that changing it doesn't change the one other tests load.
+pub fn copy_dylib(dylib: &Path) {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::copy(built_dylib, dylib).expect("Failed to copy dylib.");
+}
+
+/// Changes the contents of `dylib` as well as its modified time, which
+/// is set `offset` seconds from now, so that it's a change with the
+/// `content-hash` feature too.
+pub fn change_dylib(dylib: &Path, offset: u64) {
+    File::options()
+        .append(true)
+        .open(dylib)
+        .and_then(|mut file| {
+            file.write_all(b"changed")?;
+            file.set_modified(SystemTime::now() + Duration::from_secs(offset))
+        })
+        .expect("Failed to change plugin dylib.");
+}
@@ -0,0 +1,49 @@
+#![cfg(all(
+    feature = "content-hash",
+    not(any(feature = "force-static", feature = "force-stub"))
+))]
+
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+use dymod::DynamicModule;
+
+const MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod content hash tests"
+);
+
+static PLUGIN: DynamicModule = DynamicModule::new(MANIFEST_DIR, "plugin", &["stored_value"]);
+
+fn set_modified(path: &std::path::Path, offset: u64) {
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(offset))
+        .unwrap();
+}
+
+#[test]
+fn identical_rebuild_is_not_reloaded() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(MANIFEST_DIR, "plugin");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    assert!(PLUGIN.reload_if_changed().expect("Failed initial load."));
+
+    // Only the modified time changes
+    set_modified(&dylib, 10);
+    assert!(!PLUGIN.pending_reload());
+    assert!(!PLUGIN.reload_if_changed().expect("Failed to check dylib."));
+    assert_eq!(PLUGIN.status().version, 1);
+
+    // The contents change too
+    std::fs::File::options()
+        .append(true)
+        .open(&dylib)
+        .and_then(|mut file| file.write_all(b"changed"))
+        .expect("Failed to change dylib.");
+    set_modified(&dylib, 20);
+    assert!(PLUGIN.pending_reload());
+    assert!(PLUGIN.reload_if_changed().expect("Failed to reload dylib."));
+    assert_eq!(PLUGIN.status().version, 2);
+}
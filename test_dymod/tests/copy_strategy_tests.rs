@@ -0,0 +1,191 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::{CopyStrategy, DynamicModule};
+
+const MANIFEST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod read-only tests");
+
+static PLUGIN: DynamicModule = DynamicModule::new(MANIFEST_DIR, "plugin", &["stored_value"]);
+
+const RENAMED_MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod atomic rename tests"
+);
+
+static RENAMED: DynamicModule =
+    DynamicModule::new(RENAMED_MANIFEST_DIR, "plugin", &["stored_value"]);
+
+const COPIED_MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod copy directory tests"
+);
+
+static COPIED: DynamicModule = DynamicModule::new(COPIED_MANIFEST_DIR, "plugin", &["stored_value"]);
+
+const DEFAULT_MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod platform default tests"
+);
+
+static DEFAULT: DynamicModule =
+    DynamicModule::new(DEFAULT_MANIFEST_DIR, "plugin", &["stored_value"]);
+
+fn set_read_only(dir: &std::path::Path, read_only: bool) {
+    let mut permissions = std::fs::metadata(dir).unwrap().permissions();
+    permissions.set_readonly(read_only);
+    std::fs::set_permissions(dir, permissions).unwrap();
+}
+
+fn entries(dir: &std::path::Path) -> Vec<std::ffi::OsString> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn never_copying_loads_without_writing_to_dylib_directory() {
+    // Lay out a copy of the plugin's dylib in a read-only directory
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(MANIFEST_DIR, "plugin");
+    let dir = dylib.parent().unwrap();
+    if dir.exists() {
+        set_read_only(dir, false);
+        std::fs::remove_dir_all(dir).expect("Failed to clear test dir.");
+    }
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+    set_read_only(dir, true);
+
+    PLUGIN.set_copy_strategy(CopyStrategy::Never);
+    PLUGIN.force_reload().expect("Failed to load dylib.");
+    PLUGIN.force_reload().expect("Failed to reload dylib.");
+    PLUGIN.check().expect("Failed to check dylib.");
+
+    let stored_value = unsafe {
+        PLUGIN.with_library(|lib| {
+            let stored_value: dymod::Symbol<extern "C" fn() -> u32> =
+                lib.get(b"stored_value").expect("Failed to get symbol.");
+            stored_value()
+        })
+    };
+    assert_eq!(stored_value, 0);
+    assert_eq!(PLUGIN.status().version, 2);
+    assert_eq!(entries(dir), [dylib.file_name().unwrap()]);
+
+    set_read_only(dir, false);
+}
+
+#[test]
+fn atomic_rename_moves_new_dylib_into_place() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(RENAMED_MANIFEST_DIR, "plugin");
+    let new_dylib = dymod::new_dylib_path(&dylib);
+    let dir = dylib.parent().unwrap();
+    if dir.exists() {
+        std::fs::remove_dir_all(dir).expect("Failed to clear test dir.");
+    }
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    // Without a new version, the first load copies the original
+    RENAMED.set_copy_strategy(CopyStrategy::AtomicRename);
+    assert!(RENAMED.reload_if_changed().expect("Failed to load dylib."));
+    assert!(!RENAMED.pending_reload());
+
+    // Writing a new version marks the dylib as changed
+    std::fs::copy(&built_dylib, &new_dylib).expect("Failed to copy dylib.");
+    assert!(RENAMED.pending_reload());
+    assert!(RENAMED
+        .reload_if_changed()
+        .expect("Failed to reload dylib."));
+    assert!(!RENAMED.pending_reload());
+
+    assert_eq!(RENAMED.status().version, 2);
+    assert_eq!(
+        entries(dir),
+        [
+            dylib.file_name().unwrap(),
+            RENAMED.copy_path(0).file_name().unwrap(),
+            RENAMED.copy_path(1).file_name().unwrap()
+        ]
+    );
+}
+
+#[test]
+fn versioned_copies_go_in_copy_directory() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(COPIED_MANIFEST_DIR, "plugin");
+    let dir = dylib.parent().unwrap();
+    let copies = std::path::Path::new(COPIED_MANIFEST_DIR).join("copies");
+    if std::path::Path::new(COPIED_MANIFEST_DIR).exists() {
+        std::fs::remove_dir_all(COPIED_MANIFEST_DIR).expect("Failed to clear test dir.");
+    }
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    COPIED.set_copy_strategy(CopyStrategy::Versioned);
+    COPIED.set_copy_directory(&copies);
+    COPIED.force_reload().expect("Failed to load dylib.");
+    COPIED.force_reload().expect("Failed to reload dylib.");
+
+    assert_eq!(COPIED.status().version, 2);
+    assert_eq!(entries(dir), [dylib.file_name().unwrap()]);
+    assert_eq!(
+        entries(&copies),
+        [
+            COPIED.copy_path(0).file_name().unwrap(),
+            COPIED.copy_path(1).file_name().unwrap()
+        ]
+    );
+}
+
+/// Lays out a copy of the plugin's dylib in an empty directory, and
+/// loads it twice with the default strategy.
+fn load_with_platform_default() -> (std::path::PathBuf, Vec<std::ffi::OsString>) {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(DEFAULT_MANIFEST_DIR, "plugin");
+    let dir = dylib.parent().unwrap();
+    if dir.exists() {
+        std::fs::remove_dir_all(dir).expect("Failed to clear test dir.");
+    }
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    assert_eq!(CopyStrategy::default(), CopyStrategy::PlatformDefault);
+    DEFAULT.force_reload().expect("Failed to load dylib.");
+    DEFAULT.force_reload().expect("Failed to reload dylib.");
+    assert_eq!(DEFAULT.status().version, 2);
+
+    let entries = entries(dir);
+    (dylib, entries)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn platform_default_loads_without_copying_on_linux() {
+    assert_eq!(CopyStrategy::PlatformDefault.resolve(), CopyStrategy::Never);
+
+    let (dylib, entries) = load_with_platform_default();
+    assert_eq!(entries, [dylib.file_name().unwrap()]);
+}
+
+#[cfg(not(target_os = "linux"))]
+#[test]
+fn platform_default_copies_elsewhere() {
+    assert_eq!(
+        CopyStrategy::PlatformDefault.resolve(),
+        CopyStrategy::Versioned
+    );
+
+    let (dylib, entries) = load_with_platform_default();
+    assert_eq!(
+        entries,
+        [
+            dylib.file_name().unwrap(),
+            DEFAULT.copy_path(0).file_name().unwrap(),
+            DEFAULT.copy_path(1).file_name().unwrap()
+        ]
+    );
+}
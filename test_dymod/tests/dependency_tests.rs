@@ -0,0 +1,29 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[dependency = plugin]
+    pub mod plugin {
+        fn opposite(direction: Direction) -> Direction;
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn types_are_exported_from_the_dependency() {
+    assert!(matches!(
+        plugin::opposite(plugin::Direction::Left),
+        plugin::Direction::Right
+    ));
+    assert!(matches!(
+        plugin::opposite(plugin::Direction::Right),
+        plugin::Direction::Left
+    ));
+}
+
+#[test]
+fn functions_are_the_modules_own() {
+    assert_eq!(plugin::FUNCTIONS, ["opposite", "stored_value"]);
+    assert_eq!(plugin::stored_value(), 0);
+}
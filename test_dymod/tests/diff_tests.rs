@@ -0,0 +1,33 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+        fn missing_function();
+    }
+}
+
+#[test]
+fn reload_with_diff_returns_functions_which_moved() {
+    assert_eq!(
+        plugin::reload_with_diff().expect("Failed to load dylib."),
+        ["store_value", "stored_value"]
+    );
+
+    // The same dylib again, so nothing has moved
+    assert!(plugin::reload_with_diff()
+        .expect("Failed to reload dylib.")
+        .is_empty());
+    assert_eq!(plugin::current_generation(), 2);
+}
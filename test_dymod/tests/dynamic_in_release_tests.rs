@@ -0,0 +1,19 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../subcrate/src/lib.rs"]
+    #[dynamic_in_release]
+    pub mod subcrate {
+        fn count_sheep(sheep: u32) -> &'static str;
+    }
+}
+
+#[test]
+fn marked_module_is_dynamically_loaded_in_any_mode() {
+    // Only the dynamic module has a `reload` function
+    subcrate::reload();
+
+    assert!(!subcrate::count_sheep(3).is_empty());
+}
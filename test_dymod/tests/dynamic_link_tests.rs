@@ -1,7 +1,10 @@
-#![cfg(any(
-    feature = "force-dynamic",
-    feature = "auto-reload",
-    all(not(feature = "force-static"), debug_assertions,)
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
 ))]
 
 use dymod::dymod;
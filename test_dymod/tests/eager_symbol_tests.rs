@@ -0,0 +1,91 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../subcrate/src/lib.rs"]
+    pub mod subcrate {
+        fn count_sheep(sheep: u32) -> &'static str;
+        fn count_goats(goats: u32) -> &'static str;
+    }
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn eager_resolution_fails_reload_on_missing_symbol_and_keeps_old_library() {
+    // Only a copy can be loaded alongside the old library
+    subcrate::set_copy_strategy(dymod::CopyStrategy::Versioned);
+    subcrate::force_reload().expect("Failed initial load.");
+    let sheep = subcrate::count_sheep(3);
+
+    subcrate::set_eager_symbol_resolution(true);
+    match subcrate::force_reload() {
+        Err(dymod::ReloadError::SymbolNotFound { symbol, .. }) => assert_eq!(symbol, "count_goats"),
+        other => panic!("Expected SymbolNotFound, got {:?}", other),
+    }
+
+    assert_eq!(subcrate::count_sheep(3), sheep);
+}
+
+mod validated {
+    dymod::dymod! {
+        #[path = "../subcrate/src/lib.rs"]
+        pub mod subcrate {
+            fn count_sheep(sheep: u32) -> &'static str;
+            fn count_goats(goats: u32) -> &'static str;
+        }
+
+        #[path = "../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn store_value(value: u32);
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn validated_resolution_fails_reload_on_missing_symbol() {
+    use validated::subcrate;
+
+    subcrate::set_symbol_resolution(dymod::SymbolResolution::ValidateEager);
+    match subcrate::force_reload() {
+        Err(dymod::ReloadError::SymbolNotFound { symbol, .. }) => assert_eq!(symbol, "count_goats"),
+        other => panic!("Expected SymbolNotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn validated_resolution_still_resolves_lazily() {
+    use validated::plugin;
+
+    plugin::set_symbol_resolution(dymod::SymbolResolution::ValidateEager);
+    plugin::force_reload().expect("Failed to load dylib.");
+
+    // Looked up on this thread, and again on another
+    plugin::store_value(6);
+    assert_eq!(plugin::stored_value(), 6);
+    assert_eq!(std::thread::spawn(plugin::stored_value).join().unwrap(), 6);
+}
+
+#[test]
+fn check_reports_missing_symbol() {
+    match subcrate::check() {
+        Err(dymod::ReloadError::SymbolNotFound { symbol, .. }) => assert_eq!(symbol, "count_goats"),
+        other => panic!("Expected SymbolNotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn declared_functions_are_listed() {
+    assert_eq!(subcrate::FUNCTIONS, ["count_sheep", "count_goats"]);
+}
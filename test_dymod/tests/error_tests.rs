@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+#[cfg(not(any(feature = "force-static", feature = "force-stub")))]
+use dymod::DynamicModule;
+use dymod::ReloadError;
+
+fn path() -> PathBuf {
+    PathBuf::from("/plugins/libplugin.so")
+}
+
+fn not_found() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, "file not found")
+}
+
+#[test]
+fn load_failed_suggests_building_the_subcrate() {
+    let error = ReloadError::LoadFailed {
+        path: path(),
+        error: not_found(),
+    };
+    let message = error.to_string();
+    assert!(message.contains("failed to load dylib at /plugins/libplugin.so: file not found"));
+    assert!(message.contains("cargo build"));
+    assert!(message.contains(r#"crate-type = ["dylib"]"#));
+    assert_eq!(error.source().unwrap().to_string(), "file not found");
+}
+
+#[test]
+fn every_error_names_its_path() {
+    let errors = [
+        ReloadError::CopyFailed {
+            path: path(),
+            error: not_found(),
+        },
+        ReloadError::ReadFailed {
+            path: path(),
+            error: not_found(),
+        },
+        ReloadError::SymbolNotFound {
+            symbol: "count_sheep",
+            path: path(),
+        },
+        ReloadError::BuildFailed {
+            path: path(),
+            error: std::io::Error::other("cargo build failed"),
+            stderr: "error: expected `;`".to_owned(),
+        },
+        ReloadError::ReentrantReload { path: path() },
+        ReloadError::ArityMismatch {
+            symbol: "count_sheep",
+            path: path(),
+            expected: 1,
+            found: 2,
+        },
+        ReloadError::AbiMismatch {
+            function: "count_sheep",
+            path: path(),
+            expected: "C",
+        },
+        ReloadError::AbiVersionMismatch {
+            path: path(),
+            expected: dymod::ABI_MAGIC,
+            found: 0,
+        },
+        ReloadError::ArchitectureMismatch {
+            path: path(),
+            expected: "x86_64",
+            found: "aarch64",
+        },
+    ];
+
+    for error in &errors {
+        assert!(
+            error.to_string().contains("/plugins/libplugin.so"),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn errors_name_the_problem_and_fix() {
+    let symbol = ReloadError::SymbolNotFound {
+        symbol: "count_sheep",
+        path: path(),
+    }
+    .to_string();
+    assert!(symbol.contains("function `count_sheep` not found"));
+    assert!(symbol.contains("#[no_mangle]"));
+
+    let build = ReloadError::BuildFailed {
+        path: path(),
+        error: std::io::Error::other("cargo build failed"),
+        stderr: "error: expected `;`".to_owned(),
+    }
+    .to_string();
+    assert!(build.contains("failed to build subcrate"));
+    assert!(build.ends_with("error: expected `;`"));
+
+    let arity = ReloadError::ArityMismatch {
+        symbol: "count_sheep",
+        path: path(),
+        expected: 1,
+        found: 2,
+    }
+    .to_string();
+    assert!(arity.contains("declared with 1 arguments, but takes 2"));
+
+    let linkage = ReloadError::AbiMismatch {
+        function: "count_sheep",
+        path: path(),
+        expected: "C",
+    }
+    .to_string();
+    assert!(linkage.contains("function `count_sheep`"));
+    assert!(linkage.contains("isn't `extern \"C\"`"));
+
+    let abi = ReloadError::AbiVersionMismatch {
+        path: path(),
+        expected: dymod::ABI_MAGIC,
+        found: 0,
+    }
+    .to_string();
+    assert!(abi.contains("different version of dymod"));
+
+    let arch = ReloadError::ArchitectureMismatch {
+        path: path(),
+        expected: "x86_64",
+        found: "aarch64",
+    }
+    .to_string();
+    assert!(arch.contains("built for aarch64, but this process is x86_64"));
+}
+
+#[cfg(not(any(feature = "force-static", feature = "force-stub")))]
+#[test]
+fn status_reports_the_error_message() {
+    static MISSING: DynamicModule = DynamicModule::new(
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/target/dymod missing dylib tests"
+        ),
+        "plugin",
+        &["stored_value"],
+    );
+
+    let error = MISSING.force_reload().unwrap_err();
+    let last_error = MISSING.status().last_error.unwrap();
+    assert_eq!(last_error, error.to_string());
+    assert!(last_error.contains("failed to load dylib at"));
+    assert!(last_error.contains("cargo build"));
+}
@@ -0,0 +1,53 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::{dymod, DymodEvent};
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+        fn missing_function();
+    }
+}
+
+// With the `strict` feature, its failed reload aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn reloads_are_sent_as_events() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    plugin::set_event_sender(sender);
+
+    plugin::stored_value();
+    plugin::force_reload().expect("Failed to reload.");
+    assert_eq!(
+        receiver.try_iter().collect::<Vec<_>>(),
+        [
+            DymodEvent::ReloadStarted,
+            DymodEvent::ReloadSucceeded { version: 1 },
+            DymodEvent::ReloadStarted,
+            DymodEvent::ReloadSucceeded { version: 2 },
+        ]
+    );
+
+    // Only a copy can be loaded alongside the old library
+    plugin::set_copy_strategy(dymod::CopyStrategy::Versioned);
+    plugin::set_symbol_resolution(dymod::SymbolResolution::Eager);
+    assert!(plugin::force_reload().is_err());
+    plugin::set_symbol_resolution(dymod::SymbolResolution::Lazy);
+    let events: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(events[0], DymodEvent::ReloadStarted);
+    match &events[1..] {
+        [DymodEvent::ReloadFailed { error }] => assert!(error.contains("missing_function")),
+        other => panic!("Expected ReloadFailed, got {:?}", other),
+    }
+
+    drop(receiver);
+    plugin::force_reload().expect("Failed to reload without a receiver.");
+}
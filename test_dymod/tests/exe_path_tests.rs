@@ -0,0 +1,26 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dynamic_in_release]
+    #[path_relative_to = "exe"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn dylib_loads_from_next_to_executable() {
+    let exe = std::env::current_exe().expect("Failed to find executable.");
+    let dylib = plugin::dylib_path();
+    assert_eq!(dylib.parent(), exe.parent());
+
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    std::fs::copy(&built_dylib, dylib).expect("Failed to copy dylib.");
+
+    plugin::store_value(7);
+    assert_eq!(plugin::stored_value(), 7);
+}
@@ -0,0 +1,69 @@
+//! Tests which linking mode each combination of features selects.
+//!
+//! The `force-static` feature of this crate also enables `auto-reload`,
+//! which must have no effect.
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+dymod! {
+    #[path = "../subcrate/src/lib.rs"]
+    #[dynamic_in_release]
+    #[allow(improper_ctypes_definitions)]
+    pub mod subcrate {
+        fn count_sheep(sheep: u32) -> &'static str;
+    }
+}
+
+#[cfg(feature = "force-stub")]
+#[test]
+fn force_stub_stubs_every_module() {
+    assert!(!plugin::status().loaded);
+    assert!(!plugin::status().auto_reload);
+    assert!(!subcrate::status().auto_reload);
+}
+
+#[cfg(all(feature = "force-static", not(feature = "force-stub")))]
+#[test]
+fn force_static_links_every_module_statically() {
+    // Only statically linked functions are the real `extern "C"` ones
+    let _: extern "C" fn() -> u32 = plugin::stored_value;
+    let _: extern "C" fn(u32) -> &'static str = subcrate::count_sheep;
+}
+
+#[cfg(all(
+    not(feature = "force-stub"),
+    not(feature = "force-static"),
+    any(feature = "force-dynamic", feature = "auto-reload", debug_assertions)
+))]
+#[test]
+fn dynamic_features_link_every_module_dynamically() {
+    let _: fn() -> u32 = plugin::stored_value;
+
+    plugin::force_reload().expect("Failed to load dylib.");
+    subcrate::force_reload().expect("Failed to load dylib.");
+    assert!(plugin::status().auto_reload);
+    assert!(subcrate::status().auto_reload);
+}
+
+#[cfg(all(
+    not(feature = "force-stub"),
+    not(feature = "force-static"),
+    not(feature = "force-dynamic"),
+    not(feature = "auto-reload"),
+    not(debug_assertions)
+))]
+#[test]
+fn release_links_only_marked_modules_dynamically() {
+    let _: extern "C" fn() -> u32 = plugin::stored_value;
+    let _: fn(u32) -> &'static str = subcrate::count_sheep;
+
+    subcrate::force_reload().expect("Failed to load dylib.");
+    assert!(subcrate::status().auto_reload);
+}
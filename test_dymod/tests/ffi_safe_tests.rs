@@ -0,0 +1,80 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[ffi_safe]
+    #[checked]
+    pub mod plugin {
+        fn sum(values: &[f32]) -> f32;
+        fn double_all(values: &mut [u32]);
+        fn render_to(buffer: &mut [u8], frame: u8) -> usize;
+        fn copy_values(to: &mut [u32], from: &[u32]) -> usize;
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+        fn find_value(key: u32) -> Option<std::ptr::NonNull<u32>>;
+        fn for_each_odd_up_to(limit: u32, callback: impl FnMut(u32));
+        fn count_matching(limit: u32, predicate: impl FnMut(u32) -> bool) -> u32;
+    }
+}
+
+#[test]
+fn slice_is_passed_as_pointer_and_length() {
+    assert_eq!(plugin::sum(&[1.0, 2.0, 3.5]), 6.5);
+    assert_eq!(plugin::sum(&[]), 0.0);
+}
+
+#[test]
+fn mutable_slice_is_passed_as_pointer_and_length() {
+    let mut values = [1, 2, 3];
+    plugin::double_all(&mut values);
+    assert_eq!(values, [2, 4, 6]);
+}
+
+#[test]
+fn dylib_renders_into_host_buffer() {
+    let mut buffer = [b'.'; 10];
+    assert_eq!(plugin::render_to(&mut buffer, 3), 7);
+    assert_eq!(&buffer, b"frame 3...");
+
+    let mut short = [0; 4];
+    assert_eq!(plugin::render_to(&mut short, 3), 4);
+    assert_eq!(&short, b"fram");
+    assert_eq!(plugin::render_to(&mut [], 3), 0);
+}
+
+#[test]
+fn each_slice_keeps_its_own_length() {
+    let mut to = [0; 2];
+    assert_eq!(plugin::copy_values(&mut to, &[4, 5, 6]), 2);
+    assert_eq!(to, [4, 5]);
+}
+
+#[test]
+fn other_arguments_are_passed_as_is() {
+    plugin::store_value(7);
+    assert_eq!(plugin::stored_value(), 7);
+}
+
+#[test]
+fn null_pointer_is_returned_as_none() {
+    assert!(plugin::find_value(0).is_some());
+    assert!(plugin::find_value(1).is_none());
+}
+
+#[test]
+fn capturing_closure_is_passed_with_trampoline() {
+    let mut odds = Vec::new();
+    plugin::for_each_odd_up_to(7, |n| odds.push(n));
+    assert_eq!(odds, [1, 3, 5, 7]);
+
+    let divisor = 3;
+    let mut calls = 0;
+    let count = plugin::count_matching(10, |n| {
+        calls += 1;
+        n % divisor == 0
+    });
+    assert_eq!(count, 4);
+    assert_eq!(calls, 10);
+}
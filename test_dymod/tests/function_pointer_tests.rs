@@ -0,0 +1,38 @@
+#![cfg(not(feature = "force-stub"))]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn for_each_up_to(limit: u32, callback: extern "C" fn(u32));
+        fn apply_twice(f: fn(u32) -> u32, value: u32) -> u32;
+        fn find_value(key: u32) -> *mut u32;
+    }
+}
+
+#[test]
+fn extern_c_callback_is_called_by_dynamic_code() {
+    static TOTAL: AtomicU32 = AtomicU32::new(0);
+
+    extern "C" fn add_to_total(value: u32) {
+        TOTAL.fetch_add(value, Ordering::SeqCst);
+    }
+
+    plugin::for_each_up_to(5, add_to_total);
+
+    assert_eq!(TOTAL.load(Ordering::SeqCst), 1 + 2 + 3 + 4);
+}
+
+#[test]
+fn non_capturing_closure_is_passed_as_fn_pointer() {
+    assert_eq!(plugin::apply_twice(|x| x * 3, 2), 18);
+}
+
+#[test]
+fn raw_pointer_is_returned_as_is() {
+    assert!(!plugin::find_value(0).is_null());
+    assert!(plugin::find_value(1).is_null());
+}
@@ -0,0 +1,63 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn only_the_loaded_generation_is_live() {
+    assert_eq!(plugin::current_generation(), 0);
+    assert!(!plugin::is_generation_live(0));
+
+    plugin::stored_value();
+    let first = plugin::current_generation();
+    assert_eq!(first, 1);
+    assert!(plugin::is_generation_live(first));
+
+    plugin::force_reload().expect("Failed to reload.");
+    let second = plugin::current_generation();
+    assert_ne!(second, first);
+    assert!(plugin::is_generation_live(second));
+    assert!(!plugin::is_generation_live(first));
+}
+
+mod retained {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn version_string() -> *const std::os::raw::c_char;
+        }
+    }
+}
+
+#[test]
+fn retained_generation_stays_live_after_reload() {
+    use retained::plugin;
+
+    // Only a copy can stay loaded alongside the new library
+    plugin::set_copy_strategy(dymod::CopyStrategy::Versioned);
+    plugin::set_max_retained_libraries(1);
+
+    let version = plugin::version_string();
+    let first = plugin::current_generation();
+    plugin::force_reload().expect("Failed to reload.");
+    assert!(plugin::is_generation_live(first));
+    let borrowed = unsafe { std::ffi::CStr::from_ptr(version) };
+    assert_eq!(borrowed.to_str().unwrap(), "plugin 0.1.0");
+
+    plugin::force_reload().expect("Failed to reload.");
+    assert!(!plugin::is_generation_live(first));
+    assert!(plugin::is_generation_live(first + 1));
+}
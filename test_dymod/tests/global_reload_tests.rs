@@ -0,0 +1,44 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+mod plugins {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+mod subcrates {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../../subcrate/src/lib.rs"]
+        pub mod subcrate {
+            fn count_sheep(sheep: u32) -> &'static str;
+        }
+    }
+}
+
+use plugins::plugin;
+use subcrates::subcrate;
+
+#[test]
+fn reload_all_reloads_modules_from_every_block() {
+    plugin::stored_value();
+    subcrate::count_sheep(1);
+
+    dymod::reload_all().expect("Failed to reload all modules.");
+
+    assert_eq!(plugin::status().version, 2);
+    assert_eq!(subcrate::status().version, 2);
+}
@@ -0,0 +1,44 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+
+    #[path = "../subcrate/src/lib.rs"]
+    pub mod subcrate {
+        fn count_sheep(sheep: u32) -> &'static str;
+    }
+}
+
+#[test]
+fn handle_calls_functions() {
+    let handle = subcrate::DynModule::take().expect("Handle already taken.");
+    let sheep: &str = handle.count_sheep(3);
+    assert_eq!(sheep, subcrate::count_sheep(3));
+
+    // There's only one handle
+    assert!(subcrate::DynModule::take().is_none());
+}
+
+#[cfg(any(
+    feature = "force-dynamic",
+    feature = "auto-reload",
+    all(not(feature = "force-static"), debug_assertions,)
+))]
+#[test]
+fn handle_reloads_through_mutable_borrow() {
+    let mut handle = plugin::DynModule::take().expect("Handle already taken.");
+    handle.store_value(4);
+    assert_eq!(handle.stored_value(), 4);
+
+    assert!(!handle.try_reload().expect("Failed to check dylib."));
+    assert_eq!(handle.stored_value(), 4);
+
+    handle.force_reload().expect("Failed to reload.");
+    assert_eq!(handle.stored_value(), 0);
+}
@@ -0,0 +1,44 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+static SAVED_VALUE: AtomicU32 = AtomicU32::new(0);
+
+#[test]
+fn hooks_carry_state_across_reload() {
+    plugin::on_before_reload(|| {
+        EVENTS.lock().unwrap().push("before");
+        SAVED_VALUE.store(plugin::stored_value(), Ordering::SeqCst);
+    });
+    plugin::on_reload(|| {
+        EVENTS.lock().unwrap().push("after");
+        plugin::store_value(SAVED_VALUE.load(Ordering::SeqCst));
+    });
+
+    plugin::store_value(42);
+    assert!(EVENTS.lock().unwrap().is_empty());
+
+    plugin::force_reload().unwrap();
+    assert_eq!(*EVENTS.lock().unwrap(), ["before", "after"]);
+    assert_eq!(plugin::stored_value(), 42);
+    assert_eq!(plugin::status().version, 2);
+}
@@ -0,0 +1,64 @@
+#![cfg(not(feature = "force-static"))]
+
+#[test]
+fn dylib_is_loaded_from_bytes() {
+    let path = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let bytes = std::fs::read(path).expect("Failed to read plugin dylib.");
+
+    let library = unsafe { dymod::host::load_from_bytes(&bytes) }.expect("Failed to load bytes.");
+    let add_system: dymod::Symbol<extern "system" fn(u32, u32) -> u32> =
+        unsafe { library.get(b"add_system") }.expect("Failed to get symbol.");
+
+    assert_eq!(add_system(2, 3), 5);
+}
+
+#[test]
+fn invalid_bytes_fail_to_load() {
+    match unsafe { dymod::host::load_from_bytes(b"not a dylib") } {
+        Err(dymod::ReloadError::LoadFailed { .. }) => {}
+        Err(other) => panic!("Expected LoadFailed, got {:?}", other),
+        Ok(_) => panic!("Expected LoadFailed, got a library"),
+    }
+}
+
+#[test]
+fn library_set_reports_a_missing_dylib_as_unreadable() {
+    let mut versions = dymod::host::LibrarySet::new();
+    match unsafe { versions.load("missing", "/nonexistent/libplugin.so") } {
+        Err(dymod::ReloadError::ReadFailed { .. }) => {}
+        other => panic!("Expected ReadFailed, got {:?}", other),
+    }
+}
+
+fn stored_value(versions: &dymod::host::LibrarySet, label: &str) -> Option<u32> {
+    unsafe { versions.call_in(label, "stored_value", |f: extern "C" fn() -> u32| f()) }
+}
+
+#[test]
+fn library_set_keeps_versions_side_by_side() {
+    let path = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let mut versions = dymod::host::LibrarySet::new();
+    unsafe {
+        versions.load("old", &path).expect("Failed to load old.");
+        versions.load("new", &path).expect("Failed to load new.");
+    }
+
+    let mut labels: Vec<_> = versions.labels().collect();
+    labels.sort();
+    assert_eq!(labels, ["new", "old"]);
+
+    // Each copy has its own statics
+    unsafe {
+        versions.call_in("old", "store_value", |f: extern "C" fn(u32)| f(1));
+        versions.call_in("new", "store_value", |f: extern "C" fn(u32)| f(2));
+        assert_eq!(stored_value(&versions, "old"), Some(1));
+        assert_eq!(stored_value(&versions, "new"), Some(2));
+
+        assert_eq!(
+            versions.call_in("old", "missing", |f: extern "C" fn()| f()),
+            None
+        );
+        assert!(versions.remove("old"));
+        assert_eq!(stored_value(&versions, "old"), None);
+    }
+}
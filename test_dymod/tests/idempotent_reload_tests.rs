@@ -0,0 +1,68 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+
+use dymod::{dymod, DynamicModule};
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+const MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod idempotent reload tests"
+);
+
+static COPIED: DynamicModule = DynamicModule::new(MANIFEST_DIR, "plugin", &["stored_value"]);
+
+#[test]
+fn unchanged_dylib_is_not_reloaded() {
+    assert!(plugin::try_reload().expect("Failed initial load."));
+    plugin::store_value(7);
+
+    assert!(!plugin::try_reload().expect("Failed to check dylib."));
+    plugin::reload();
+    assert_eq!(plugin::current_generation(), 1);
+    assert_eq!(plugin::stored_value(), 7);
+
+    plugin::force_reload().expect("Failed to reload.");
+    assert_eq!(plugin::current_generation(), 2);
+    assert_eq!(plugin::stored_value(), 0);
+}
+
+#[test]
+fn modified_dylib_is_reloaded() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dylib = dymod::dylib_path(MANIFEST_DIR, "plugin");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    assert!(COPIED.reload_if_modified().expect("Failed initial load."));
+    assert!(!COPIED.reload_if_modified().expect("Failed to check dylib."));
+
+    // Changed contents too, in case of the `content-hash` feature
+    std::fs::File::options()
+        .append(true)
+        .open(&dylib)
+        .and_then(|mut file| {
+            file.write_all(b"changed")?;
+            file.set_modified(SystemTime::now() + Duration::from_secs(10))
+        })
+        .expect("Failed to change dylib.");
+    assert!(COPIED
+        .reload_if_modified()
+        .expect("Failed to reload dylib."));
+    assert_eq!(COPIED.status().version, 2);
+}
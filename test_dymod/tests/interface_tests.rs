@@ -0,0 +1,17 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[interface = "../plugin/interface.rs"]
+    pub mod plugin {}
+}
+
+#[test]
+fn functions_are_declared_by_interface_file() {
+    plugin::store_value(8);
+    assert_eq!(plugin::stored_value(), 8);
+    assert!(plugin::is_even(8));
+    assert!(!plugin::is_even(7));
+}
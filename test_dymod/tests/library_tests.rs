@@ -0,0 +1,32 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+    }
+}
+
+#[test]
+fn undeclared_symbol_is_callable_through_library() {
+    plugin::store_value(12);
+
+    let value = unsafe {
+        plugin::with_library(|lib| {
+            let stored_value: dymod::Symbol<extern "C" fn() -> u32> =
+                lib.get(b"stored_value").expect("Failed to get symbol.");
+            stored_value()
+        })
+    };
+
+    assert_eq!(value, 12);
+}
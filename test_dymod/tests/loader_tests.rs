@@ -0,0 +1,51 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dymod::{DefaultLoader, DymodLoader, DynamicModule, Library, ReloadError};
+
+static PLUGIN: DynamicModule =
+    DynamicModule::new(env!("CARGO_MANIFEST_DIR"), "plugin", &["stored_value"]);
+
+static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingLoader;
+
+impl DymodLoader for CountingLoader {
+    fn load(&self, path: &Path) -> Result<Library, ReloadError> {
+        LOADS.fetch_add(1, Ordering::SeqCst);
+        DefaultLoader.load(path)
+    }
+}
+
+struct FailingLoader;
+
+impl DymodLoader for FailingLoader {
+    fn load(&self, path: &Path) -> Result<Library, ReloadError> {
+        Err(ReloadError::LoadFailed {
+            path: path.to_owned(),
+            error: std::io::Error::new(std::io::ErrorKind::Unsupported, "not on this platform"),
+        })
+    }
+}
+
+#[test]
+fn custom_loader_opens_dylib() {
+    PLUGIN.set_loader(FailingLoader);
+    match PLUGIN.force_reload() {
+        Err(ReloadError::LoadFailed { error, .. }) => {
+            assert_eq!(error.kind(), std::io::ErrorKind::Unsupported)
+        }
+        other => panic!("Expected LoadFailed, got {:?}", other),
+    }
+    assert!(!PLUGIN.status().loaded);
+
+    PLUGIN.set_loader(CountingLoader);
+    PLUGIN.force_reload().expect("Failed to load dylib.");
+    PLUGIN.force_reload().expect("Failed to reload dylib.");
+    PLUGIN.check().expect("Failed to check dylib.");
+
+    assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+    assert_eq!(PLUGIN.status().version, 2);
+}
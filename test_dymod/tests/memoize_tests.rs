@@ -0,0 +1,43 @@
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        #[memoize]
+        fn counted_square(value: u32) -> u32;
+        fn call_count() -> u32;
+    }
+}
+
+#[cfg(not(feature = "force-stub"))]
+#[test]
+fn memoized_function_returns_result() {
+    assert_eq!(plugin::counted_square(3), 9);
+    assert_eq!(plugin::counted_square(3), 9);
+    assert_eq!(plugin::counted_square(4), 16);
+
+    // Function calls are only counted by the dylib they were made to
+    #[cfg(any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    ))]
+    {
+        plugin::force_reload().expect("Failed to reload.");
+        assert_eq!(plugin::call_count(), 0);
+
+        assert_eq!(plugin::counted_square(5), 25);
+        assert_eq!(plugin::counted_square(5), 25);
+        assert_eq!(plugin::call_count(), 1);
+
+        // Different arguments are cached separately
+        assert_eq!(plugin::counted_square(6), 36);
+        assert_eq!(plugin::counted_square(5), 25);
+        assert_eq!(plugin::call_count(), 2);
+
+        // The new dylib is called again after a reload
+        plugin::force_reload().expect("Failed to reload.");
+        assert_eq!(plugin::counted_square(5), 25);
+        assert_eq!(plugin::call_count(), 1);
+    }
+}
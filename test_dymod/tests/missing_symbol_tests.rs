@@ -0,0 +1,70 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+
+        #[missing_symbol = "default"]
+        fn stored_value() -> u32;
+
+        #[missing_symbol = "default"]
+        fn not_written_yet(value: u32) -> u32;
+
+        #[missing_symbol = "default"]
+        fn not_written_either();
+
+        #[missing_symbol = "panic"]
+        fn not_written_at_all() -> u32;
+    }
+}
+
+mod checked {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[checked]
+        pub mod plugin {
+            #[missing_symbol = "default"]
+            fn is_even(n: u32) -> bool;
+
+            #[missing_symbol = "default"]
+            fn is_odd(n: u32) -> bool;
+        }
+    }
+}
+
+#[test]
+fn missing_function_returns_default() {
+    plugin::store_value(3);
+    assert_eq!(plugin::stored_value(), 3);
+    assert_eq!(plugin::no_reload::stored_value(), 3);
+
+    assert_eq!(plugin::not_written_yet(5), 0);
+    assert_eq!(plugin::no_reload::not_written_yet(5), 0);
+    plugin::not_written_either();
+}
+
+#[test]
+fn missing_function_in_checked_module_returns_default() {
+    assert!(checked::plugin::is_even(4));
+    assert!(!checked::plugin::is_odd(3));
+}
+
+// With the `strict` feature, this aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn missing_function_panics_by_default() {
+    let result = std::panic::catch_unwind(plugin::not_written_at_all);
+    assert!(result.is_err());
+}
@@ -0,0 +1,35 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../subcrate/src/lib.rs"]
+    #[depends_on(plugin)]
+    pub mod subcrate {
+        fn count_sheep(sheep: u32) -> &'static str;
+    }
+
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn all_modules_in_a_block_are_usable_after_reload_all() {
+    reload_all().expect("Failed to reload modules.");
+
+    assert!(!subcrate::count_sheep(3).is_empty());
+    assert_eq!(plugin::stored_value(), 0);
+}
+
+#[test]
+fn modules_are_reloaded_after_their_dependencies() {
+    const ORDER: [usize; 3] = dymod::reload_order([
+        ("gameplay", &["core", "audio"]),
+        ("audio", &["core"]),
+        ("core", &[]),
+    ]);
+
+    assert_eq!(ORDER, [2, 1, 0]);
+}
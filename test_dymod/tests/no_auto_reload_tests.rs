@@ -0,0 +1,38 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod no auto reload tests/{prefix}{name}{suffix}"]
+    #[no_auto_reload]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn module_only_reloads_explicitly() {
+    common::copy_dylib(plugin::dylib_path());
+    plugin::stored_value();
+    assert!(!plugin::status().auto_reload);
+
+    common::change_dylib(plugin::dylib_path(), 1);
+
+    dymod::trigger_reload();
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+    assert!(plugin::pending_reload());
+
+    assert!(plugin::reload_if_changed().expect("Failed to reload."));
+    assert_eq!(plugin::status().version, 2);
+}
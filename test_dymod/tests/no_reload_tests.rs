@@ -0,0 +1,37 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod no reload tests/{prefix}{name}{suffix}"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn no_reload_functions_skip_change_check_until_reload_if_changed() {
+    common::copy_dylib(plugin::dylib_path());
+    plugin::no_reload::stored_value();
+    assert_eq!(plugin::status().version, 1);
+    assert!(!plugin::reload_if_changed().expect("Failed to check for changes."));
+
+    common::change_dylib(plugin::dylib_path(), 1);
+
+    plugin::no_reload::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    assert!(plugin::reload_if_changed().expect("Failed to reload."));
+    assert_eq!(plugin::status().version, 2);
+    assert!(!plugin::reload_if_changed().expect("Failed to check for changes."));
+}
@@ -0,0 +1,18 @@
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn static_modules_build_without_std() {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = Command::new(cargo)
+        .arg("build")
+        .current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("no_std"))
+        .output()
+        .expect("Failed to run cargo.");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,36 @@
+#![cfg(all(
+    target_os = "linux",
+    not(any(feature = "force-static", feature = "force-stub"))
+))]
+
+use std::ffi::{c_char, c_void};
+
+use dymod::{DynamicModule, ReloadError};
+
+const RTLD_NOW: i32 = 2;
+const RTLD_GLOBAL: i32 = 0x100;
+
+extern "C" {
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+}
+
+static PLUGIN: DynamicModule =
+    DynamicModule::new(env!("CARGO_MANIFEST_DIR"), "plugin", &["stored_value"]);
+
+#[test]
+fn open_flags_are_passed_to_dlopen() {
+    // Neither RTLD_NOW nor RTLD_LAZY is an invalid mode
+    PLUGIN.set_open_flags(0);
+    match PLUGIN.force_reload() {
+        Err(ReloadError::LoadFailed { .. }) => {}
+        other => panic!("Expected LoadFailed, got {:?}", other),
+    }
+
+    PLUGIN.set_open_flags(RTLD_NOW | RTLD_GLOBAL);
+    PLUGIN.force_reload().expect("Failed to load dylib.");
+    PLUGIN.check().expect("Failed to check dylib.");
+
+    // A null handle is RTLD_DEFAULT, which only searches global symbols
+    let symbol = unsafe { dlsym(std::ptr::null_mut(), c"find_value".as_ptr()) };
+    assert!(!symbol.is_null());
+}
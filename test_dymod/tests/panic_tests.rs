@@ -0,0 +1,39 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::Mutex;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn checked_divide(a: u32, b: u32) -> u32;
+    }
+}
+
+static PANICS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record_panic(message: &str) {
+    PANICS.lock().unwrap().push(message.to_owned());
+}
+
+#[test]
+fn dylib_panics_are_forwarded_to_handler() {
+    dymod::set_panic_handler(record_panic);
+
+    assert_eq!(plugin::checked_divide(12, 4), 3);
+    assert!(PANICS.lock().unwrap().is_empty());
+
+    assert_eq!(plugin::checked_divide(12, 0), 0);
+    let panics = PANICS.lock().unwrap();
+    assert_eq!(panics.len(), 1);
+    assert!(panics[0].starts_with("panicked at"), "{}", panics[0]);
+    assert!(panics[0].contains("divide by zero"), "{}", panics[0]);
+}
@@ -0,0 +1,175 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dynamic_in_release]
+    #[debug_path]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn resolved_dylib_path_matches_runtime_path() {
+    assert_eq!(
+        std::path::Path::new(plugin::RESOLVED_DYLIB_PATH),
+        plugin::dylib_path()
+    );
+    assert!(plugin::dylib_path().exists());
+}
+
+/// An awkwardly-named directory to lay out a copy of the subcrate under.
+const AWKWARD_MANIFEST_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target/dymod path tests/My Nàme"
+);
+
+static AWKWARD: dymod::DynamicModule =
+    dymod::DynamicModule::new(AWKWARD_MANIFEST_DIR, "subcrate", &[]);
+
+#[test]
+fn dylib_loads_from_directory_with_spaces_and_non_ascii_characters() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "subcrate");
+    let manifest_dir = AWKWARD_MANIFEST_DIR;
+    let dylib = dymod::dylib_path(manifest_dir, "subcrate");
+    std::fs::create_dir_all(dylib.parent().unwrap()).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    let versioned = AWKWARD.copy_path(0);
+    assert!(versioned.starts_with(manifest_dir));
+    std::fs::copy(&dylib, &versioned).expect("Failed to copy versioned dylib.");
+
+    unsafe {
+        let lib = dymod::Library::new(versioned.as_os_str()).expect("Failed to load dylib.");
+        let count_sheep: dymod::Symbol<extern "C" fn(u32) -> &'static str> =
+            lib.get(b"count_sheep").expect("Failed to get symbol.");
+        assert!(!count_sheep(3).is_empty());
+    }
+}
+
+static RESOLVED: dymod::DynamicModule =
+    dymod::DynamicModule::new("/nonexistent", "plugin", &["stored_value"]);
+
+#[test]
+fn path_resolver_replaces_built_in_path() {
+    let built_dylib = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/target/dymod resolver tests");
+    let dylib = std::path::Path::new(dir).join(built_dylib.file_name().unwrap());
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(&built_dylib, &dylib).expect("Failed to copy dylib.");
+
+    assert!(RESOLVED.force_reload().is_err());
+
+    let resolved = dylib.clone();
+    RESOLVED.set_path_resolver(move || resolved.clone());
+    assert_eq!(RESOLVED.dylib_path(), dylib);
+    RESOLVED
+        .force_reload()
+        .expect("Failed to load resolved dylib.");
+    assert!(RESOLVED.status().loaded);
+}
+
+mod templated {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[dynamic_in_release]
+        #[dylib = "{manifest}/{name}/target/debug/{prefix}{name}{suffix}"]
+        pub mod plugin {
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+#[test]
+fn dylib_template_fills_in_placeholders() {
+    assert_eq!(
+        templated::plugin::dylib_path(),
+        dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin")
+    );
+    templated::plugin::force_reload().expect("Failed to load templated dylib.");
+    assert_eq!(templated::plugin::stored_value(), 0);
+
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let target_dir = match std::env::var("CARGO_TARGET_DIR") {
+        Ok(dir) => std::path::PathBuf::from(dir),
+        Err(_) => std::path::Path::new("/host").join("plugin").join("target"),
+    };
+    assert_eq!(
+        dymod::template_dylib_path("{target_dir}/{profile}/{name}.so", "/host", "plugin"),
+        target_dir.join(profile).join("plugin.so")
+    );
+}
+
+mod targeted {
+    use dymod::dymod;
+
+    dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[dynamic_in_release]
+        #[debug_path]
+        #[target = "x86_64-unknown-linux-gnu"]
+        pub mod plugin {
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+#[test]
+fn target_triple_is_part_of_dylib_path() {
+    let expected = dymod::target_dylib_path(
+        env!("CARGO_MANIFEST_DIR"),
+        "plugin",
+        "x86_64-unknown-linux-gnu",
+    );
+    assert_eq!(targeted::plugin::dylib_path(), expected);
+    assert_eq!(
+        std::path::Path::new(targeted::plugin::RESOLVED_DYLIB_PATH),
+        expected
+    );
+    assert!(expected
+        .parent()
+        .unwrap()
+        .ends_with("plugin/target/x86_64-unknown-linux-gnu/debug"));
+}
+
+mod once {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        #[dynamic_in_release]
+        pub mod plugin {
+            fn store_value(value: u32);
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+#[test]
+fn reload_from_loads_another_dylib_once() {
+    use once::plugin;
+
+    let built_dylib = plugin::dylib_path();
+    let dir = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/target/dymod reload from tests"
+    );
+    let other = std::path::Path::new(dir).join(built_dylib.file_name().unwrap());
+    std::fs::create_dir_all(dir).expect("Failed to create test dir.");
+    std::fs::copy(built_dylib, &other).expect("Failed to copy dylib.");
+
+    plugin::store_value(4);
+    plugin::reload_from(&other).expect("Failed to load other dylib.");
+    assert_eq!(plugin::dylib_path(), built_dylib);
+    assert_eq!(plugin::loaded_dylib_info().unwrap().path, other);
+    assert_eq!(plugin::stored_value(), 0);
+
+    plugin::force_reload().expect("Failed to reload.");
+    assert_eq!(plugin::loaded_dylib_info().unwrap().path, built_dylib);
+}
@@ -0,0 +1,36 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod pending reload tests/{prefix}{name}{suffix}"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn pending_reload_reports_changes_without_reloading() {
+    common::copy_dylib(plugin::dylib_path());
+    plugin::stored_value();
+    assert!(!plugin::pending_reload());
+
+    common::change_dylib(plugin::dylib_path(), 1);
+
+    assert!(plugin::pending_reload());
+    assert!(plugin::pending_reload());
+    assert_eq!(plugin::status().version, 1);
+
+    plugin::reload();
+    assert!(!plugin::pending_reload());
+}
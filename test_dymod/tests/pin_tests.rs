@@ -0,0 +1,42 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod pin tests/{prefix}{name}{suffix}"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn pinned_module_is_only_reloaded_explicitly() {
+    common::copy_dylib(plugin::dylib_path());
+    plugin::stored_value();
+    plugin::pin();
+
+    common::change_dylib(plugin::dylib_path(), 1);
+    dymod::trigger_reload();
+    assert!(!plugin::pending_reload());
+    plugin::stored_value();
+    assert!(!plugin::reload_if_changed().expect("Failed to check dylib."));
+    assert_eq!(plugin::status().version, 1);
+
+    plugin::reload();
+    assert_eq!(plugin::status().version, 2);
+
+    plugin::unpin();
+    common::change_dylib(plugin::dylib_path(), 2);
+    assert!(plugin::reload_if_changed().expect("Failed to reload dylib."));
+    assert_eq!(plugin::status().version, 3);
+}
@@ -0,0 +1,43 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::Mutex;
+
+use dymod::{dymod, ReloadError};
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn for_each_up_to(limit: u32, callback: extern "C" fn(u32));
+    }
+}
+
+static CALLBACK_RESULT: Mutex<Option<Result<(), ReloadError>>> = Mutex::new(None);
+
+extern "C" fn reload_from_callback(_: u32) {
+    *CALLBACK_RESULT.lock().unwrap() = Some(plugin::force_reload());
+}
+
+#[test]
+fn reload_from_inside_dylib_call_is_refused() {
+    plugin::force_reload().expect("Failed initial load.");
+
+    plugin::for_each_up_to(1, reload_from_callback);
+    match CALLBACK_RESULT.lock().unwrap().take() {
+        Some(Err(ReloadError::ReentrantReload { path })) => {
+            assert_eq!(path, plugin::dylib_path())
+        }
+        other => panic!("Expected ReentrantReload, got {:?}", other),
+    }
+    assert_eq!(plugin::status().version, 1);
+
+    // Once the call has returned, reloading works again
+    plugin::force_reload().expect("Failed to reload.");
+    assert_eq!(plugin::status().version, 2);
+}
@@ -0,0 +1,34 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn guard_reloads_at_end_of_block() {
+    plugin::force_reload().expect("Failed initial load.");
+    let version = plugin::status().version;
+
+    {
+        let _reload = plugin::reload_on_drop();
+        plugin::store_value(5);
+        assert_eq!(plugin::stored_value(), 5);
+        assert_eq!(plugin::status().version, version);
+    }
+
+    assert_eq!(plugin::status().version, version + 1);
+    assert_eq!(plugin::stored_value(), 0);
+}
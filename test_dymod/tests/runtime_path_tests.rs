@@ -0,0 +1,37 @@
+#![cfg(not(any(feature = "force-static", feature = "force-stub")))]
+
+use dymod::dymod;
+
+dymod! {
+    #[runtime_path]
+    pub mod chosen {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+
+    #[runtime_path]
+    pub mod unchosen {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn runtime_path_module_loads_from_given_path() {
+    match chosen::try_reload() {
+        Err(dymod::ReloadError::NoDylibPath { module }) => assert_eq!(module, "chosen"),
+        other => panic!("Expected NoDylibPath, got {:?}", other),
+    }
+
+    let path = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    chosen::load_from(&path).expect("Failed to load dylib.");
+    assert_eq!(chosen::dylib_path(), path);
+
+    chosen::store_value(9);
+    assert_eq!(chosen::stored_value(), 9);
+}
+
+#[test]
+#[should_panic(expected = "load_from()")]
+fn runtime_path_module_panics_before_load_from() {
+    unchosen::stored_value();
+}
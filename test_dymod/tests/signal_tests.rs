@@ -0,0 +1,48 @@
+#![cfg(all(
+    unix,
+    feature = "signal",
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::time::{Duration, Instant};
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn sigusr1_reloads_on_next_call() {
+    dymod::reload_on_signal();
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    let status = std::process::Command::new("kill")
+        .arg("-USR1")
+        .arg(std::process::id().to_string())
+        .status()
+        .expect("Failed to run kill.");
+    assert!(status.success());
+
+    // The signal is delivered asynchronously, so keep calling until it
+    // has been
+    let start = Instant::now();
+    while plugin::status().version == 1 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Module wasn't reloaded after SIGUSR1"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        plugin::stored_value();
+    }
+    assert_eq!(plugin::status().version, 2);
+}
@@ -0,0 +1,58 @@
+#![cfg(not(feature = "force-stub"))]
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr::NonNull;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+        fn transpose(matrix: [f32; 16]) -> [f32; 16];
+        fn find_value(key: u32) -> Option<NonNull<u32>>;
+        fn version_string() -> *const std::os::raw::c_char;
+        fn set_name(name: *const c_char);
+        fn name_len() -> usize;
+    }
+}
+
+#[test]
+fn function_without_return_type_is_callable() {
+    plugin::store_value(7);
+
+    assert_eq!(plugin::stored_value(), 7);
+    assert_eq!(plugin::no_reload::stored_value(), 7);
+}
+
+#[test]
+fn large_array_is_passed_and_returned() {
+    let matrix: [f32; 16] = std::array::from_fn(|i| i as f32);
+    let transposed = plugin::transpose(matrix);
+
+    for row in 0..4 {
+        for column in 0..4 {
+            assert_eq!(transposed[column * 4 + row], matrix[row * 4 + column]);
+        }
+    }
+    assert_eq!(plugin::transpose(transposed), matrix);
+}
+
+#[test]
+fn null_pointer_is_returned_as_none() {
+    let found = plugin::find_value(0).expect("Expected a pointer.");
+
+    assert_eq!(unsafe { *found.as_ptr() }, 42);
+    assert!(plugin::find_value(1).is_none());
+}
+
+#[test]
+fn c_strings_are_passed_and_returned() {
+    let version = unsafe { CStr::from_ptr(plugin::version_string()) };
+    assert_eq!(version.to_str(), Ok("plugin 0.1.0"));
+
+    plugin::set_name(c"Dolly".as_ptr());
+    assert_eq!(plugin::name_len(), 5);
+}
@@ -0,0 +1,32 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn state_survives_reload_preserving_state() {
+    plugin::reload_preserving_state().expect("Failed initial load.");
+    plugin::store_value(42);
+
+    plugin::reload_preserving_state().expect("Failed to reload.");
+    assert_eq!(plugin::status().version, 2);
+    assert_eq!(plugin::stored_value(), 42);
+
+    // A plain reload still loses it
+    plugin::force_reload().expect("Failed to reload.");
+    assert_eq!(plugin::stored_value(), 0);
+}
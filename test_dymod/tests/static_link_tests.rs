@@ -1,9 +1,12 @@
-#![cfg(any(
-    feature = "force-static",
-    all(
-        not(feature = "force-dynamic"),
-        not(feature = "auto-reload"),
-        not(debug_assertions)
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-static",
+        all(
+            not(feature = "force-dynamic"),
+            not(feature = "auto-reload"),
+            not(debug_assertions)
+        )
     )
 ))]
 
@@ -67,3 +70,15 @@ fn subcrate_is_statically_linked_and_not_hotswapped() {
         assert_eq!(subcrate::count_sheep(4), "Lots");
     }
 }
+
+#[test]
+fn declared_functions_are_the_real_functions() {
+    // A wrapper function would have the Rust ABI instead
+    let count_sheep: extern "C" fn(u32) -> &'static str = subcrate::count_sheep;
+    assert_eq!(count_sheep(0), "None");
+}
+
+#[test]
+fn declared_functions_are_listed() {
+    assert_eq!(subcrate::FUNCTIONS, ["count_sheep"]);
+}
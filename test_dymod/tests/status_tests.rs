@@ -0,0 +1,75 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+        fn missing_function();
+    }
+}
+
+// With the `strict` feature, its failed reload aborts instead
+#[cfg(not(feature = "strict"))]
+#[test]
+fn status_tracks_reloads_and_last_error() {
+    let status = plugin::status();
+    assert!(!status.loaded);
+    assert_eq!(status.version, 0);
+    assert_eq!(status.last_reload, None);
+
+    plugin::stored_value();
+    let status = plugin::status();
+    assert!(status.loaded);
+    assert_eq!(status.version, 1);
+    assert!(status.last_reload.is_some());
+    assert_eq!(status.last_error, None);
+
+    // Only a copy can be loaded alongside the old library
+    plugin::set_copy_strategy(dymod::CopyStrategy::Versioned);
+    plugin::set_eager_symbol_resolution(true);
+    assert!(plugin::force_reload().is_err());
+    let failed = plugin::status();
+    assert!(failed.loaded);
+    assert_eq!(failed.version, 1);
+    assert_eq!(failed.last_reload, status.last_reload);
+    assert!(failed.last_error.unwrap().contains("missing_function"));
+
+    plugin::set_eager_symbol_resolution(false);
+    plugin::force_reload().expect("Failed to reload.");
+    let status = plugin::status();
+    assert_eq!(status.version, 2);
+    assert_eq!(status.last_error, None);
+}
+
+mod info {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn stored_value() -> u32;
+        }
+    }
+}
+
+#[test]
+fn loaded_dylib_info_describes_the_dylib() {
+    use info::plugin;
+
+    assert_eq!(plugin::loaded_dylib_info(), None);
+
+    plugin::stored_value();
+    let info = plugin::loaded_dylib_info().expect("Dylib should be loaded.");
+    let metadata = std::fs::metadata(plugin::dylib_path()).unwrap();
+    assert_eq!(info.path, plugin::dylib_path());
+    assert_eq!(info.size, metadata.len());
+    assert_eq!(info.modified, metadata.modified().unwrap());
+    assert_eq!(info.generation, plugin::current_generation());
+}
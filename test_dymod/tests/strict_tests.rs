@@ -0,0 +1,66 @@
+#![cfg(all(
+    feature = "strict",
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::process::Command;
+
+use dymod::{dymod, DynamicModule};
+
+static MISDECLARED: DynamicModule =
+    DynamicModule::new(env!("CARGO_MANIFEST_DIR"), "plugin", &["add_pair"]).arities(&[1]);
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+        fn not_in_dylib() -> u32;
+    }
+}
+
+/// Whether this is the copy of the test binary which is expected to
+/// abort, rather than the test checking that it does.
+fn is_child() -> bool {
+    std::env::var_os("DYMOD_STRICT_CHILD").is_some()
+}
+
+/// Runs just `test` in a copy of this test binary, returning what it
+/// printed to stderr once it has aborted.
+fn run_aborting(test: &str) -> String {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--nocapture", "--test-threads", "1"])
+        .env("DYMOD_STRICT_CHILD", "1")
+        .output()
+        .expect("Failed to run test binary.");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(!output.status.success(), "Expected abort, got:\n{}", stderr);
+    stderr
+}
+
+#[test]
+fn mismatched_arity_aborts() {
+    if is_child() {
+        let _ = MISDECLARED.force_reload();
+        return;
+    }
+
+    let stderr = run_aborting("mismatched_arity_aborts");
+    assert!(stderr.contains("declared with 1 arguments, but takes 2"));
+    assert!(stderr.contains("the `strict` feature is enabled"));
+}
+
+#[test]
+fn missing_function_aborts() {
+    if is_child() {
+        assert_eq!(plugin::stored_value(), 0);
+        plugin::not_in_dylib();
+        return;
+    }
+
+    let stderr = run_aborting("missing_function_aborts");
+    assert!(stderr.contains("`not_in_dylib` couldn't be found in the dylib"));
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "force-stub")]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../subcrate/src/lib.rs"]
+    pub mod subcrate {
+        #[stub = "Stubbed"]
+        fn count_sheep(sheep: u32) -> &'static str;
+        fn count_goats(goats: u32) -> &'static str;
+    }
+}
+
+#[test]
+fn stubbed_function_returns_provided_value() {
+    subcrate::reload();
+
+    assert_eq!(subcrate::count_sheep(0), "Stubbed");
+    assert_eq!(subcrate::count_sheep(3), "Stubbed");
+}
+
+#[test]
+#[should_panic(expected = "stubbed out")]
+fn stubbed_function_without_value_is_unimplemented() {
+    subcrate::count_goats(3);
+}
+
+#[test]
+fn stubbed_module_is_never_loaded() {
+    subcrate::reload();
+
+    let status = subcrate::status();
+    assert!(!status.loaded);
+    assert_eq!(status.version, 0);
+
+    dymod::reload_all().expect("Stubbed modules can't fail to reload.");
+}
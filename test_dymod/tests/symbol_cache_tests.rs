@@ -0,0 +1,65 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn store_value(value: u32);
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn reload_invalidates_cached_symbols_on_every_thread() {
+    plugin::force_reload().expect("Failed initial load.");
+
+    // Hammer the functions from several threads while reloading
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    plugin::store_value(1);
+                    assert!(plugin::stored_value() <= 1);
+                }
+            });
+        }
+
+        for _ in 0..20 {
+            plugin::force_reload().expect("Failed to reload.");
+        }
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    // A thread which cached `stored_value` before a reload must call the
+    // new dylib's version after it, which hasn't had a value stored
+    let (request, requests) = mpsc::channel::<()>();
+    let (reply, replies) = mpsc::channel();
+    let caller = std::thread::spawn(move || {
+        for () in requests {
+            reply.send(plugin::stored_value()).unwrap();
+        }
+    });
+
+    plugin::store_value(5);
+    request.send(()).unwrap();
+    assert_eq!(replies.recv().unwrap(), 5);
+
+    plugin::force_reload().expect("Failed to reload.");
+    request.send(()).unwrap();
+    assert_eq!(replies.recv().unwrap(), 0);
+
+    drop(request);
+    caller.join().unwrap();
+}
@@ -0,0 +1,39 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn prefixed_answer() -> u32;
+    }
+}
+
+#[test]
+fn symbol_is_found_with_leading_underscore() {
+    plugin::check().expect("prefixed_answer should be found as _prefixed_answer.");
+    assert_eq!(plugin::prefixed_answer(), 42);
+    assert_eq!(plugin::no_reload::prefixed_answer(), 42);
+}
+
+mod eager {
+    dymod::dymod! {
+        #[path = "../plugin/src/lib.rs"]
+        pub mod plugin {
+            fn prefixed_answer() -> u32;
+        }
+    }
+}
+
+#[test]
+fn symbol_is_found_with_leading_underscore_eagerly() {
+    eager::plugin::set_symbol_resolution(dymod::SymbolResolution::Eager);
+    assert_eq!(eager::plugin::prefixed_answer(), 42);
+}
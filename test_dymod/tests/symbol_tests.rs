@@ -0,0 +1,28 @@
+#![cfg(not(feature = "force-stub"))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        /// Reads the first byte of a `u8`
+        #[symbol = "first_byte"]
+        fn first_byte_of_u8(value: *const u8) -> u8;
+
+        #[symbol = "first_byte"]
+        fn first_byte_of_u32(value: *const u32) -> u8;
+    }
+}
+
+#[test]
+fn two_declarations_share_one_symbol() {
+    let byte = 7u8;
+    let word = u32::from_ne_bytes([9, 0, 0, 0]);
+
+    assert_eq!(plugin::first_byte_of_u8(&byte), 7);
+    assert_eq!(plugin::first_byte_of_u32(&word), 9);
+    assert_eq!(
+        plugin::no_reload::first_byte_of_u32(&word),
+        plugin::first_byte_of_u32(&word)
+    );
+}
@@ -0,0 +1,37 @@
+#![cfg(all(
+    target_os = "linux",
+    target_env = "gnu",
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        #[symbol_version = "PLUGIN_1"]
+        fn versioned_answer() -> u32;
+    }
+}
+
+#[test]
+fn symbol_version_selects_older_symbol() {
+    assert_eq!(plugin::versioned_answer(), 1);
+    assert_eq!(plugin::no_reload::versioned_answer(), 1);
+
+    // The default version is still found by name
+    plugin::check().expect("Failed to check dylib.");
+    let answer = unsafe {
+        plugin::with_library(|lib| {
+            let answer: dymod::Symbol<extern "C" fn() -> u32> =
+                lib.get(b"versioned_answer").unwrap();
+            answer()
+        })
+    };
+    assert_eq!(answer, 2);
+}
@@ -0,0 +1,64 @@
+//! Calls a module from many threads while another reloads it. To check
+//! for data races too, run it under ThreadSanitizer with nightly:
+//!
+//! ```sh
+//! RUSTFLAGS=-Zsanitizer=thread cargo +nightly test -Zbuild-std \
+//!     --target x86_64-unknown-linux-gnu --test thread_tests
+//! ```
+
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn add_pair(a: u32, b: u32) -> u32;
+    }
+}
+
+const CALLERS: usize = 8;
+const RELOADS: usize = 50;
+
+#[test]
+fn calls_on_many_threads_survive_reloads_on_another() {
+    let stop = AtomicBool::new(false);
+    let calls = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        for caller in 0..CALLERS {
+            let (stop, calls) = (&stop, &calls);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    // Nothing returned can borrow from the dylib, as it
+                    // may be unloaded as soon as the call returns
+                    let caller = caller as u32;
+                    assert_eq!(plugin::add_pair(caller, 1), caller + 1);
+                    calls.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+
+        let reloader = scope.spawn(|| {
+            for _ in 0..RELOADS {
+                plugin::force_reload().expect("Failed to reload.");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        });
+        reloader.join().unwrap();
+        stop.store(true, Ordering::Relaxed);
+    });
+
+    assert!(calls.load(Ordering::Relaxed) > 0);
+    assert!(plugin::status().version >= RELOADS);
+}
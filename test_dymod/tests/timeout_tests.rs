@@ -0,0 +1,31 @@
+#![cfg(not(feature = "force-stub"))]
+
+use std::time::Duration;
+
+use dymod::{call_with_timeout, CallError};
+
+dymod::dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn checked_divide(a: u32, b: u32) -> u32;
+        fn hang(value: u32) -> u32;
+    }
+}
+
+#[test]
+fn call_which_returns_in_time_gives_its_result() {
+    let result = call_with_timeout(|| plugin::checked_divide(12, 4), Duration::from_secs(60));
+    assert_eq!(result, Ok(3));
+}
+
+#[test]
+fn call_which_hangs_times_out() {
+    let result = call_with_timeout(|| plugin::hang(1), Duration::from_millis(100));
+    assert_eq!(result, Err(CallError::Timeout));
+}
+
+#[test]
+#[should_panic(expected = "panicked on its thread")]
+fn call_which_panics_panics_the_caller() {
+    let _ = call_with_timeout(|| panic!("panicked on its thread"), Duration::from_secs(60));
+}
@@ -0,0 +1,58 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::fs::File;
+use std::time::{Duration, SystemTime};
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod trigger file tests/{prefix}{name}{suffix}"]
+    #[trigger = "plugin.trigger"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn only_touching_trigger_file_reloads() {
+    common::copy_dylib(plugin::dylib_path());
+    let trigger = plugin::dylib_path().with_file_name("plugin.trigger");
+    let _ = std::fs::remove_file(&trigger);
+
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    // A change to the dylib itself is ignored
+    common::change_dylib(plugin::dylib_path(), 1);
+    assert!(!plugin::pending_reload());
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    // Creating the trigger file is, even though it didn't exist when the
+    // dylib was loaded
+    File::create(&trigger).expect("Failed to create trigger file.");
+    assert!(plugin::pending_reload());
+    assert!(plugin::reload_if_changed().expect("Failed to reload dylib."));
+    assert_eq!(plugin::status().version, 2);
+    assert!(!plugin::pending_reload());
+
+    // As is touching it again, once there's a new dylib
+    common::change_dylib(plugin::dylib_path(), 2);
+    assert!(!plugin::pending_reload());
+    File::options()
+        .write(true)
+        .open(&trigger)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(3)))
+        .expect("Failed to touch trigger file.");
+    assert!(plugin::pending_reload());
+}
@@ -0,0 +1,32 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn trigger_reload_reloads_on_next_call() {
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    dymod::trigger_reload();
+    assert_eq!(plugin::status().version, 1);
+
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 2);
+
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 2);
+}
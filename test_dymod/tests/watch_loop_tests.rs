@@ -0,0 +1,52 @@
+#![cfg(all(
+    not(feature = "force-stub"),
+    any(
+        feature = "force-dynamic",
+        feature = "auto-reload",
+        all(not(feature = "force-static"), debug_assertions,)
+    )
+))]
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use dymod::dymod;
+
+mod common;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    #[dylib = "{manifest}/target/dymod watch loop tests/{prefix}{name}{suffix}"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+#[test]
+fn watch_loop_reloads_changes_until_stopped() {
+    common::copy_dylib(plugin::dylib_path());
+    let stop = AtomicBool::new(false);
+    let reloads = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            plugin::run_watch_loop(Duration::from_millis(10), &stop, || {
+                reloads.fetch_add(1, Ordering::SeqCst);
+            })
+        });
+
+        // The first load counts as a reload
+        while reloads.load(Ordering::SeqCst) < 1 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        common::change_dylib(plugin::dylib_path(), 1);
+
+        while reloads.load(Ordering::SeqCst) < 2 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        stop.store(true, Ordering::SeqCst);
+    });
+
+    assert_eq!(plugin::status().version, 2);
+}
@@ -0,0 +1,44 @@
+#![cfg(all(feature = "watch", not(feature = "force-stub")))]
+
+use std::fs::File;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+use dymod::dymod;
+
+dymod! {
+    #[path = "../plugin/src/lib.rs"]
+    pub mod plugin {
+        fn stored_value() -> u32;
+    }
+}
+
+fn touch_plugin(offset: Duration) {
+    let path = dymod::dylib_path(env!("CARGO_MANIFEST_DIR"), "plugin");
+    File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now() + offset))
+        .expect("Failed to touch plugin dylib.");
+}
+
+#[test]
+fn watcher_coalesces_changes_into_one_reload() {
+    plugin::set_reload_debounce(Duration::from_millis(300));
+    plugin::stored_value();
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    touch_plugin(Duration::from_secs(1));
+    sleep(Duration::from_millis(100));
+    touch_plugin(Duration::from_secs(2));
+    sleep(Duration::from_millis(100));
+
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 1);
+
+    sleep(Duration::from_millis(600));
+    plugin::stored_value();
+    plugin::stored_value();
+    assert_eq!(plugin::status().version, 2);
+}